@@ -1 +1,60 @@
 pub mod types;
+
+use alloy_primitives::Address;
+use anyhow::anyhow;
+
+use crate::state::tree::verify_state_proof;
+use crate::state::{claim_state_key, claim_state_value, object_state_key, object_state_value};
+use types::ProofCertificate;
+
+// checks a proof certificate offline: the block certificate
+// must be signed by the given signer, the certificate must
+// not have expired at the given time, and the proofs must
+// hold under the signed state root
+pub fn verify_proof_certificate(
+    certificate: &ProofCertificate,
+    signer: Address,
+    now: u64,
+) -> anyhow::Result<()> {
+    if certificate.block_certificate.recover_signer()? != signer {
+        return Err(anyhow!(
+            "block certificate is not signed by the expected signer"
+        ));
+    }
+
+    if now > certificate.expires_at() {
+        return Err(anyhow!("certificate has expired"));
+    }
+
+    if !verify_proof_certificate_state(certificate) {
+        return Err(anyhow!("certificate proofs do not match the state root"));
+    }
+
+    Ok(())
+}
+
+// whether the object, with the certificate's lifespan, and
+// the claim are in the state signed by the block certificate
+pub fn verify_proof_certificate_state(certificate: &ProofCertificate) -> bool {
+    let state_root = &certificate.block_certificate.state_root;
+
+    let object_value = object_state_value(
+        &certificate.transaction_hash,
+        certificate.output_index,
+        certificate.lifespan,
+    );
+
+    let claim_value = claim_state_value(&certificate.claim);
+
+    verify_state_proof(
+        state_root,
+        &object_state_key(&certificate.object_id),
+        Some(&object_value),
+        &certificate.object_proof,
+    ) && verify_state_proof(
+        state_root,
+        &claim_state_key(&certificate.object_id, &certificate.claim),
+        Some(&claim_value),
+        &certificate.claim_proof,
+    )
+}
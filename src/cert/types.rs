@@ -1,10 +1,14 @@
-use alloy_primitives::{FixedBytes, B256};
+use alloy_primitives::{Address, FixedBytes, B256};
 use anyhow::anyhow;
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, DisplayFromStr};
 use sha3::{Digest, Keccak256};
 
-use crate::{quible_ecdsa_utils::sign_message, tx::types::Hashable};
+use crate::{
+    quible_ecdsa_utils::{recover_signer_unchecked, sign_message},
+    state::tree::StateProof,
+    tx::types::Hashable,
+};
 
 pub trait Signable {
     fn sign(&self, secret: [u8; 32]) -> anyhow::Result<QuibleSignature>;
@@ -51,7 +55,7 @@ impl<'de> Deserialize<'de> for QuibleSignature {
     where
         D: serde::Deserializer<'de>,
     {
-        let s: &str = Deserialize::deserialize(deserializer)?;
+        let s: String = Deserialize::deserialize(deserializer)?;
 
         let byte_vec: Vec<u8> = (0..s.len())
             .step_by(2)
@@ -67,22 +71,111 @@ impl<'de> Deserialize<'de> for QuibleSignature {
     }
 }
 
+// what a signed certificate attests to about an object's
+// claim set. Membership details keep their original encoding,
+// and non-membership details name the claim `excluded_claim`
+// so that the two cannot be confused.
 #[serde_as]
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CertificateSigningRequestDetails {
-    pub object_id: [u8; 32],
-    pub claim: Vec<u8>,
+#[serde(untagged)]
+pub enum CertificateSigningRequestDetails {
+    Membership {
+        object_id: [u8; 32],
+        claim: Vec<u8>,
 
-    #[serde_as(as = "DisplayFromStr")]
-    pub expires_at: u64,
+        // the block whose state the certificate attests to
+        #[serde_as(as = "DisplayFromStr")]
+        block_height: u64,
+        block_hash: [u8; 32],
+
+        #[serde_as(as = "DisplayFromStr")]
+        expires_at: u64,
+    },
+
+    // the claim is not in the object's claim set as of the
+    // block
+    NonMembership {
+        object_id: [u8; 32],
+        excluded_claim: Vec<u8>,
+
+        #[serde_as(as = "DisplayFromStr")]
+        block_height: u64,
+        block_hash: [u8; 32],
+
+        #[serde_as(as = "DisplayFromStr")]
+        expires_at: u64,
+    },
+}
+
+impl CertificateSigningRequestDetails {
+    pub fn object_id(&self) -> [u8; 32] {
+        match self {
+            CertificateSigningRequestDetails::Membership { object_id, .. }
+            | CertificateSigningRequestDetails::NonMembership { object_id, .. } => *object_id,
+        }
+    }
+
+    pub fn block_height(&self) -> u64 {
+        match self {
+            CertificateSigningRequestDetails::Membership { block_height, .. }
+            | CertificateSigningRequestDetails::NonMembership { block_height, .. } => *block_height,
+        }
+    }
+
+    pub fn block_hash(&self) -> [u8; 32] {
+        match self {
+            CertificateSigningRequestDetails::Membership { block_hash, .. }
+            | CertificateSigningRequestDetails::NonMembership { block_hash, .. } => *block_hash,
+        }
+    }
+
+    pub fn expires_at(&self) -> u64 {
+        match self {
+            CertificateSigningRequestDetails::Membership { expires_at, .. }
+            | CertificateSigningRequestDetails::NonMembership { expires_at, .. } => *expires_at,
+        }
+    }
 }
 
 impl Hashable for CertificateSigningRequestDetails {
     fn hash(&self) -> anyhow::Result<[u8; 32]> {
         let mut hasher = Keccak256::new();
-        hasher.update(self.object_id);
-        hasher.update(&self.claim);
-        hasher.update(bytemuck::cast::<u64, [u8; 8]>(self.expires_at));
+
+        let (object_id, claim, block_height, block_hash, expires_at) = match self {
+            CertificateSigningRequestDetails::Membership {
+                object_id,
+                claim,
+                block_height,
+                block_hash,
+                expires_at,
+            } => (object_id, claim, block_height, block_hash, expires_at),
+
+            // prefixed so that a non-membership signature can
+            // never pass for a membership one over the same
+            // fields
+            CertificateSigningRequestDetails::NonMembership {
+                object_id,
+                excluded_claim,
+                block_height,
+                block_hash,
+                expires_at,
+            } => {
+                hasher.update(Keccak256::digest(b"NonMembership"));
+                (
+                    object_id,
+                    excluded_claim,
+                    block_height,
+                    block_hash,
+                    expires_at,
+                )
+            }
+        };
+
+        hasher.update(object_id);
+        hasher.update(claim);
+        hasher.update(bytemuck::cast::<u64, [u8; 8]>(*block_height));
+        hasher.update(block_hash);
+        hasher.update(bytemuck::cast::<u64, [u8; 8]>(*expires_at));
         let hash_vec = hasher.finalize();
         hash_vec
             .as_slice()
@@ -113,3 +206,99 @@ pub struct SignedCertificate {
     pub details: CertificateSigningRequestDetails,
     pub signature: QuibleSignature,
 }
+
+// signature of a block's state by the node, made once per
+// block so that certificates can be issued against it without
+// signing every request
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockCertificate {
+    pub block_hash: [u8; 32],
+    pub state_root: [u8; 32],
+
+    #[serde_as(as = "DisplayFromStr")]
+    pub timestamp: u64,
+    pub signature: QuibleSignature,
+}
+
+impl BlockCertificate {
+    // the signature is over keccak256(block_hash ‖ state_root ‖
+    // timestamp), with the timestamp as 8 big-endian bytes
+    pub fn signing_hash(block_hash: &[u8; 32], state_root: &[u8; 32], timestamp: u64) -> [u8; 32] {
+        let mut hasher = Keccak256::new();
+        hasher.update(block_hash);
+        hasher.update(state_root);
+        hasher.update(timestamp.to_be_bytes());
+        hasher.finalize().into()
+    }
+
+    pub fn sign(
+        block_hash: [u8; 32],
+        state_root: [u8; 32],
+        timestamp: u64,
+        secret: [u8; 32],
+    ) -> anyhow::Result<Self> {
+        let hash = BlockCertificate::signing_hash(&block_hash, &state_root, timestamp);
+
+        let signature_bytes = sign_message(B256::from_slice(&secret), FixedBytes::new(hash))
+            .map_err(|err| anyhow!(err))?;
+
+        Ok(BlockCertificate {
+            block_hash,
+            state_root,
+            timestamp,
+            signature: QuibleSignature {
+                raw: signature_bytes,
+            },
+        })
+    }
+
+    pub fn recover_signer(&self) -> anyhow::Result<Address> {
+        let hash =
+            BlockCertificate::signing_hash(&self.block_hash, &self.state_root, self.timestamp);
+
+        recover_signer_unchecked(&self.signature.raw, &hash).map_err(|err| anyhow!(err))
+    }
+}
+
+// certificate any node can issue from a block certificate,
+// without the signer key: the object and claim are proven
+// to be in the state the block certificate signs
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProofCertificate {
+    pub block_certificate: BlockCertificate,
+    pub object_id: [u8; 32],
+    pub claim: Vec<u8>,
+
+    // seconds after the block timestamp that the certificate
+    // expires, as set by the object's cert TTL
+    #[serde_as(as = "DisplayFromStr")]
+    pub lifespan: u64,
+
+    // the object's state leaf commits to the lifespan along
+    // with the output holding the object
+    pub transaction_hash: [u8; 32],
+    #[serde_as(as = "DisplayFromStr")]
+    pub output_index: u64,
+
+    pub object_proof: StateProof,
+    pub claim_proof: StateProof,
+}
+
+impl ProofCertificate {
+    pub fn expires_at(&self) -> u64 {
+        self.block_certificate
+            .timestamp
+            .saturating_add(self.lifespan)
+    }
+}
+
+// signed certificates keep their original encoding, so that
+// clients not asking for proofs see no change
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Certificate {
+    Signed(SignedCertificate),
+    Proof(Box<ProofCertificate>),
+}
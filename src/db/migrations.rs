@@ -0,0 +1,510 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use surrealdb::engine::any::Any as AnyDb;
+use surrealdb::Surreal;
+
+use super::types::{
+    object_claim_id, object_claim_key, BlockRow, ObjectClaimRow, SurrealID, TransactionOutputRow,
+};
+use crate::tx::script::extract_pubkey_script_owners;
+use crate::tx::types::TransactionOutput;
+
+// brings rows written by earlier versions of the node up to the
+// current schema. SurrealDB keeps the fields a schema no longer
+// defines on existing rows, and rejects any update to a row that
+// lacks a field the schema now requires, so each legacy row is
+// patched once with every field it is missing.
+pub async fn migrate_legacy_rows(db: &Surreal<AnyDb>) -> anyhow::Result<()> {
+    migrate_legacy_transaction_outputs(db).await?;
+    migrate_legacy_object_claims(db).await?;
+    migrate_legacy_objects(db).await?;
+
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+struct LegacyTransactionOutputRow {
+    id: SurrealID,
+    transaction_hash: String,
+    output_index: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct TransactionOutputPatch {
+    block_height: u64,
+    coinbase: bool,
+    owners: Vec<String>,
+
+    #[serde(flatten)]
+    spend: Option<TransactionOutputSpend>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct TransactionOutputSpend {
+    spent: bool,
+    spending_transaction_hash: String,
+    spent_at_height: u64,
+}
+
+async fn migrate_legacy_transaction_outputs(db: &Surreal<AnyDb>) -> anyhow::Result<()> {
+    let legacy_rows: Vec<LegacyTransactionOutputRow> = db
+        .query(
+            "SELECT id, transaction_hash, output_index FROM transaction_outputs \
+             WHERE block_height = NONE OR coinbase = NONE OR owners = NONE",
+        )
+        .await?
+        .take(0)?;
+
+    if legacy_rows.is_empty() {
+        return Ok(());
+    }
+
+    // every output was created by a transaction included in a
+    // block, which says everything the row is missing. Earlier
+    // versions never marked outputs spent either, so the spends
+    // are recovered from the blocks too.
+    let block_rows: Vec<BlockRow> = db.select("blocks").await?;
+
+    let mut patches = HashMap::<(String, u64), TransactionOutputPatch>::new();
+    let mut spends = HashMap::<(String, u64), TransactionOutputSpend>::new();
+    for block_row in block_rows {
+        // the coinbase has always come first in a block
+        for (transaction_index, (transaction_hash, transaction)) in
+            block_row.transactions.iter().enumerate()
+        {
+            if transaction_index > 0 {
+                for input in transaction.inputs() {
+                    spends.insert(
+                        (hex::encode(input.outpoint.txid), input.outpoint.index),
+                        TransactionOutputSpend {
+                            spent: true,
+                            spending_transaction_hash: hex::encode(transaction_hash),
+                            spent_at_height: block_row.height,
+                        },
+                    );
+                }
+            }
+
+            for (output_index, output) in transaction.outputs().iter().enumerate() {
+                let (TransactionOutput::Value { pubkey_script, .. }
+                | TransactionOutput::Object { pubkey_script, .. }) = output;
+
+                patches.insert(
+                    (hex::encode(transaction_hash), output_index.try_into()?),
+                    TransactionOutputPatch {
+                        block_height: block_row.height,
+                        coinbase: transaction_index == 0,
+                        owners: extract_pubkey_script_owners(pubkey_script),
+                        spend: None,
+                    },
+                );
+            }
+        }
+    }
+
+    for legacy_row in legacy_rows {
+        let outpoint = (legacy_row.transaction_hash, legacy_row.output_index);
+        let Some(mut patch) = patches.remove(&outpoint) else {
+            println!(
+                "could not migrate transaction output {}: no block includes it",
+                legacy_row.id.0
+            );
+            continue;
+        };
+        patch.spend = spends.remove(&outpoint);
+
+        db.query("UPDATE $id MERGE $patch")
+            .bind(("id", legacy_row.id))
+            .bind(("patch", patch))
+            .await?
+            .check()?;
+    }
+
+    println!("migrated legacy transaction outputs");
+
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+struct LegacyObjectRow {
+    id: SurrealID,
+    object_id: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ObjectPatch {
+    transaction_hash: String,
+    output_index: u64,
+    owners: Vec<String>,
+}
+
+// objects used to be stored without the output holding them,
+// which is their latest unspent object output
+async fn migrate_legacy_objects(db: &Surreal<AnyDb>) -> anyhow::Result<()> {
+    let legacy_rows: Vec<LegacyObjectRow> = db
+        .query(
+            "SELECT id, object_id FROM objects \
+             WHERE transaction_hash = NONE OR output_index = NONE OR owners = NONE",
+        )
+        .await?
+        .take(0)?;
+
+    if legacy_rows.is_empty() {
+        return Ok(());
+    }
+
+    let output_rows: Vec<TransactionOutputRow> = db
+        .query("SELECT * FROM transaction_outputs WHERE output_type = \"Object\" AND spent = false")
+        .await?
+        .take(0)?;
+
+    let mut holding_rows = HashMap::<String, TransactionOutputRow>::new();
+    for output_row in output_rows {
+        let TransactionOutput::Object { object_id, .. } = &output_row.output else {
+            continue;
+        };
+
+        let object_id_hex = hex::encode(object_id.raw);
+        if holding_rows
+            .get(&object_id_hex)
+            .is_some_and(|holding_row| holding_row.block_height > output_row.block_height)
+        {
+            continue;
+        }
+
+        holding_rows.insert(object_id_hex, output_row);
+    }
+
+    for legacy_row in legacy_rows {
+        let Some(holding_row) = holding_rows.get(&legacy_row.object_id) else {
+            println!(
+                "could not migrate object {}: no unspent output holds it",
+                legacy_row.object_id
+            );
+            continue;
+        };
+
+        db.query("UPDATE $id MERGE $patch")
+            .bind(("id", legacy_row.id))
+            .bind((
+                "patch",
+                ObjectPatch {
+                    transaction_hash: holding_row.transaction_hash.clone(),
+                    output_index: holding_row.output_index,
+                    owners: holding_row.owners.clone(),
+                },
+            ))
+            .await?
+            .check()?;
+    }
+
+    println!("migrated legacy objects");
+
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+struct LegacyObjectClaimsRow {
+    object_id: String,
+    claims: Vec<Vec<u8>>,
+}
+
+// claims used to be stored as an array on the object. They are
+// copied to object_claims before anything else updates the object
+// row, since an update to a schemafull row drops the fields the
+// schema no longer defines.
+async fn migrate_legacy_object_claims(db: &Surreal<AnyDb>) -> anyhow::Result<()> {
+    let legacy_rows: Vec<LegacyObjectClaimsRow> = db
+        .query("SELECT object_id, claims FROM objects WHERE claims != NONE")
+        .await?
+        .take(0)?;
+
+    if legacy_rows.is_empty() {
+        return Ok(());
+    }
+
+    for legacy_row in &legacy_rows {
+        for claim in &legacy_row.claims {
+            let claim_hex = hex::encode(claim);
+            let _: Option<ObjectClaimRow> = db
+                .update((
+                    "object_claims",
+                    object_claim_key(&legacy_row.object_id, &claim_hex),
+                ))
+                .content(ObjectClaimRow {
+                    id: object_claim_id(&legacy_row.object_id, &claim_hex),
+                    object_id: legacy_row.object_id.clone(),
+                    claim: claim_hex,
+                })
+                .await?;
+        }
+    }
+
+    // rows still missing required fields can't be updated here;
+    // migrating them drops the array instead
+    db.query(
+        "UPDATE objects UNSET claims WHERE claims != NONE \
+         AND transaction_hash != NONE AND output_index != NONE AND owners != NONE",
+    )
+    .await?
+    .check()?;
+
+    println!("migrated legacy object claims");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use surrealdb::engine::any;
+    use surrealdb::sql::Thing;
+
+    use super::migrate_legacy_rows;
+    use crate::db::schema::initialize_db;
+    use crate::db::types::{BlockRow, ObjectRow, SurrealID, TransactionOutputRow};
+    use crate::tx::types::{
+        BlockHeader, Hashable, ObjectIdentifier, ObjectMode, Transaction, TransactionInput,
+        TransactionOpCode, TransactionOutpoint, TransactionOutput,
+    };
+
+    #[tokio::test]
+    async fn migrates_rows_written_by_earlier_versions() -> anyhow::Result<()> {
+        let db = any::connect("memory").await?;
+        db.use_ns("quible").use_db("quible_node").await?;
+
+        let owner_address = [7u8; 20];
+        let owner_pubkey_script = vec![
+            TransactionOpCode::Dup,
+            TransactionOpCode::Push {
+                data: owner_address.to_vec(),
+            },
+            TransactionOpCode::EqualVerify,
+            TransactionOpCode::CheckEip191SigVerify,
+        ];
+
+        let coinbase_transaction = Transaction::Version1 {
+            inputs: vec![TransactionInput {
+                outpoint: TransactionOutpoint {
+                    txid: [0u8; 32],
+                    index: 0,
+                },
+                signature_script: vec![],
+            }],
+            outputs: vec![TransactionOutput::Value {
+                value: 5,
+                pubkey_script: owner_pubkey_script.clone(),
+            }],
+            locktime: 0,
+        };
+        let coinbase_transaction_hash = coinbase_transaction.hash_eip191()?;
+
+        let object_id = [9u8; 32];
+        let object_transaction = Transaction::Version1 {
+            inputs: vec![],
+            outputs: vec![TransactionOutput::Object {
+                object_id: ObjectIdentifier {
+                    raw: object_id,
+                    mode: ObjectMode::Fresh,
+                },
+                data_script: vec![TransactionOpCode::Insert { data: vec![1] }],
+                pubkey_script: owner_pubkey_script.clone(),
+            }],
+            locktime: 0,
+        };
+        let object_transaction_hash = object_transaction.hash_eip191()?;
+
+        // spends the first block's coinbase output in a later
+        // block, which earlier versions left marked unspent
+        let spending_transaction = Transaction::Version1 {
+            inputs: vec![TransactionInput {
+                outpoint: TransactionOutpoint {
+                    txid: coinbase_transaction_hash,
+                    index: 0,
+                },
+                signature_script: vec![],
+            }],
+            outputs: vec![TransactionOutput::Value {
+                value: 5,
+                pubkey_script: owner_pubkey_script.clone(),
+            }],
+            locktime: 0,
+        };
+        let spending_transaction_hash = spending_transaction.hash_eip191()?;
+
+        let later_coinbase_transaction = Transaction::Version1 {
+            inputs: vec![TransactionInput {
+                outpoint: TransactionOutpoint {
+                    txid: [0u8; 32],
+                    index: 0,
+                },
+                signature_script: vec![TransactionOpCode::Push { data: vec![4] }],
+            }],
+            outputs: vec![TransactionOutput::Value {
+                value: 5,
+                pubkey_script: owner_pubkey_script.clone(),
+            }],
+            locktime: 0,
+        };
+        let later_coinbase_transaction_hash = later_coinbase_transaction.hash_eip191()?;
+
+        // rows as the node wrote them before the schema changed
+        let _: Vec<BlockRow> = db
+            .create("blocks")
+            .content(BlockRow {
+                id: SurrealID(Thing::from(("blocks".to_string(), "legacy".to_string()))),
+                hash: "legacy".to_string(),
+                header: BlockHeader::Version1 {
+                    previous_block_header_hash: [0u8; 32],
+                    merkle_root: [0u8; 32],
+                    timestamp: 0,
+                },
+                height: 3,
+                transactions: vec![
+                    (coinbase_transaction_hash, coinbase_transaction.clone()),
+                    (object_transaction_hash, object_transaction.clone()),
+                ],
+            })
+            .await?;
+
+        let _: Vec<BlockRow> = db
+            .create("blocks")
+            .content(BlockRow {
+                id: SurrealID(Thing::from(("blocks".to_string(), "later".to_string()))),
+                hash: "later".to_string(),
+                header: BlockHeader::Version1 {
+                    previous_block_header_hash: [0u8; 32],
+                    merkle_root: [0u8; 32],
+                    timestamp: 0,
+                },
+                height: 4,
+                transactions: vec![
+                    (
+                        later_coinbase_transaction_hash,
+                        later_coinbase_transaction.clone(),
+                    ),
+                    (spending_transaction_hash, spending_transaction.clone()),
+                ],
+            })
+            .await?;
+
+        for (transaction_hash, transaction, output_type) in [
+            (coinbase_transaction_hash, &coinbase_transaction, "Value"),
+            (object_transaction_hash, &object_transaction, "Object"),
+            (
+                later_coinbase_transaction_hash,
+                &later_coinbase_transaction,
+                "Value",
+            ),
+            (spending_transaction_hash, &spending_transaction, "Value"),
+        ] {
+            db.query("CREATE $id CONTENT $row")
+                .bind((
+                    "id",
+                    SurrealID(Thing::from((
+                        "transaction_outputs".to_string(),
+                        format!("{}:0", hex::encode(transaction_hash)),
+                    ))),
+                ))
+                .bind((
+                    "row",
+                    serde_json::json!({
+                        "transaction_hash": hex::encode(transaction_hash),
+                        "output_index": 0,
+                        "output": transaction.outputs()[0],
+                        "output_type": output_type,
+                        "owner": hex::encode(owner_address),
+                        "spent": false,
+                    }),
+                ))
+                .await?
+                .check()?;
+        }
+
+        db.query("CREATE $id CONTENT $row")
+            .bind((
+                "id",
+                SurrealID(Thing::from(("objects".to_string(), hex::encode(object_id)))),
+            ))
+            .bind((
+                "row",
+                serde_json::json!({
+                    "object_id": hex::encode(object_id),
+                    "cert_ttl": 86400,
+                    "claims": [[1]],
+                }),
+            ))
+            .await?
+            .check()?;
+
+        initialize_db(&db).await?;
+        migrate_legacy_rows(&db).await?;
+
+        // migrating again finds nothing left to do
+        migrate_legacy_rows(&db).await?;
+
+        let db_arc = Arc::new(db);
+
+        for (transaction_hash, block_height, coinbase, spending_transaction) in [
+            (
+                coinbase_transaction_hash,
+                3,
+                true,
+                Some(spending_transaction_hash),
+            ),
+            (object_transaction_hash, 3, false, None),
+            (later_coinbase_transaction_hash, 4, true, None),
+            (spending_transaction_hash, 4, false, None),
+        ] {
+            let output_row: TransactionOutputRow = db_arc
+                .select((
+                    "transaction_outputs",
+                    format!("{}:0", hex::encode(transaction_hash)),
+                ))
+                .await?
+                .ok_or(anyhow::anyhow!("missing transaction output"))?;
+
+            assert_eq!(output_row.block_height, block_height);
+            assert_eq!(output_row.coinbase, coinbase);
+            assert_eq!(output_row.owners, vec![hex::encode(owner_address)]);
+            assert_eq!(output_row.spent, spending_transaction.is_some());
+            assert_eq!(
+                output_row.spending_transaction_hash,
+                spending_transaction.map(hex::encode)
+            );
+            assert_eq!(output_row.spent_at_height, spending_transaction.map(|_| 4));
+        }
+
+        let object_row: ObjectRow = db_arc
+            .select(("objects", hex::encode(object_id)))
+            .await?
+            .ok_or(anyhow::anyhow!("missing object"))?;
+
+        assert_eq!(
+            object_row.transaction_hash,
+            hex::encode(object_transaction_hash)
+        );
+        assert_eq!(object_row.output_index, 0);
+        assert_eq!(object_row.owners, vec![hex::encode(owner_address)]);
+
+        let claims: Vec<String> = db_arc
+            .query("SELECT VALUE claim FROM object_claims WHERE object_id = $object_id")
+            .bind(("object_id", hex::encode(object_id)))
+            .await?
+            .take(0)?;
+        assert_eq!(claims, vec![hex::encode([1u8])]);
+        let remaining_claims: Option<Vec<Vec<u8>>> = db_arc
+            .query("SELECT VALUE claims FROM objects WHERE id = $id")
+            .bind((
+                "id",
+                SurrealID(Thing::from(("objects".to_string(), hex::encode(object_id)))),
+            ))
+            .await?
+            .take(0)?;
+        assert_eq!(remaining_claims, None);
+
+        Ok(())
+    }
+}
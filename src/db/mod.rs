@@ -1,2 +1,3 @@
+pub mod migrations;
 pub mod schema;
 pub mod types;
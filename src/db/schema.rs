@@ -43,6 +43,15 @@ pub async fn initialize_db(db: &Surreal<AnyDb>) -> surrealdb::Result<()> {
     db.query("DEFINE FIELD size ON pending_transactions TYPE int;")
         .await?;
 
+    // a pending transaction claims the outpoints it spends, so
+    // that two conflicting transactions are never both pending
+    db.query("DEFINE FIELD outpoints ON pending_transactions TYPE array<string> DEFAULT [];")
+        .await?;
+    db.query(
+        "DEFINE INDEX pending_transactions_outpoints ON pending_transactions FIELDS outpoints UNIQUE;",
+    )
+    .await?;
+
     // TODO: define the event type more thoroughly here to avoid the use of FLEXIBLE
     // db.query("DEFINE FIELD data.data.inputs.* ON pending_transactions FLEXIBLE TYPE object;").await?;
 
@@ -82,18 +91,100 @@ pub async fn initialize_db(db: &Surreal<AnyDb>) -> surrealdb::Result<()> {
         .await?;
     db.query("DEFINE FIELD output_type ON transaction_outputs TYPE string;")
         .await?;
-    db.query("DEFINE FIELD owner ON transaction_outputs TYPE string;")
+    db.query("DEFINE FIELD owners ON transaction_outputs TYPE array<string>;")
         .await?;
     db.query("DEFINE FIELD spent ON transaction_outputs TYPE bool;")
         .await?;
+    db.query("DEFINE FIELD spending_transaction_hash ON transaction_outputs TYPE option<string>;")
+        .await?;
+    db.query("DEFINE FIELD spent_at_height ON transaction_outputs TYPE option<int>;")
+        .await?;
+    db.query("DEFINE FIELD block_height ON transaction_outputs TYPE int;")
+        .await?;
+    db.query("DEFINE FIELD coinbase ON transaction_outputs TYPE bool;")
+        .await?;
+
+    db.query("DEFINE TABLE transaction_rejections SCHEMAFULL;")
+        .await?;
+    db.query("DEFINE FIELD transaction_hash ON transaction_rejections TYPE string;")
+        .await?;
+    db.query("DEFINE FIELD code ON transaction_rejections TYPE int;")
+        .await?;
+    db.query("DEFINE FIELD message ON transaction_rejections TYPE string;")
+        .await?;
+    db.query("DEFINE FIELD error ON transaction_rejections FLEXIBLE TYPE object;")
+        .await?;
+    db.query("DEFINE FIELD block_height ON transaction_rejections TYPE int;")
+        .await?;
 
     db.query("DEFINE TABLE objects SCHEMAFULL;").await?;
     db.query("DEFINE FIELD object_id ON objects TYPE string;")
         .await?;
     db.query("DEFINE FIELD cert_ttl ON objects TYPE int;")
         .await?;
-    db.query("DEFINE FIELD claims ON objects FLEXIBLE TYPE array;")
+    db.query("DEFINE FIELD transaction_hash ON objects TYPE string;")
+        .await?;
+    db.query("DEFINE FIELD output_index ON objects TYPE int;")
+        .await?;
+    db.query("DEFINE FIELD owners ON objects TYPE array<string>;")
+        .await?;
+
+    // every object leaf written to the state tree, keyed by
+    // the leaf, so that certificates for past blocks can read
+    // the TTL and holding output the leaf commits to
+    db.query("DEFINE TABLE object_states SCHEMAFULL;").await?;
+    db.query("DEFINE FIELD object_id ON object_states TYPE string;")
+        .await?;
+    db.query("DEFINE FIELD cert_ttl ON object_states TYPE int;")
+        .await?;
+    db.query("DEFINE FIELD transaction_hash ON object_states TYPE string;")
+        .await?;
+    db.query("DEFINE FIELD output_index ON object_states TYPE int;")
+        .await?;
+    db.query("DEFINE FIELD owners ON object_states TYPE array<string>;")
+        .await?;
+
+    // one row per member of an object's claim set, keyed by
+    // object id and claim so inserts and deletes are idempotent
+    db.query("DEFINE TABLE object_claims SCHEMAFULL;").await?;
+    db.query("DEFINE FIELD object_id ON object_claims TYPE string;")
+        .await?;
+    db.query("DEFINE FIELD claim ON object_claims TYPE string;")
+        .await?;
+    db.query("DEFINE INDEX object_claims_object_id ON object_claims FIELDS object_id;")
+        .await?;
+
+    // addresses allowed to insert and delete an object's claims
+    // on behalf of its owner, keyed like object_claims
+    db.query("DEFINE TABLE object_claim_managers SCHEMAFULL;")
+        .await?;
+    db.query("DEFINE FIELD object_id ON object_claim_managers TYPE string;")
+        .await?;
+    db.query("DEFINE FIELD address ON object_claim_managers TYPE string;")
+        .await?;
+    db.query(
+        "DEFINE INDEX object_claim_managers_object_id ON object_claim_managers FIELDS object_id;",
+    )
+    .await?;
+
+    // nodes of the state tree, keyed by their hash. Nodes are
+    // never deleted, so that past state roots remain provable.
+    db.query("DEFINE TABLE state_nodes SCHEMAFULL;").await?;
+    db.query("DEFINE FIELD node ON state_nodes FLEXIBLE TYPE object;")
+        .await?;
+
+    // the node's signature of each block's state, keyed by
+    // block hash
+    db.query("DEFINE TABLE block_certificates SCHEMAFULL;")
+        .await?;
+    db.query("DEFINE FIELD block_height ON block_certificates TYPE int;")
+        .await?;
+    db.query("DEFINE FIELD certificate ON block_certificates FLEXIBLE TYPE object;")
         .await?;
+    db.query(
+        "DEFINE INDEX block_certificates_block_height ON block_certificates FIELDS block_height;",
+    )
+    .await?;
 
     db.query("DEFINE TABLE intermediate_faucet_outputs SCHEMAFULL;")
         .await?;
@@ -1,5 +1,9 @@
+use crate::cert::types::BlockCertificate;
+use crate::state::tree::StateNode;
+use crate::tx::error::TransactionValidationError;
 use crate::tx::types::{BlockHeader, Transaction, TransactionOutput};
 use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SurrealID(pub surrealdb::sql::Thing);
@@ -12,6 +16,10 @@ pub struct PendingTransactionRow {
     // pub hash: TransactionHash,
     pub data: Transaction,
     pub size: u64,
+
+    // "txid:index" of each outpoint the transaction spends
+    #[serde(default)]
+    pub outpoints: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,9 +43,28 @@ pub struct TransactionOutputRow {
     pub transaction_hash: String,
     pub output_index: u64,
     pub output: TransactionOutput,
-    pub owner: String,
+
+    // addresses that can take part in spending the output:
+    // the P2A address, or every participant of a multisig
+    pub owners: Vec<String>,
     pub spent: bool,
+    pub spending_transaction_hash: Option<String>,
+    pub spent_at_height: Option<u64>,
+    pub block_height: u64,
     pub output_type: String,
+
+    // coinbase outputs cannot be spent until they mature
+    pub coinbase: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionRejectionRow {
+    pub id: SurrealID,
+    pub transaction_hash: String,
+    pub code: u32,
+    pub message: String,
+    pub error: TransactionValidationError,
+    pub block_height: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -45,7 +72,53 @@ pub struct ObjectRow {
     pub id: SurrealID,
     pub object_id: String,
     pub cert_ttl: u64,
-    pub claims: Vec<Vec<u8>>,
+
+    // the unspent output currently holding the object
+    pub transaction_hash: String,
+    pub output_index: u64,
+    pub owners: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObjectClaimRow {
+    pub id: SurrealID,
+    pub object_id: String,
+    pub claim: String,
+}
+
+pub fn object_claim_key(object_id_hex: &str, claim_hex: &str) -> String {
+    format!("{}:{}", object_id_hex, claim_hex)
+}
+
+pub fn object_claim_id(object_id_hex: &str, claim_hex: &str) -> SurrealID {
+    SurrealID(Thing::from((
+        "object_claims".to_string(),
+        object_claim_key(object_id_hex, claim_hex),
+    )))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObjectClaimManagerRow {
+    pub id: SurrealID,
+    pub object_id: String,
+    pub address: String,
+}
+
+pub fn object_claim_manager_key(object_id_hex: &str, address_hex: &str) -> String {
+    format!("{}:{}", object_id_hex, address_hex)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateNodeRow {
+    pub id: SurrealID,
+    pub node: StateNode,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockCertificateRow {
+    pub id: SurrealID,
+    pub block_height: u64,
+    pub certificate: BlockCertificate,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,9 @@
+// the parts of the node that tooling can use without running
+// one: transaction types and validation, script evaluation,
+// state proofs and certificate verification, and the database
+// schema with its migrations
+pub mod cert;
+pub mod db;
+pub mod quible_ecdsa_utils;
+pub mod state;
+pub mod tx;
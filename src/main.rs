@@ -1,10 +1,15 @@
-use alloy_primitives::{Address, FixedBytes, B256};
+use alloy_primitives::{Address, B256};
 use anyhow::anyhow;
 use async_trait::async_trait;
-use cert::types::{CertificateSigningRequestDetails, QuibleSignature, SignedCertificate};
+use cert::types::{
+    BlockCertificate, Certificate, CertificateSigningRequestDetails, ProofCertificate, Signable,
+    SignedCertificate,
+};
 use db::types::{
-    BlockRow, IntermediateFaucetOutputRow, ObjectRow, PendingTransactionRow, SurrealID,
-    TrackerPing, TransactionOutputRow,
+    object_claim_id, object_claim_key, object_claim_manager_key, BlockCertificateRow, BlockRow,
+    IntermediateFaucetOutputRow, ObjectClaimManagerRow, ObjectClaimRow, ObjectRow,
+    PendingTransactionRow, StateNodeRow, SurrealID, TrackerPing, TransactionOutputRow,
+    TransactionRejectionRow,
 };
 use futures::prelude::stream::StreamExt;
 use hex;
@@ -16,6 +21,8 @@ use k256::ecdsa::SigningKey;
 use libp2p::{multiaddr, noise, ping, swarm::SwarmEvent, tcp, yamux};
 use quible_ecdsa_utils::sign_message;
 use serde::{Deserialize, Serialize};
+use state::tree::{StateNode, StateNodeStore, StateTree, EMPTY_ROOT};
+use state::{claim_state_key, claim_state_value, object_state_key, object_state_value};
 use std::collections::HashMap;
 use std::env;
 use std::fs;
@@ -31,40 +38,311 @@ use tokio::{
     time::{sleep_until, Duration, Instant},
 };
 use tower_http::cors::{Any, CorsLayer};
-use tx::engine::{collect_valid_block_transactions, ExecutionContext};
+use tx::eip712::transaction_typed_data;
+use tx::engine::{
+    collect_valid_block_transactions, final_block_position, is_final_locktime,
+    validate_block_header, validate_coinbase_transaction, ExecutionContext, MAX_BLOCK_SIZE,
+    MAX_TRANSACTION_SIZE,
+};
+use tx::error::TransactionValidationError;
+use tx::merkle::{compute_merkle_proof, compute_merkle_root};
+use tx::reward::RewardSchedule;
+use tx::script::extract_pubkey_script_owners;
+use tx::sigcache::PreviewedTransaction;
 use tx::types::{
     BlockHeader, Hashable, ObjectIdentifier, ObjectMode, Transaction, TransactionInput,
     TransactionOpCode, TransactionOutpoint, TransactionOutput,
 };
 use types::{
-    BlockDetailsPayload, BlockHeightPayload, FaucetOutputPayload, HealthCheckResponse,
-    ValueOutputEntry, ValueOutputsPayload,
+    BlockDetailsPayload, BlockHeightPayload, BlockRewardPayload, FaucetOutputPayload,
+    HealthCheckResponse, ObjectChangeEntry, TransactionInclusionProofPayload,
+    TransactionRejectionPayload, TransactionSimulationPayload, ValueOutputEntry,
+    ValueOutputsPayload,
 };
 
 use rpc::QuibleRpcServer;
 
-pub mod cert;
-pub mod db;
-pub mod quible_ecdsa_utils;
+pub use quible_node::{cert, db, quible_ecdsa_utils, state, tx};
+
 pub mod quible_transaction_utils;
 pub mod rpc;
-pub mod tx;
 pub mod types;
 
 const SLOT_DURATION: Duration = Duration::from_secs(4);
 
+#[derive(Debug, Clone)]
+pub struct NodeConfig {
+    // minimum fee, in value units per encoded byte, that a
+    // pending transaction must pay to be included in a block
+    pub minimum_fee_rate: u64,
+
+    // total encoded size of the transactions a proposed
+    // block may carry, excluding the coinbase
+    pub maximum_block_size: u64,
+
+    // whether a pending transaction may be evicted by a
+    // conflicting transaction paying a higher fee, so that
+    // wallets can bump a stuck transaction. On by default.
+    pub replace_by_fee: bool,
+
+    // network that Version2 transactions must commit to, so
+    // that they cannot be replayed on other networks
+    pub chain_id: u64,
+
+    // block height from which Version1 transactions, which
+    // commit to no chain, are rejected. If unset they are
+    // accepted indefinitely.
+    pub chain_id_activation_height: Option<u64>,
+
+    // value minted by the coinbase of each block
+    pub reward_schedule: RewardSchedule,
+
+    // number of blocks that must pass before a coinbase
+    // output can be spent
+    pub coinbase_maturity: u64,
+}
+
+impl Default for NodeConfig {
+    fn default() -> Self {
+        NodeConfig {
+            minimum_fee_rate: 0,
+            maximum_block_size: MAX_BLOCK_SIZE,
+            replace_by_fee: true,
+            chain_id: 0,
+            chain_id_activation_height: None,
+            reward_schedule: RewardSchedule::default(),
+            coinbase_maturity: 0,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 struct Record {
     #[allow(dead_code)]
     id: Thing,
 }
 
+#[derive(Debug, Clone)]
+pub struct MempoolEntry {
+    transaction_hash: [u8; 32],
+    transaction: Transaction,
+    fee: u64,
+    size: u64,
+}
+
+impl MempoolEntry {
+    // compares fee per byte without dividing, so that
+    // small differences in fee rate are not rounded away
+    fn cmp_fee_rate(&self, other: &MempoolEntry) -> std::cmp::Ordering {
+        (self.fee as u128 * other.size as u128).cmp(&(other.fee as u128 * self.size as u128))
+    }
+}
+
+// a replacement must pay a strictly higher fee rate than every
+// transaction it evicts, and enough absolute fee to cover the
+// evicted fees plus its own size at the minimum fee rate
+fn check_replacement(
+    replacement: &MempoolEntry,
+    conflicts: &[MempoolEntry],
+    minimum_fee_rate: u64,
+) -> Result<(), TransactionValidationError> {
+    if conflicts
+        .iter()
+        .any(|conflict| replacement.cmp_fee_rate(conflict) != std::cmp::Ordering::Greater)
+    {
+        return Err(TransactionValidationError::InsufficientReplacementFee);
+    }
+
+    let required_fee = conflicts
+        .iter()
+        .fold(0u64, |total, conflict| total.saturating_add(conflict.fee))
+        .saturating_add(replacement.size.saturating_mul(minimum_fee_rate));
+
+    if replacement.fee < required_fee {
+        return Err(TransactionValidationError::InsufficientReplacementFee);
+    }
+
+    Ok(())
+}
+
+// pending transactions, other than the given one, that spend
+// any of the outpoints the given transaction spends
+async fn fetch_conflicting_mempool_entries(
+    db: &Arc<Surreal<AnyDb>>,
+    transaction_hash: [u8; 32],
+    transaction: &Transaction,
+) -> anyhow::Result<Vec<MempoolEntry>> {
+    let inputs = transaction.inputs();
+
+    let pending_transaction_rows: Vec<PendingTransactionRow> =
+        db.select("pending_transactions").await?;
+
+    let mut conflicts = vec![];
+    for row in pending_transaction_rows {
+        let pending_transaction_hash = row.data.hash_eip191()?;
+        let pending_inputs = row.data.inputs();
+
+        let conflicting = pending_transaction_hash != transaction_hash
+            && pending_inputs.iter().any(|pending_input| {
+                inputs
+                    .iter()
+                    .any(|input| input.outpoint == pending_input.outpoint)
+            });
+
+        if conflicting {
+            let spent_outputs = fetch_spent_outputs(db, &row.data).await?;
+            conflicts.push(MempoolEntry {
+                transaction_hash: pending_transaction_hash,
+                fee: estimate_transaction_fee(&row.data, &spent_outputs),
+                size: row.size,
+                transaction: row.data,
+            });
+        }
+    }
+
+    Ok(conflicts)
+}
+
 pub struct QuibleBlockProposerExecutionContextImpl {
     db: Arc<Surreal<AnyDb>>,
-    mempool: Vec<([u8; 32], Transaction)>,
+    mempool: Vec<MempoolEntry>,
+
+    // the mempool as of the start of the block, with the
+    // pubkey scripts its transactions spend
+    previewed_transactions: Vec<PreviewedTransaction>,
+    minimum_fee_rate: u64,
+    chain_id: u64,
+    chain_id_activation_height: Option<u64>,
+    coinbase_maturity: u64,
+    remaining_block_size: u64,
+    block_height: u64,
+    block_timestamp: u64,
     transaction_cache: HashMap<[u8; 32], Transaction>,
     spent_outpoints: Vec<TransactionOutpoint>,
     included_transactions: Vec<[u8; 32]>,
+    collected_fees: u64,
+}
+
+async fn fetch_transaction_output_row(
+    db: &Arc<Surreal<AnyDb>>,
+    outpoint: &TransactionOutpoint,
+) -> anyhow::Result<Option<TransactionOutputRow>> {
+    let transaction_hash_hex = hex::encode(outpoint.txid);
+    let mut result = db
+        .query("SELECT * FROM transaction_outputs WHERE id = $id")
+        .bind((
+            "id",
+            SurrealID(Thing::from((
+                "transaction_outputs".to_string(),
+                format!(
+                    "{}:{}",
+                    transaction_hash_hex.clone().to_string(),
+                    outpoint.index
+                ),
+            ))),
+        ))
+        .await
+        .map_err(|err| anyhow!(err))?;
+
+    Ok(result.take(0)?)
+}
+
+async fn fetch_confirmed_unspent_output(
+    db: &Arc<Surreal<AnyDb>>,
+    outpoint: &TransactionOutpoint,
+) -> anyhow::Result<TransactionOutput> {
+    match fetch_transaction_output_row(db, outpoint).await? {
+        Some(transaction_output_row) => {
+            if transaction_output_row.spent {
+                return Err(TransactionValidationError::DoubleSpend.into());
+            }
+
+            Ok(transaction_output_row.output)
+        }
+
+        None => Err(TransactionValidationError::MissingOutpoint.into()),
+    }
+}
+
+async fn fetch_confirmed_output_block_height(
+    db: &Arc<Surreal<AnyDb>>,
+    outpoint: &TransactionOutpoint,
+) -> anyhow::Result<u64> {
+    fetch_transaction_output_row(db, outpoint)
+        .await?
+        .map(|row| row.block_height)
+        .ok_or(TransactionValidationError::MissingOutpoint.into())
+}
+
+async fn fetch_confirmed_output_is_coinbase(
+    db: &Arc<Surreal<AnyDb>>,
+    outpoint: &TransactionOutpoint,
+) -> anyhow::Result<bool> {
+    fetch_transaction_output_row(db, outpoint)
+        .await?
+        .map(|row| row.coinbase)
+        .ok_or(TransactionValidationError::MissingOutpoint.into())
+}
+
+async fn fetch_confirmed_object_claim_managers(
+    db: &Arc<Surreal<AnyDb>>,
+    object_id: [u8; 32],
+) -> anyhow::Result<Vec<[u8; 20]>> {
+    let addresses: Vec<String> = db
+        .query("SELECT VALUE address FROM object_claim_managers WHERE object_id = $object_id")
+        .bind(("object_id", hex::encode(object_id)))
+        .await?
+        .take(0)?;
+
+    addresses
+        .into_iter()
+        .map(|address| Ok(hex::decode(address)?.as_slice().try_into()?))
+        .collect()
+}
+
+// outputs spent by each input of a pending transaction, or
+// None for inputs whose output cannot be found
+async fn fetch_spent_outputs(
+    db: &Arc<Surreal<AnyDb>>,
+    transaction: &Transaction,
+) -> anyhow::Result<Vec<Option<TransactionOutput>>> {
+    let mut spent_outputs = vec![];
+    for input in transaction.inputs() {
+        spent_outputs.push(
+            fetch_transaction_output_row(db, &input.outpoint)
+                .await?
+                .map(|output_row| output_row.output),
+        );
+    }
+
+    Ok(spent_outputs)
+}
+
+// estimates the implicit fee of a pending transaction for
+// mempool ordering. Inputs that cannot be found contribute
+// no value; such transactions are rejected by the engine.
+fn estimate_transaction_fee(
+    transaction: &Transaction,
+    spent_outputs: &[Option<TransactionOutput>],
+) -> u64 {
+    let input_value = spent_outputs
+        .iter()
+        .map(|output| match output {
+            Some(TransactionOutput::Value { value, .. }) => *value,
+            _ => 0,
+        })
+        .fold(0u64, |total, value| total.saturating_add(value));
+
+    let output_value = transaction
+        .outputs()
+        .iter()
+        .map(|output| match output {
+            TransactionOutput::Value { value, .. } => *value,
+            TransactionOutput::Object { .. } => 0,
+        })
+        .fold(0u64, |total, value| total.saturating_add(value));
+
+    input_value.saturating_sub(output_value)
 }
 
 #[async_trait]
@@ -72,67 +350,118 @@ impl ExecutionContext for QuibleBlockProposerExecutionContextImpl {
     async fn fetch_next_pending_transaction(
         &mut self,
     ) -> anyhow::Result<Option<([u8; 32], Transaction)>> {
-        let entry = self.mempool.pop();
+        // transactions too large for the remaining space, or
+        // whose locktime has not passed yet, stay in the
+        // mempool and are considered again next slot
+        let best_index = self
+            .mempool
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.size <= self.remaining_block_size)
+            .filter(|(_, entry)| {
+                is_final_locktime(
+                    entry.transaction.locktime(),
+                    self.block_height,
+                    self.block_timestamp,
+                )
+            })
+            .max_by(|(_, left), (_, right)| left.cmp_fee_rate(right))
+            .map(|(index, _)| index);
 
-        if let Some((transaction_hash, transaction)) = entry.clone() {
-            self.transaction_cache.insert(transaction_hash, transaction);
-        }
+        let Some(index) = best_index else {
+            return Ok(None);
+        };
+
+        let entry = self.mempool.swap_remove(index);
+
+        self.transaction_cache
+            .insert(entry.transaction_hash, entry.transaction.clone());
+
+        Ok(Some((entry.transaction_hash, entry.transaction)))
+    }
 
-        Ok(entry)
+    async fn preview_pending_transactions(&mut self) -> anyhow::Result<Vec<PreviewedTransaction>> {
+        Ok(std::mem::take(&mut self.previewed_transactions))
     }
 
     async fn fetch_unspent_output(
         &mut self,
         outpoint: TransactionOutpoint,
     ) -> anyhow::Result<TransactionOutput> {
-        let transaction_hash_hex = hex::encode(outpoint.txid);
-        let mut result = self
-            .db
-            .query("SELECT * FROM transaction_outputs WHERE id = $id")
-            .bind((
-                "id",
-                SurrealID(Thing::from((
-                    "transaction_outputs".to_string(),
-                    format!(
-                        "{}:{}",
-                        transaction_hash_hex.clone().to_string(),
-                        outpoint.index
-                    ),
-                ))),
-            ))
-            .await
-            .map_err(|err| anyhow!(err))?;
+        // outputs consumed earlier in the block being built are
+        // not yet marked as spent in the database
+        if self.spent_outpoints.contains(&outpoint) {
+            return Err(TransactionValidationError::DoubleSpend.into());
+        }
 
-        let transaction_output_row_maybe: Option<TransactionOutputRow> = result.take(0)?;
+        fetch_confirmed_unspent_output(&self.db, &outpoint).await
+    }
 
-        match transaction_output_row_maybe {
-            Some(transaction_output_row) => {
-                if transaction_output_row.spent {
-                    return Err(anyhow!("cannot spend output twice"));
-                }
+    fn block_height(&self) -> u64 {
+        self.block_height
+    }
 
-                Ok(transaction_output_row.output)
-            }
+    fn block_timestamp(&self) -> u64 {
+        self.block_timestamp
+    }
 
-            None => {
-                dbg!(outpoint);
-                Err(anyhow!("transaction hash not found!"))
-            }
-        }
+    async fn fetch_output_block_height(
+        &mut self,
+        outpoint: TransactionOutpoint,
+    ) -> anyhow::Result<u64> {
+        fetch_confirmed_output_block_height(&self.db, &outpoint).await
+    }
+
+    async fn fetch_output_is_coinbase(
+        &mut self,
+        outpoint: TransactionOutpoint,
+    ) -> anyhow::Result<bool> {
+        fetch_confirmed_output_is_coinbase(&self.db, &outpoint).await
+    }
+
+    fn coinbase_maturity(&self) -> u64 {
+        self.coinbase_maturity
+    }
+
+    async fn fetch_object_claim_managers(
+        &mut self,
+        object_id: [u8; 32],
+    ) -> anyhow::Result<Vec<[u8; 20]>> {
+        fetch_confirmed_object_claim_managers(&self.db, object_id).await
+    }
+
+    fn minimum_fee_rate(&self) -> u64 {
+        self.minimum_fee_rate
+    }
+
+    fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    fn chain_id_activation_height(&self) -> Option<u64> {
+        self.chain_id_activation_height
     }
 
-    async fn include_in_next_block(&mut self, transaction_hash: [u8; 32]) -> anyhow::Result<()> {
+    async fn include_in_next_block(
+        &mut self,
+        transaction_hash: [u8; 32],
+        fee: u64,
+    ) -> anyhow::Result<()> {
         let transaction = self
             .transaction_cache
             .get(&transaction_hash)
             .ok_or(anyhow!("transaction hash not found!"))?;
-        let Transaction::Version1 { inputs, .. } = transaction;
-
-        for input in inputs {
+        let transaction_size = transaction.encoded_size()?;
+        for input in transaction.inputs() {
             self.spent_outpoints.push(input.clone().outpoint);
         }
 
         self.included_transactions.push(transaction_hash);
+        self.collected_fees = self
+            .collected_fees
+            .checked_add(fee)
+            .ok_or(anyhow!("collected fees overflow"))?;
+        self.remaining_block_size = self.remaining_block_size.saturating_sub(transaction_size);
 
         Ok(())
     }
@@ -143,8 +472,28 @@ impl ExecutionContext for QuibleBlockProposerExecutionContextImpl {
         error: anyhow::Error,
     ) -> anyhow::Result<()> {
         let transaction_hash_hex = hex::encode(transaction_hash);
+        let validation_error = TransactionValidationError::from_anyhow(&error);
+
+        println!(
+            "rejected transaction {}: {}",
+            transaction_hash_hex, validation_error
+        );
 
-        dbg!(transaction_hash_hex.clone(), error);
+        let _: Option<TransactionRejectionRow> = self
+            .db
+            .update(("transaction_rejections", transaction_hash_hex.clone()))
+            .content(TransactionRejectionRow {
+                id: SurrealID(Thing::from((
+                    "transaction_rejections".to_string(),
+                    transaction_hash_hex.clone(),
+                ))),
+                transaction_hash: transaction_hash_hex.clone(),
+                code: validation_error.code(),
+                message: validation_error.to_string(),
+                error: validation_error,
+                block_height: self.block_height,
+            })
+            .await?;
 
         self.db
             .query("DELETE FROM pending_transactions WHERE id = $id")
@@ -161,116 +510,565 @@ impl ExecutionContext for QuibleBlockProposerExecutionContextImpl {
     }
 }
 
-async fn digest_object_output(
-    db: &Arc<Surreal<AnyDb>>,
-    object_id: &ObjectIdentifier,
-    data_script: &Vec<TransactionOpCode>,
-) -> anyhow::Result<()> {
-    let object_id_hex = hex::encode(object_id.raw);
-    let surreal_object_id = SurrealID(Thing::from((
-        "objects".to_string(),
-        object_id_hex.to_string(),
-    )));
+// executes a single transaction as if it were the only one in
+// the next block, against the confirmed set of unspent outputs.
+// Nothing is written to the database.
+pub struct QuibleSimulationExecutionContextImpl {
+    db: Arc<Surreal<AnyDb>>,
+    transaction: Option<([u8; 32], Transaction)>,
+    minimum_fee_rate: u64,
+    chain_id: u64,
+    chain_id_activation_height: Option<u64>,
+    coinbase_maturity: u64,
+    block_height: u64,
+    block_timestamp: u64,
+    outcome: Option<Result<u64, TransactionValidationError>>,
+}
 
-    if let ObjectMode::Fresh = object_id.mode {
-        let _result: Vec<ObjectRow> = db
-            .create("objects")
-            .content(ObjectRow {
-                id: surreal_object_id.clone(),
-                object_id: object_id_hex,
-                claims: vec![],
-                cert_ttl: 86400,
-            })
-            .await?;
-    };
+#[async_trait]
+impl ExecutionContext for QuibleSimulationExecutionContextImpl {
+    async fn fetch_next_pending_transaction(
+        &mut self,
+    ) -> anyhow::Result<Option<([u8; 32], Transaction)>> {
+        Ok(self.transaction.take())
+    }
 
-    for opcode in data_script {
-        match opcode {
-            TransactionOpCode::DeleteAll => {
-                db.query("UPDATE objects SET claims = [] WHERE id = $id")
-                    .bind(("id", surreal_object_id.clone()))
-                    .await?;
-            }
+    // a single transaction gains nothing from recovering
+    // its signatures ahead of time
+    async fn preview_pending_transactions(&mut self) -> anyhow::Result<Vec<PreviewedTransaction>> {
+        Ok(vec![])
+    }
 
-            TransactionOpCode::Insert { data } => {
-                db.query("UPDATE objects SET claims += $value WHERE id = $id")
-                    .bind(("id", surreal_object_id.clone()))
-                    .bind(("value", surrealdb::sql::Bytes::from(data.clone())))
-                    .await?;
-            }
+    async fn fetch_unspent_output(
+        &mut self,
+        outpoint: TransactionOutpoint,
+    ) -> anyhow::Result<TransactionOutput> {
+        fetch_confirmed_unspent_output(&self.db, &outpoint).await
+    }
 
-            TransactionOpCode::Delete { data } => {
-                db.query("UPDATE objects SET claims -= $value WHERE id = $id")
-                    .bind(("id", surreal_object_id.clone()))
-                    .bind(("value", surrealdb::sql::Bytes::from(data.clone())))
-                    .await?;
-            }
+    fn block_height(&self) -> u64 {
+        self.block_height
+    }
 
-            TransactionOpCode::SetCertTTL { data } => {
-                db.query("UPDATE objects SET cert_ttl = $value WHERE id = $id")
-                    .bind(("id", surreal_object_id.clone()))
-                    .bind(("value", data))
-                    .await?;
-            }
+    fn block_timestamp(&self) -> u64 {
+        self.block_timestamp
+    }
 
-            _ => {}
-        }
+    async fn fetch_output_block_height(
+        &mut self,
+        outpoint: TransactionOutpoint,
+    ) -> anyhow::Result<u64> {
+        fetch_confirmed_output_block_height(&self.db, &outpoint).await
     }
 
-    Ok(())
+    async fn fetch_output_is_coinbase(
+        &mut self,
+        outpoint: TransactionOutpoint,
+    ) -> anyhow::Result<bool> {
+        fetch_confirmed_output_is_coinbase(&self.db, &outpoint).await
+    }
+
+    fn coinbase_maturity(&self) -> u64 {
+        self.coinbase_maturity
+    }
+
+    async fn fetch_object_claim_managers(
+        &mut self,
+        object_id: [u8; 32],
+    ) -> anyhow::Result<Vec<[u8; 20]>> {
+        fetch_confirmed_object_claim_managers(&self.db, object_id).await
+    }
+
+    fn minimum_fee_rate(&self) -> u64 {
+        self.minimum_fee_rate
+    }
+
+    fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    fn chain_id_activation_height(&self) -> Option<u64> {
+        self.chain_id_activation_height
+    }
+
+    async fn include_in_next_block(
+        &mut self,
+        _transaction_hash: [u8; 32],
+        fee: u64,
+    ) -> anyhow::Result<()> {
+        self.outcome = Some(Ok(fee));
+        Ok(())
+    }
+
+    async fn record_invalid_transaction(
+        &mut self,
+        _transaction_hash: [u8; 32],
+        error: anyhow::Error,
+    ) -> anyhow::Result<()> {
+        self.outcome = Some(Err(TransactionValidationError::from_anyhow(&error)));
+        Ok(())
+    }
 }
 
-async fn propose_block(
-    db_arc: &Arc<Surreal<AnyDb>>,
-    node_signing_key: &SigningKey,
-) -> anyhow::Result<BlockRow> {
-    let previous_block_row: Option<BlockRow> = db_arc
-        .query("SELECT * FROM blocks ORDER BY height DESC LIMIT 1")
+// height and timestamp of the next block
+async fn fetch_next_block_position(db_arc: &Arc<Surreal<AnyDb>>) -> anyhow::Result<(u64, u64)> {
+    let block_height: Option<u64> = db_arc
+        .query("SELECT height FROM blocks ORDER BY height DESC LIMIT 1")
         .await
-        .and_then(|mut response| response.take(0))?;
+        .and_then(|mut response| response.take((0, "height")))?;
 
-    let block_number = previous_block_row
-        .clone()
-        .map(|row| row.height + 1)
-        .unwrap_or(0);
+    let block_timestamp: u64 = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs();
 
-    println!("preparing block {}", block_number);
+    Ok((block_height.map_or(0, |height| height + 1), block_timestamp))
+}
 
-    let pending_transaction_rows: Vec<PendingTransactionRow> =
-        db_arc.select("pending_transactions").await?;
+// runs the engine over a single transaction at the height of the
+// next block, returning its fee or the reason it would be rejected
+async fn simulate_transaction(
+    db_arc: &Arc<Surreal<AnyDb>>,
+    node_config: &NodeConfig,
+    transaction_hash: [u8; 32],
+    transaction: Transaction,
+) -> anyhow::Result<Result<u64, TransactionValidationError>> {
+    let (block_height, block_timestamp) = fetch_next_block_position(db_arc).await?;
 
-    let mut execution_context = QuibleBlockProposerExecutionContextImpl {
-        transaction_cache: HashMap::new(),
-        mempool: pending_transaction_rows
-            .iter()
-            .map(|tx| {
-                Ok((tx.clone().data.hash_eip191()?, tx.clone().data))
-                    as Result<([u8; 32], Transaction), anyhow::Error>
-            })
-            .collect::<Result<Vec<([u8; 32], Transaction)>, anyhow::Error>>()?,
+    simulate_transaction_at(
+        db_arc,
+        node_config,
+        transaction_hash,
+        transaction,
+        block_height,
+        block_timestamp,
+    )
+    .await
+}
+
+async fn simulate_transaction_at(
+    db_arc: &Arc<Surreal<AnyDb>>,
+    node_config: &NodeConfig,
+    transaction_hash: [u8; 32],
+    transaction: Transaction,
+    block_height: u64,
+    block_timestamp: u64,
+) -> anyhow::Result<Result<u64, TransactionValidationError>> {
+    let mut execution_context = QuibleSimulationExecutionContextImpl {
         db: db_arc.clone(),
-        spent_outpoints: vec![],
-        included_transactions: vec![],
+        transaction: Some((transaction_hash, transaction)),
+        minimum_fee_rate: node_config.minimum_fee_rate,
+        chain_id: node_config.chain_id,
+        chain_id_activation_height: node_config.chain_id_activation_height,
+        coinbase_maturity: node_config.coinbase_maturity,
+        block_height,
+        block_timestamp,
+        outcome: None,
     };
 
-    let timestamp: u64 = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map_err(|e| ErrorDb::Thrown(format!("Failed to generate timestamp: {}", e).into()))?
-        .as_secs();
-
     collect_valid_block_transactions(&mut execution_context).await?;
 
-    let previous_block_header_hash =
-        previous_block_row.map_or(Ok([0u8; 32]), |row| row.header.hash())?;
+    execution_context.outcome.ok_or(anyhow!(
+        "simulated transaction was neither included nor rejected"
+    ))
+}
 
-    let block_header = BlockHeader::Version1 {
-        previous_block_header_hash,
-        merkle_root: [0u8; 32],
-        timestamp,
+// describes what digest_object_output would do with the object
+// outputs of a transaction, without applying it
+fn summarize_object_changes(transaction: &Transaction) -> Vec<ObjectChangeEntry> {
+    transaction
+        .outputs()
+        .iter()
+        .filter_map(|output| match output {
+            TransactionOutput::Object {
+                object_id,
+                data_script,
+                ..
+            } => Some((object_id, data_script)),
+            _ => None,
+        })
+        .map(|(object_id, data_script)| {
+            let mut change = ObjectChangeEntry {
+                object_id: object_id.raw,
+                created: matches!(object_id.mode, ObjectMode::Fresh),
+                deleted_all_claims: false,
+                inserted_claims: vec![],
+                deleted_claims: vec![],
+                cert_ttl: None,
+                granted_claim_managers: vec![],
+                revoked_claim_managers: vec![],
+            };
+
+            for opcode in data_script {
+                match opcode {
+                    TransactionOpCode::DeleteAll => {
+                        change.deleted_all_claims = true;
+                        change.inserted_claims.clear();
+                    }
+                    TransactionOpCode::Insert { data } => change.inserted_claims.push(data.clone()),
+                    TransactionOpCode::Delete { data } => {
+                        change.inserted_claims.retain(|claim| claim != data);
+                        change.deleted_claims.push(data.clone());
+                    }
+                    TransactionOpCode::SetCertTTL { data } => change.cert_ttl = Some(*data),
+                    TransactionOpCode::GrantClaimManager { data } => {
+                        change
+                            .revoked_claim_managers
+                            .retain(|address| address != data);
+                        change.granted_claim_managers.push(data.clone());
+                    }
+                    TransactionOpCode::RevokeClaimManager { data } => {
+                        change
+                            .granted_claim_managers
+                            .retain(|address| address != data);
+                        change.revoked_claim_managers.push(data.clone());
+                    }
+                    _ => {}
+                }
+            }
+
+            change
+        })
+        .collect()
+}
+
+pub struct QuibleStateNodeStoreImpl {
+    db: Arc<Surreal<AnyDb>>,
+}
+
+#[async_trait]
+impl StateNodeStore for QuibleStateNodeStoreImpl {
+    async fn fetch_node(&mut self, hash: [u8; 32]) -> anyhow::Result<Option<StateNode>> {
+        let row: Option<StateNodeRow> = self.db.select(("state_nodes", hex::encode(hash))).await?;
+
+        Ok(row.map(|row| row.node))
+    }
+
+    async fn store_node(&mut self, node: &StateNode) -> anyhow::Result<()> {
+        let hash_hex = hex::encode(node.hash());
+        let _: Option<StateNodeRow> = self
+            .db
+            .update(("state_nodes", hash_hex.clone()))
+            .content(StateNodeRow {
+                id: SurrealID(Thing::from(("state_nodes".to_string(), hash_hex))),
+                node: node.clone(),
+            })
+            .await?;
+
+        Ok(())
+    }
+}
+
+type QuibleStateTree = StateTree<QuibleStateNodeStoreImpl>;
+
+// state tree as of the given block. Blocks with Version1
+// headers do not commit to a state root, so the tree is then
+// rebuilt from the stored objects and claims.
+async fn load_state_tree(
+    db: &Arc<Surreal<AnyDb>>,
+    block_row: Option<&BlockRow>,
+) -> anyhow::Result<QuibleStateTree> {
+    let state_root = block_row.and_then(|row| row.header.state_root());
+
+    let mut state_tree = StateTree::new(
+        QuibleStateNodeStoreImpl { db: db.clone() },
+        state_root.unwrap_or(EMPTY_ROOT),
+    );
+
+    if state_root.is_none() {
+        let object_rows: Vec<ObjectRow> = db.select("objects").await?;
+        for object_row in object_rows {
+            let object_id = decode_hash(&object_row.object_id)?;
+            update_object_state(db, &mut state_tree, &object_id).await?;
+        }
+
+        let claim_rows: Vec<ObjectClaimRow> = db.select("object_claims").await?;
+        for claim_row in claim_rows {
+            let claim = hex::decode(claim_row.claim)?;
+            state_tree
+                .insert(
+                    claim_state_key(&decode_hash(&claim_row.object_id)?, &claim),
+                    claim_state_value(&claim),
+                )
+                .await?;
+        }
+    }
+
+    Ok(state_tree)
+}
+
+fn decode_hash(hash_hex: &str) -> anyhow::Result<[u8; 32]> {
+    let mut hash = [0u8; 32];
+    hex::decode_to_slice(hash_hex, &mut hash)?;
+
+    Ok(hash)
+}
+
+// sets the object's leaf in the state tree from its row, or
+// removes the leaf if the object no longer exists
+async fn update_object_state(
+    db: &Arc<Surreal<AnyDb>>,
+    state_tree: &mut QuibleStateTree,
+    object_id: &[u8; 32],
+) -> anyhow::Result<()> {
+    let object_row: Option<ObjectRow> = db.select(("objects", hex::encode(object_id))).await?;
+
+    match object_row {
+        Some(object_row) => {
+            let object_state = object_state_value(
+                &decode_hash(&object_row.transaction_hash)?,
+                object_row.output_index,
+                object_row.cert_ttl,
+            );
+
+            // the leaf is a hash of the row, so it keeps
+            // resolving to the row once the object changes
+            let object_state_id = hex::encode(object_state);
+            let _: Option<ObjectRow> = db
+                .update(("object_states", object_state_id.clone()))
+                .content(ObjectRow {
+                    id: SurrealID(Thing::from(("object_states".to_string(), object_state_id))),
+                    ..object_row
+                })
+                .await?;
+
+            state_tree
+                .insert(object_state_key(object_id), object_state)
+                .await
+        }
+
+        None => state_tree.remove(object_state_key(object_id)).await,
+    }
+}
+
+async fn delete_object_claims(
+    db: &Arc<Surreal<AnyDb>>,
+    state_tree: &mut QuibleStateTree,
+    object_id: &[u8; 32],
+) -> anyhow::Result<()> {
+    let object_id_hex = hex::encode(object_id);
+
+    let claims: Vec<String> = db
+        .query("SELECT VALUE claim FROM object_claims WHERE object_id = $object_id")
+        .bind(("object_id", object_id_hex.clone()))
+        .await?
+        .take(0)?;
+    for claim in claims {
+        state_tree
+            .remove(claim_state_key(object_id, &hex::decode(claim)?))
+            .await?;
+    }
+
+    db.query("DELETE FROM object_claims WHERE object_id = $object_id")
+        .bind(("object_id", object_id_hex))
+        .await?;
+
+    Ok(())
+}
+
+async fn digest_object_output(
+    db: &Arc<Surreal<AnyDb>>,
+    state_tree: &mut QuibleStateTree,
+    object_id: &ObjectIdentifier,
+    data_script: &Vec<TransactionOpCode>,
+    transaction_hash_hex: &str,
+    output_index: u64,
+    owners: &[String],
+) -> anyhow::Result<()> {
+    let object_id_hex = hex::encode(object_id.raw);
+    let surreal_object_id = SurrealID(Thing::from((
+        "objects".to_string(),
+        object_id_hex.to_string(),
+    )));
+
+    match object_id.mode {
+        ObjectMode::Fresh => {
+            let _result: Vec<ObjectRow> = db
+                .create("objects")
+                .content(ObjectRow {
+                    id: surreal_object_id.clone(),
+                    object_id: object_id_hex.clone(),
+                    cert_ttl: 86400,
+                    transaction_hash: transaction_hash_hex.to_string(),
+                    output_index,
+                    owners: owners.to_vec(),
+                })
+                .await?;
+        }
+
+        ObjectMode::Existing { .. } => {
+            db.query(
+                "UPDATE objects SET \
+                   transaction_hash = $transaction_hash, \
+                   output_index = $output_index, \
+                   owners = $owners \
+                 WHERE id = $id",
+            )
+            .bind(("id", surreal_object_id.clone()))
+            .bind(("transaction_hash", transaction_hash_hex.to_string()))
+            .bind(("output_index", output_index))
+            .bind(("owners", owners.to_vec()))
+            .await?;
+        }
     };
 
-    let block_header_hash = block_header.hash()?;
-    let block_header_hash_hex = hex::encode(block_header_hash);
+    for opcode in data_script {
+        match opcode {
+            TransactionOpCode::DeleteAll => {
+                delete_object_claims(db, state_tree, &object_id.raw).await?;
+            }
+
+            TransactionOpCode::Insert { data } => {
+                state_tree
+                    .insert(
+                        claim_state_key(&object_id.raw, data),
+                        claim_state_value(data),
+                    )
+                    .await?;
+
+                let claim_hex = hex::encode(data);
+                let _: Option<ObjectClaimRow> = db
+                    .update((
+                        "object_claims",
+                        object_claim_key(&object_id_hex, &claim_hex),
+                    ))
+                    .content(ObjectClaimRow {
+                        id: object_claim_id(&object_id_hex, &claim_hex),
+                        object_id: object_id_hex.clone(),
+                        claim: claim_hex,
+                    })
+                    .await?;
+            }
+
+            TransactionOpCode::Delete { data } => {
+                state_tree
+                    .remove(claim_state_key(&object_id.raw, data))
+                    .await?;
+
+                let _: Option<ObjectClaimRow> = db
+                    .delete((
+                        "object_claims",
+                        object_claim_key(&object_id_hex, &hex::encode(data)),
+                    ))
+                    .await?;
+            }
+
+            TransactionOpCode::SetCertTTL { data } => {
+                db.query("UPDATE objects SET cert_ttl = $value WHERE id = $id")
+                    .bind(("id", surreal_object_id.clone()))
+                    .bind(("value", data))
+                    .await?;
+            }
+
+            TransactionOpCode::GrantClaimManager { data } => {
+                let address_hex = hex::encode(data);
+                let _: Option<ObjectClaimManagerRow> = db
+                    .update((
+                        "object_claim_managers",
+                        object_claim_manager_key(&object_id_hex, &address_hex),
+                    ))
+                    .content(ObjectClaimManagerRow {
+                        id: SurrealID(Thing::from((
+                            "object_claim_managers".to_string(),
+                            object_claim_manager_key(&object_id_hex, &address_hex),
+                        ))),
+                        object_id: object_id_hex.clone(),
+                        address: address_hex,
+                    })
+                    .await?;
+            }
+
+            TransactionOpCode::RevokeClaimManager { data } => {
+                let _: Option<ObjectClaimManagerRow> = db
+                    .delete((
+                        "object_claim_managers",
+                        object_claim_manager_key(&object_id_hex, &hex::encode(data)),
+                    ))
+                    .await?;
+            }
+
+            _ => {}
+        }
+    }
+
+    update_object_state(db, state_tree, &object_id.raw).await?;
+
+    Ok(())
+}
+
+async fn propose_block(
+    db_arc: &Arc<Surreal<AnyDb>>,
+    node_signing_key: &SigningKey,
+    node_config: &NodeConfig,
+) -> anyhow::Result<BlockRow> {
+    let previous_block_row: Option<BlockRow> = db_arc
+        .query("SELECT * FROM blocks ORDER BY height DESC LIMIT 1")
+        .await
+        .and_then(|mut response| response.take(0))?;
+
+    let block_number = previous_block_row
+        .clone()
+        .map(|row| row.height + 1)
+        .unwrap_or(0);
+
+    println!("preparing block {}", block_number);
+
+    let pending_transaction_rows: Vec<PendingTransactionRow> =
+        db_arc.select("pending_transactions").await?;
+
+    let mut mempool = Vec::<MempoolEntry>::with_capacity(pending_transaction_rows.len());
+    let mut previewed_transactions = Vec::with_capacity(pending_transaction_rows.len());
+    for row in pending_transaction_rows {
+        let spent_outputs = fetch_spent_outputs(db_arc, &row.data).await?;
+
+        previewed_transactions.push(PreviewedTransaction {
+            transaction: row.data.clone(),
+            spent_pubkey_scripts: spent_outputs
+                .iter()
+                .map(|output| match output {
+                    Some(TransactionOutput::Value { pubkey_script, .. })
+                    | Some(TransactionOutput::Object { pubkey_script, .. }) => {
+                        Some(pubkey_script.clone())
+                    }
+                    None => None,
+                })
+                .collect(),
+        });
+
+        mempool.push(MempoolEntry {
+            transaction_hash: row.data.hash_eip191()?,
+            fee: estimate_transaction_fee(&row.data, &spent_outputs),
+            size: row.size,
+            transaction: row.data,
+        });
+    }
+
+    let timestamp: u64 = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| ErrorDb::Thrown(format!("Failed to generate timestamp: {}", e).into()))?
+        .as_secs();
+
+    let mut execution_context = QuibleBlockProposerExecutionContextImpl {
+        transaction_cache: HashMap::new(),
+        mempool,
+        previewed_transactions,
+        minimum_fee_rate: node_config.minimum_fee_rate,
+        chain_id: node_config.chain_id,
+        chain_id_activation_height: node_config.chain_id_activation_height,
+        coinbase_maturity: node_config.coinbase_maturity,
+        remaining_block_size: node_config.maximum_block_size,
+        block_height: block_number,
+        block_timestamp: timestamp,
+        db: db_arc.clone(),
+        spent_outpoints: vec![],
+        included_transactions: vec![],
+        collected_fees: 0,
+    };
+
+    collect_valid_block_transactions(&mut execution_context).await?;
+
+    let previous_block_header_hash = previous_block_row
+        .as_ref()
+        .map_or(Ok([0u8; 32]), |row| row.header.hash())?;
 
     let mut transactions = execution_context
         .included_transactions
@@ -289,7 +1087,14 @@ async fn propose_block(
         })
         .collect::<Result<Vec<([u8; 32], Transaction)>, anyhow::Error>>()?;
 
-    let coinbase_transaction = Transaction::Version1 {
+    let block_reward = node_config.reward_schedule.block_reward(block_number);
+
+    let coinbase_value = block_reward
+        .checked_add(execution_context.collected_fees)
+        .ok_or(anyhow!("block reward plus fees overflows"))?;
+
+    let coinbase_transaction = Transaction::Version2 {
+        chain_id: node_config.chain_id,
         inputs: vec![TransactionInput {
             outpoint: TransactionOutpoint {
                 txid: [0u8; 32],
@@ -304,7 +1109,7 @@ async fn propose_block(
         }],
 
         outputs: vec![TransactionOutput::Value {
-            value: 5,
+            value: coinbase_value,
 
             pubkey_script: vec![
                 TransactionOpCode::Dup,
@@ -320,46 +1125,113 @@ async fn propose_block(
         locktime: 0,
     };
 
-    transactions.insert(
-        0,
-        (coinbase_transaction.hash_eip191()?, coinbase_transaction),
-    );
+    let coinbase_transaction_hash = coinbase_transaction.hash_eip191()?;
 
-    let block_row = BlockRow {
-        id: SurrealID(Thing::from((
-            "blocks".to_string(),
-            block_header_hash_hex.clone().to_string(),
-        ))),
-        hash: block_header_hash_hex,
-        header: block_header,
-        height: block_number,
-        transactions: transactions.clone(),
-    };
+    transactions.insert(0, (coinbase_transaction_hash, coinbase_transaction));
 
-    db_arc
-        .create::<Vec<BlockRow>>("blocks")
-        .content(block_row.clone())
-        .await?;
+    // the block's coinbase is checked against the reward and
+    // the fees actually collected before the block is digested
+    validate_coinbase_transaction(
+        &transactions[0].1,
+        block_reward,
+        execution_context.collected_fees,
+    )?;
 
-    println!("inserted block {}", block_number);
+    let transaction_hashes = transactions
+        .iter()
+        .map(|(transaction_hash, _)| *transaction_hash)
+        .collect::<Vec<[u8; 32]>>();
 
-    for (transaction_hash, transaction) in transactions {
-        let Transaction::Version1 { outputs, .. } = transaction;
+    // the state tree is updated while digesting the block, so
+    // that the header can commit to the resulting state root
+    let mut state_tree = load_state_tree(db_arc, previous_block_row.as_ref()).await?;
+
+    for (transaction_hash, transaction) in transactions.clone() {
+        let inputs = transaction.inputs();
+        let outputs = transaction.outputs();
 
         let transaction_hash_hex = hex::encode(transaction_hash);
 
+        // the coinbase input does not reference a real output
+        if transaction_hash != coinbase_transaction_hash {
+            let continued_object_ids = outputs
+                .iter()
+                .filter_map(|output| match output {
+                    TransactionOutput::Object {
+                        object_id:
+                            ObjectIdentifier {
+                                raw,
+                                mode: ObjectMode::Existing { .. },
+                            },
+                        ..
+                    } => Some(*raw),
+                    _ => None,
+                })
+                .collect::<Vec<[u8; 32]>>();
+
+            for input in inputs {
+                // an object spent without a continuing output
+                // is destroyed along with its claims
+                if let Some(TransactionOutputRow {
+                    output: TransactionOutput::Object { object_id, .. },
+                    ..
+                }) = fetch_transaction_output_row(db_arc, &input.outpoint).await?
+                {
+                    if !continued_object_ids.contains(&object_id.raw) {
+                        delete_object_claims(db_arc, &mut state_tree, &object_id.raw).await?;
+
+                        db_arc
+                            .query("DELETE FROM object_claim_managers WHERE object_id = $object_id")
+                            .bind(("object_id", hex::encode(object_id.raw)))
+                            .await?;
+
+                        db_arc
+                            .query("DELETE FROM objects WHERE id = $id")
+                            .bind((
+                                "id",
+                                SurrealID(Thing::from((
+                                    "objects".to_string(),
+                                    hex::encode(object_id.raw),
+                                ))),
+                            ))
+                            .await?;
+
+                        update_object_state(db_arc, &mut state_tree, &object_id.raw).await?;
+                    }
+                }
+
+                db_arc
+                    .query(
+                        "UPDATE transaction_outputs SET \
+                           spent = true, \
+                           spending_transaction_hash = $spending_transaction_hash, \
+                           spent_at_height = $spent_at_height \
+                         WHERE id = $id",
+                    )
+                    .bind((
+                        "id",
+                        SurrealID(Thing::from((
+                            "transaction_outputs".to_string(),
+                            format!(
+                                "{}:{}",
+                                hex::encode(input.outpoint.txid),
+                                input.outpoint.index
+                            ),
+                        ))),
+                    ))
+                    .bind(("spending_transaction_hash", transaction_hash_hex.clone()))
+                    .bind(("spent_at_height", block_number))
+                    .await?;
+            }
+        }
+
         for (index, output) in outputs.iter().enumerate() {
             let (output_type, pubkey_script) = match output {
                 TransactionOutput::Object { pubkey_script, .. } => ("Object", pubkey_script),
                 TransactionOutput::Value { pubkey_script, .. } => ("Value", pubkey_script),
             };
 
-            let owner = match &pubkey_script[..] {
-                [TransactionOpCode::Dup, TransactionOpCode::Push { data: address_vec }, TransactionOpCode::EqualVerify, TransactionOpCode::CheckEip191SigVerify] => {
-                    hex::encode(address_vec.as_slice())
-                }
-                _ => "".to_string(),
-            };
+            let owners = extract_pubkey_script_owners(pubkey_script);
 
             db_arc
                 .create::<Vec<TransactionOutputRow>>("transaction_outputs")
@@ -372,8 +1244,12 @@ async fn propose_block(
                     output_index: index.try_into()?,
                     output_type: output_type.to_string(),
                     output: output.clone(),
-                    owner,
+                    owners: owners.clone(),
                     spent: false,
+                    spending_transaction_hash: None,
+                    spent_at_height: None,
+                    block_height: block_number,
+                    coinbase: transaction_hash == coinbase_transaction_hash,
                 })
                 .await?;
 
@@ -383,7 +1259,16 @@ async fn propose_block(
                     data_script,
                     ..
                 } => {
-                    digest_object_output(&db_arc, object_id, data_script).await?;
+                    digest_object_output(
+                        &db_arc,
+                        &mut state_tree,
+                        object_id,
+                        data_script,
+                        &transaction_hash_hex,
+                        index.try_into()?,
+                        &owners,
+                    )
+                    .await?;
                 }
 
                 _ => {}
@@ -404,12 +1289,70 @@ async fn propose_block(
 
     println!("digested block {}", block_number);
 
+    let block_header = BlockHeader::Version2 {
+        previous_block_header_hash,
+        merkle_root: compute_merkle_root(&transaction_hashes),
+        state_root: state_tree.root,
+        timestamp,
+    };
+
+    validate_block_header(
+        &block_header,
+        &transactions
+            .iter()
+            .map(|(_, transaction)| transaction.clone())
+            .collect::<Vec<Transaction>>(),
+    )?;
+
+    let block_header_hash = block_header.hash()?;
+    let block_header_hash_hex = hex::encode(block_header_hash);
+
+    let block_row = BlockRow {
+        id: SurrealID(Thing::from((
+            "blocks".to_string(),
+            block_header_hash_hex.clone().to_string(),
+        ))),
+        hash: block_header_hash_hex,
+        header: block_header,
+        height: block_number,
+        transactions: transactions.clone(),
+    };
+
+    db_arc
+        .create::<Vec<BlockRow>>("blocks")
+        .content(block_row.clone())
+        .await?;
+
+    // signed once here so that certificates for this block can
+    // be issued without signing again
+    let block_certificate = BlockCertificate::sign(
+        block_header_hash,
+        state_tree.root,
+        timestamp,
+        node_signing_key.to_bytes().into(),
+    )?;
+
+    db_arc
+        .create::<Vec<BlockCertificateRow>>("block_certificates")
+        .content(BlockCertificateRow {
+            id: SurrealID(Thing::from((
+                "block_certificates".to_string(),
+                block_row.hash.clone(),
+            ))),
+            block_height: block_number,
+            certificate: block_certificate,
+        })
+        .await?;
+
+    println!("inserted block {}", block_number);
+
     Ok(block_row)
 }
 
 pub struct QuibleRpcServerImpl {
     db: Arc<Surreal<AnyDb>>,
     node_signer_key: [u8; 32],
+    node_config: NodeConfig,
 }
 
 fn format_pending_transaction_row(
@@ -422,6 +1365,13 @@ fn format_pending_transaction_row(
             Some(format!("{}", err.root_cause())),
         )
     })?;
+    let transaction_size = transaction.encoded_size().map_err(|err| {
+        ErrorObjectOwned::owned::<String>(
+            CALL_EXECUTION_FAILED_CODE,
+            "call execution failed: failed to compute transaction size",
+            Some(format!("{}", err.root_cause())),
+        )
+    })?;
     // let transaction_json = serde_json::to_value(&transaction).unwrap();
 
     let transaction_hash_hex = hex::encode(transaction_hash);
@@ -437,10 +1387,21 @@ fn format_pending_transaction_row(
             // hash: surrealdb::sql::Bytes::from(transaction_hash.to_vec()),
             hash: transaction_hash_hex,
 
+            outpoints: transaction
+                .inputs()
+                .iter()
+                .map(|input| {
+                    format!(
+                        "{}:{}",
+                        hex::encode(input.outpoint.txid),
+                        input.outpoint.index
+                    )
+                })
+                .collect(),
+
             data: transaction.clone(),
 
-            // TODO: https://linear.app/quible/issue/QUI-100/track-transaction-sizes-and-only-pull-small-enough-transactions
-            size: 0,
+            size: transaction_size,
         },
     ))
 }
@@ -448,32 +1409,157 @@ fn format_pending_transaction_row(
 #[jsonrpsee_async_trait]
 impl rpc::QuibleRpcServer for QuibleRpcServerImpl {
     async fn send_transaction(&self, transaction: Transaction) -> Result<(), ErrorObjectOwned> {
-        let (_, pending_transaction_row) = format_pending_transaction_row(transaction)?;
+        let (transaction_hash, pending_transaction_row) =
+            format_pending_transaction_row(transaction.clone())?;
 
-        let result: Result<Vec<PendingTransactionRow>, surrealdb::Error> = self
-            .db
-            .create("pending_transactions")
-            .content(pending_transaction_row)
-            .await;
+        if pending_transaction_row.size > MAX_TRANSACTION_SIZE {
+            return Err(ErrorObjectOwned::owned::<String>(
+                CALL_EXECUTION_FAILED_CODE,
+                "call execution failed: transaction exceeds maximum size",
+                None,
+            ));
+        }
 
-        match result {
-            Ok(pending_transaction_rows) => {
-                if pending_transaction_rows.len() == 0 {
-                    Err(ErrorObjectOwned::owned::<String>(
-                        CALL_EXECUTION_FAILED_CODE,
-                        "call execution failed: transaction already inserted",
-                        None,
-                    ))
-                } else {
-                    Ok(())
-                }
+        let admission_error = |err: anyhow::Error| {
+            ErrorObjectOwned::owned(
+                CALL_EXECUTION_FAILED_CODE,
+                "call execution failed: failed to validate transaction",
+                Some(err.to_string()),
+            )
+        };
+
+        let rejection = |error: TransactionValidationError| {
+            ErrorObjectOwned::owned(
+                CALL_EXECUTION_FAILED_CODE,
+                format!("call execution failed: transaction rejected ({})", error),
+                Some(error),
+            )
+        };
+
+        let outcome = simulate_transaction(
+            &self.db,
+            &self.node_config,
+            transaction_hash,
+            transaction.clone(),
+        )
+        .await
+        .map_err(admission_error)?;
+
+        let fee = match outcome {
+            Ok(fee) => fee,
+
+            // the proposer holds back transactions that are not
+            // final yet, so they are allowed to wait in the mempool,
+            // but must be valid in the first block they are final in
+            Err(TransactionValidationError::LocktimeNotReached) => {
+                let (block_height, block_timestamp) = fetch_next_block_position(&self.db)
+                    .await
+                    .map_err(admission_error)?;
+                let (block_height, block_timestamp) =
+                    final_block_position(transaction.locktime(), block_height, block_timestamp);
+
+                simulate_transaction_at(
+                    &self.db,
+                    &self.node_config,
+                    transaction_hash,
+                    transaction.clone(),
+                    block_height,
+                    block_timestamp,
+                )
+                .await
+                .map_err(admission_error)?
+                .map_err(rejection)?
             }
-            Err(error) => Err(ErrorObjectOwned::owned::<String>(
+
+            Err(error) => return Err(rejection(error)),
+        };
+
+        let conflicts = fetch_conflicting_mempool_entries(&self.db, transaction_hash, &transaction)
+            .await
+            .map_err(admission_error)?;
+
+        if !conflicts.is_empty() {
+            if !self.node_config.replace_by_fee {
+                return Err(rejection(TransactionValidationError::MempoolConflict));
+            }
+
+            check_replacement(
+                &MempoolEntry {
+                    transaction_hash,
+                    transaction: transaction.clone(),
+                    fee,
+                    size: pending_transaction_row.size,
+                },
+                &conflicts,
+                self.node_config.minimum_fee_rate,
+            )
+            .map_err(rejection)?;
+        }
+
+        // evicting the conflicts and inserting the transaction are
+        // one database transaction. The unique index on outpoints
+        // fails the insert if a conflicting transaction was
+        // admitted after the conflicts were looked up.
+        let evicted_ids = conflicts
+            .iter()
+            .map(|conflict| {
+                SurrealID(Thing::from((
+                    "pending_transactions".to_string(),
+                    hex::encode(conflict.transaction_hash),
+                )))
+            })
+            .collect::<Vec<SurrealID>>();
+
+        let mut response = self
+            .db
+            .query(
+                "
+                    BEGIN TRANSACTION;
+                    DELETE pending_transactions WHERE id INSIDE $evicted_ids;
+                    CREATE $id CONTENT $transaction;
+                    COMMIT TRANSACTION;
+                ",
+            )
+            .bind(("evicted_ids", evicted_ids))
+            .bind(("id", pending_transaction_row.id.clone()))
+            .bind(("transaction", pending_transaction_row))
+            .await
+            .map_err(|err| admission_error(err.into()))?;
+
+        let errors = response.take_errors();
+        if errors.is_empty() {
+            return Ok(());
+        }
+
+        if errors
+            .values()
+            .any(|error| matches!(error, surrealdb::Error::Db(ErrorDb::IndexExists { .. })))
+        {
+            return Err(rejection(TransactionValidationError::MempoolConflict));
+        }
+
+        if errors
+            .values()
+            .any(|error| matches!(error, surrealdb::Error::Db(ErrorDb::RecordExists { .. })))
+        {
+            return Err(ErrorObjectOwned::owned::<String>(
                 CALL_EXECUTION_FAILED_CODE,
-                "call execution failed: failed to insert",
-                Some(error.to_string()),
-            )),
+                "call execution failed: transaction already inserted",
+                None,
+            ));
         }
+
+        Err(ErrorObjectOwned::owned::<String>(
+            CALL_EXECUTION_FAILED_CODE,
+            "call execution failed: failed to insert",
+            Some(
+                errors
+                    .values()
+                    .map(|error| error.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", "),
+            ),
+        ))
     }
 
     async fn send_raw_transaction(&self, raw_transaction: String) -> Result<(), ErrorObjectOwned> {
@@ -498,6 +1584,53 @@ impl rpc::QuibleRpcServer for QuibleRpcServerImpl {
         self.send_transaction(transaction).await
     }
 
+    async fn simulate_transaction(
+        &self,
+        transaction: Transaction,
+    ) -> Result<TransactionSimulationPayload, ErrorObjectOwned> {
+        let (transaction_hash, _) = format_pending_transaction_row(transaction.clone())?;
+
+        let outcome = simulate_transaction(
+            &self.db,
+            &self.node_config,
+            transaction_hash,
+            transaction.clone(),
+        )
+        .await
+        .map_err(|err| {
+            ErrorObjectOwned::owned(
+                CALL_EXECUTION_FAILED_CODE,
+                "call execution failed: failed to simulate transaction",
+                Some(err.to_string()),
+            )
+        })?;
+
+        Ok(match outcome {
+            Ok(fee) => TransactionSimulationPayload {
+                transaction_hash,
+                success: true,
+                fee: Some(fee),
+                object_changes: summarize_object_changes(&transaction),
+                error: None,
+            },
+
+            Err(error) => TransactionSimulationPayload {
+                transaction_hash,
+                success: false,
+                fee: None,
+                object_changes: vec![],
+                error: Some(error),
+            },
+        })
+    }
+
+    async fn get_transaction_typed_data(
+        &self,
+        transaction: Transaction,
+    ) -> Result<serde_json::Value, ErrorObjectOwned> {
+        Ok(transaction_typed_data(&transaction))
+    }
+
     async fn check_health(&self) -> Result<types::HealthCheckResponse, ErrorObjectOwned> {
         Ok(HealthCheckResponse {
             status: "healthy".to_string(),
@@ -508,68 +1641,93 @@ impl rpc::QuibleRpcServer for QuibleRpcServerImpl {
         &self,
         object_id: [u8; 32],
         claim: Vec<u8>,
-    ) -> Result<SignedCertificate, ErrorObjectOwned> {
-        let object_id_hex = hex::encode(object_id);
-        let surreal_object_id = SurrealID(Thing::from((
-            "objects".to_string(),
-            object_id_hex.to_string(),
-        )));
-
-        let result = self
-            .db
-            .query("SELECT object_id FROM objects WHERE id = $id AND claims CONTAINS $value")
-            .bind(("id", surreal_object_id))
-            .bind(("value", surrealdb::sql::Bytes::from(claim.clone())))
-            .await;
+        requested_at_block_height: Option<u64>,
+        include_proof: Option<bool>,
+    ) -> Result<Certificate, ErrorObjectOwned> {
+        let (block_row, block_hash) =
+            fetch_certificate_block(self, requested_at_block_height).await?;
+
+        // membership and the TTL are read as of the block the
+        // certificate is anchored to, not the current rows
+        let (object_state, claim_present) =
+            fetch_certificate_state(self, &block_row, object_id, &claim).await?;
+
+        let Some(object_state) = object_state.filter(|_| claim_present) else {
+            return Err(ErrorObjectOwned::owned(
+                CALL_EXECUTION_FAILED_CODE,
+                "call execution failed: could not find identity or claim",
+                None as Option<String>,
+            ));
+        };
 
-        let validity: Option<String> = result
-            .and_then(|mut response| response.take((0, "object_id")))
-            .map_err(|err| {
-                ErrorObjectOwned::owned(
-                    CALL_EXECUTION_FAILED_CODE,
-                    "call execution failed: database query error",
-                    Some(err.to_string()),
-                )
-            })?;
+        let object_row = fetch_certificate_object(self, object_id, object_state).await?;
 
-        validity.ok_or(ErrorObjectOwned::owned(
-            CALL_EXECUTION_FAILED_CODE,
-            "call execution failed: could not find identity or claim",
-            None as Option<String>,
-        ))?;
+        if include_proof.unwrap_or(false) {
+            return issue_proof_certificate(self, object_id, claim, object_row, block_row.height)
+                .await
+                .map(|certificate| Certificate::Proof(Box::new(certificate)));
+        }
 
-        let details = CertificateSigningRequestDetails {
+        // certificates are anchored to a block, and expire
+        // cert_ttl seconds after that block's timestamp
+        let details = CertificateSigningRequestDetails::Membership {
             object_id,
             claim,
-
-            // TODO: https://linear.app/quible/issue/QUI-107/generate-expiration-dates
-            expires_at: std::u64::MAX,
+            block_height: block_row.height,
+            block_hash,
+            expires_at: block_row
+                .header
+                .timestamp()
+                .saturating_add(object_row.cert_ttl),
         };
 
-        let hash = details.hash().map_err(|err| {
-            ErrorObjectOwned::owned::<String>(
+        sign_certificate(self, details).map(Certificate::Signed)
+    }
+
+    async fn request_non_membership_certificate(
+        &self,
+        object_id: [u8; 32],
+        claim: Vec<u8>,
+        requested_at_block_height: Option<u64>,
+    ) -> Result<SignedCertificate, ErrorObjectOwned> {
+        let (block_row, block_hash) =
+            fetch_certificate_block(self, requested_at_block_height).await?;
+
+        // the claim set is checked as of the block the
+        // certificate is anchored to
+        let (object_state, claim_present) =
+            fetch_certificate_state(self, &block_row, object_id, &claim).await?;
+
+        let Some(object_state) = object_state else {
+            return Err(ErrorObjectOwned::owned(
                 CALL_EXECUTION_FAILED_CODE,
-                "call execution failed: failed to sign",
-                Some(err.to_string()),
-            )
-        })?;
+                "call execution failed: could not find identity",
+                None as Option<String>,
+            ));
+        };
 
-        let signature_raw = sign_message(
-            B256::from_slice(&self.node_signer_key),
-            FixedBytes::new(hash),
-        )
-        .map_err(|err| {
-            ErrorObjectOwned::owned::<String>(
+        if claim_present {
+            return Err(ErrorObjectOwned::owned(
                 CALL_EXECUTION_FAILED_CODE,
-                "call execution failed: failed to sign",
-                Some(err.to_string()),
-            )
-        })?;
+                "call execution failed: claim is present in identity",
+                None as Option<String>,
+            ));
+        }
 
-        Ok(SignedCertificate {
-            details,
-            signature: QuibleSignature { raw: signature_raw },
-        })
+        let object_row = fetch_certificate_object(self, object_id, object_state).await?;
+
+        let details = CertificateSigningRequestDetails::NonMembership {
+            object_id,
+            excluded_claim: claim,
+            block_height: block_row.height,
+            block_hash,
+            expires_at: block_row
+                .header
+                .timestamp()
+                .saturating_add(object_row.cert_ttl),
+        };
+
+        sign_certificate(self, details)
     }
 
     async fn fetch_unspent_value_outputs_by_owner(
@@ -578,7 +1736,7 @@ impl rpc::QuibleRpcServer for QuibleRpcServerImpl {
     ) -> Result<ValueOutputsPayload, ErrorObjectOwned> {
         let owner_address_hex = hex::encode(owner_address);
         let result = self.db
-            .query("SELECT * FROM transaction_outputs WHERE owner = $owner AND spent = false AND output_type = \"Value\"")
+            .query("SELECT * FROM transaction_outputs WHERE owners CONTAINS $owner AND spent = false AND output_type = \"Value\"")
             .bind(("owner", owner_address_hex))
             .await;
 
@@ -728,6 +1886,19 @@ impl rpc::QuibleRpcServer for QuibleRpcServerImpl {
         })
     }
 
+    async fn get_block_reward(
+        &self,
+        height_payload: BlockHeightPayload,
+    ) -> Result<BlockRewardPayload, ErrorObjectOwned> {
+        let reward_schedule = &self.node_config.reward_schedule;
+
+        Ok(BlockRewardPayload {
+            height: height_payload.height,
+            reward: reward_schedule.block_reward(height_payload.height),
+            supply_before: reward_schedule.supply_before(height_payload.height),
+        })
+    }
+
     async fn get_block_by_height(
         &self,
         height_payload: BlockHeightPayload,
@@ -772,94 +1943,384 @@ impl rpc::QuibleRpcServer for QuibleRpcServerImpl {
 
         Ok(block_details)
     }
-}
-
-async fn generate_intermediate_faucet_output(
-    server: &QuibleRpcServerImpl,
-) -> Result<(), ErrorObjectOwned> {
-    let temporary_owner_signing_key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
-    let temporary_owner_signing_key_hex = hex::encode(temporary_owner_signing_key.to_bytes());
-    let temporary_owner_address = Address::from_private_key(&temporary_owner_signing_key);
 
-    // TODO: https://linear.app/quible/issue/QUI-111/refactor-db-schema-and-signing-key-usage
-    //
-    // parsing the signing key like this should happen
-    // before the QuibleRpcServerImpl is instantiated
-    let node_signing_key = SigningKey::from_slice(&server.node_signer_key).map_err(|err| {
-        ErrorObjectOwned::owned(
-            CALL_EXECUTION_FAILED_CODE,
-            "call execution failed: failed to parse node signer key",
-            Some(err.to_string()),
-        )
-    })?;
+    async fn get_transaction_inclusion_proof(
+        &self,
+        transaction_hash: [u8; 32],
+    ) -> Result<TransactionInclusionProofPayload, ErrorObjectOwned> {
+        let Some(block_row): Option<BlockRow> = self
+            .db
+            .query(
+                "SELECT * FROM blocks WHERE transactions[*][0] CONTAINS $transaction_hash LIMIT 1",
+            )
+            .bind(("transaction_hash", transaction_hash))
+            .await
+            .and_then(|mut response| response.take(0))
+            .map_err(|err| {
+                ErrorObjectOwned::owned(
+                    CALL_EXECUTION_FAILED_CODE,
+                    "call execution failed: database query error",
+                    Some(err.to_string()),
+                )
+            })?
+        else {
+            return Err(ErrorObjectOwned::owned(
+                CALL_EXECUTION_FAILED_CODE,
+                "call execution failed: transaction is not included in any block",
+                None as Option<String>,
+            ));
+        };
 
-    let owner_address = Address::from_private_key(&node_signing_key);
-    let owner_address_hex = hex::encode(owner_address);
-    let result = server
-        .db
-        .query(
-            "\n\
-                SELECT * FROM transaction_outputs\n\
-                WHERE owner = $owner\n\
-                AND spent = false\n\
-                AND output_type = \"Value\"\n\
-                AND count(<-spending<-intermediate_faucet_outputs) = 0
-                LIMIT 1",
-        )
-        .bind(("owner", owner_address_hex))
-        .await;
+        let transaction_hashes = block_row
+            .transactions
+            .iter()
+            .map(|(transaction_hash, _)| *transaction_hash)
+            .collect::<Vec<[u8; 32]>>();
 
-    let output_rows: Vec<TransactionOutputRow> = result
-        .and_then(|mut response| response.take(0))
-        .map_err(|err| {
-        ErrorObjectOwned::owned(
-            CALL_EXECUTION_FAILED_CODE,
-            "call execution failed: failed to fetch unspent value outputs",
-            Some(err.to_string()),
-        )
-    })?;
+        let proof = transaction_hashes
+            .iter()
+            .position(|included_hash| included_hash == &transaction_hash)
+            .and_then(|index| compute_merkle_proof(&transaction_hashes, index))
+            .ok_or(ErrorObjectOwned::owned(
+                CALL_EXECUTION_FAILED_CODE,
+                "call execution failed: transaction is not included in any block",
+                None as Option<String>,
+            ))?;
 
-    let missing_output_err = ErrorObjectOwned::owned(
-        CALL_EXECUTION_FAILED_CODE,
-        "call execution failed: no value outputs available",
-        None as Option<String>,
-    );
+        let mut block_hash = [0u8; 32];
 
-    let (origin_outpoint, origin_output) = match &output_rows[..] {
-        [row, ..] => {
-            let mut transaction_hash = [0u8; 32];
-            hex::decode_to_slice(row.transaction_hash.clone(), &mut transaction_hash).map_err(
-                |err| {
-                    ErrorObjectOwned::owned(
-                        CALL_EXECUTION_FAILED_CODE,
-                        "call execution failed: failed to decode transaction hash hex",
-                        Some(err.to_string()),
-                    )
-                },
-            )?;
+        hex::decode_to_slice(block_row.hash, &mut block_hash).map_err(|err| {
+            ErrorObjectOwned::owned(
+                CALL_EXECUTION_FAILED_CODE,
+                "call execution failed: failed to decode block hash",
+                Some(err.to_string()),
+            )
+        })?;
 
-            let outpoint = TransactionOutpoint {
-                txid: transaction_hash,
-                index: row.output_index,
-            };
+        Ok(TransactionInclusionProofPayload {
+            transaction_hash,
+            block_hash,
+            block_height: block_row.height,
+            merkle_root: block_row.header.merkle_root(),
+            proof,
+        })
+    }
 
-            Ok((outpoint, row.output.clone()))
-        }
+    async fn get_block_certificate(
+        &self,
+        block_height: Option<u64>,
+    ) -> Result<BlockCertificate, ErrorObjectOwned> {
+        let certificate_query = match block_height {
+            Some(height) => self
+                .db
+                .query("SELECT * FROM block_certificates WHERE block_height = $height LIMIT 1")
+                .bind(("height", height)),
+            None => self
+                .db
+                .query("SELECT * FROM block_certificates ORDER BY block_height DESC LIMIT 1"),
+        };
 
-        _ => Err(missing_output_err.clone()),
-    }?;
+        let Some(certificate_row): Option<BlockCertificateRow> = certificate_query
+            .await
+            .and_then(|mut response| response.take(0))
+            .map_err(|err| {
+                ErrorObjectOwned::owned(
+                    CALL_EXECUTION_FAILED_CODE,
+                    "call execution failed: database query error",
+                    Some(err.to_string()),
+                )
+            })?
+        else {
+            return Err(ErrorObjectOwned::owned(
+                CALL_EXECUTION_FAILED_CODE,
+                "call execution failed: no block certificate",
+                None as Option<String>,
+            ));
+        };
 
-    let TransactionOutput::Value { value, .. } = origin_output else {
-        return Err(missing_output_err);
-    };
+        Ok(certificate_row.certificate)
+    }
 
-    let unsigned_intermediate_faucet_transaction_inputs = vec![TransactionInput {
+    async fn get_transaction_rejection(
+        &self,
+        transaction_hash: [u8; 32],
+    ) -> Result<TransactionRejectionPayload, ErrorObjectOwned> {
+        let Some(rejection_row): Option<TransactionRejectionRow> = self
+            .db
+            .select(("transaction_rejections", hex::encode(transaction_hash)))
+            .await
+            .map_err(|err| {
+                ErrorObjectOwned::owned(
+                    CALL_EXECUTION_FAILED_CODE,
+                    "call execution failed: database query error",
+                    Some(err.to_string()),
+                )
+            })?
+        else {
+            return Err(ErrorObjectOwned::owned(
+                CALL_EXECUTION_FAILED_CODE,
+                "call execution failed: no rejection recorded for transaction",
+                None as Option<String>,
+            ));
+        };
+
+        Ok(TransactionRejectionPayload {
+            transaction_hash,
+            code: rejection_row.code,
+            message: rejection_row.message,
+            error: rejection_row.error,
+            block_height: rejection_row.block_height,
+        })
+    }
+}
+
+// the object's leaf as of the given block, and whether the
+// claim was in its claim set
+async fn fetch_certificate_state(
+    server: &QuibleRpcServerImpl,
+    block_row: &BlockRow,
+    object_id: [u8; 32],
+    claim: &[u8],
+) -> Result<(Option<[u8; 32]>, bool), ErrorObjectOwned> {
+    let state = async {
+        // a block without a state root has its tree rebuilt from
+        // the current rows, which only describe the latest block
+        if block_row.header.state_root().is_none() {
+            let latest_height: Option<u64> = server
+                .db
+                .query("SELECT VALUE height FROM blocks ORDER BY height DESC LIMIT 1")
+                .await?
+                .take(0)?;
+
+            if latest_height != Some(block_row.height) {
+                return anyhow::Ok(None);
+            }
+        }
+
+        let mut state_tree = load_state_tree(&server.db, Some(block_row)).await?;
+        let object_state = state_tree.get(&object_state_key(&object_id)).await?;
+        let claim_state = state_tree.get(&claim_state_key(&object_id, claim)).await?;
+
+        anyhow::Ok(Some((object_state, claim_state.is_some())))
+    };
+
+    state
+        .await
+        .map_err(|err| {
+            ErrorObjectOwned::owned(
+                CALL_EXECUTION_FAILED_CODE,
+                "call execution failed: failed to load state",
+                Some(err.to_string()),
+            )
+        })?
+        .ok_or(ErrorObjectOwned::owned(
+            CALL_EXECUTION_FAILED_CODE,
+            "call execution failed: no state for the requested block",
+            None as Option<String>,
+        ))
+}
+
+// the object row the given leaf commits to. The leaf is only
+// a hash, so the row is read back from object_states.
+async fn fetch_certificate_object(
+    server: &QuibleRpcServerImpl,
+    object_id: [u8; 32],
+    object_state: [u8; 32],
+) -> Result<ObjectRow, ErrorObjectOwned> {
+    let object_rows = async {
+        let object_state_row: Option<ObjectRow> = server
+            .db
+            .select(("object_states", hex::encode(object_state)))
+            .await?;
+
+        // leaves written before object_states existed are
+        // only known while they are the object's current row
+        let object_row: Option<ObjectRow> = match object_state_row {
+            Some(object_state_row) => Some(object_state_row),
+            None => {
+                server
+                    .db
+                    .select(("objects", hex::encode(object_id)))
+                    .await?
+            }
+        };
+
+        surrealdb::Result::Ok(object_row)
+    };
+
+    let object_row = object_rows.await.map_err(|err| {
+        ErrorObjectOwned::owned(
+            CALL_EXECUTION_FAILED_CODE,
+            "call execution failed: database query error",
+            Some(err.to_string()),
+        )
+    })?;
+
+    let object_row = object_row.filter(|object_row| {
+        decode_hash(&object_row.transaction_hash).is_ok_and(|transaction_hash| {
+            object_state_value(
+                &transaction_hash,
+                object_row.output_index,
+                object_row.cert_ttl,
+            ) == object_state
+        })
+    });
+
+    object_row.ok_or(ErrorObjectOwned::owned(
+        CALL_EXECUTION_FAILED_CODE,
+        "call execution failed: identity has changed since the requested block",
+        None as Option<String>,
+    ))
+}
+
+// block a certificate is anchored to, and its hash
+async fn fetch_certificate_block(
+    server: &QuibleRpcServerImpl,
+    requested_at_block_height: Option<u64>,
+) -> Result<(BlockRow, [u8; 32]), ErrorObjectOwned> {
+    let block_query = match requested_at_block_height {
+        Some(height) => server
+            .db
+            .query("SELECT * FROM blocks WHERE height = $height LIMIT 1")
+            .bind(("height", height)),
+        None => server
+            .db
+            .query("SELECT * FROM blocks ORDER BY height DESC LIMIT 1"),
+    };
+
+    let Some(block_row): Option<BlockRow> = block_query
+        .await
+        .and_then(|mut response| response.take(0))
+        .map_err(|err| {
+            ErrorObjectOwned::owned(
+                CALL_EXECUTION_FAILED_CODE,
+                "call execution failed: database query error",
+                Some(err.to_string()),
+            )
+        })?
+    else {
+        return Err(ErrorObjectOwned::owned(
+            CALL_EXECUTION_FAILED_CODE,
+            match requested_at_block_height {
+                Some(_) => "call execution failed: unknown block height",
+                None => "call execution failed: no blocks",
+            },
+            None as Option<String>,
+        ));
+    };
+
+    let block_hash = block_row.header.hash().map_err(|err| {
+        ErrorObjectOwned::owned::<String>(
+            CALL_EXECUTION_FAILED_CODE,
+            "call execution failed: failed to hash block header",
+            Some(err.to_string()),
+        )
+    })?;
+
+    Ok((block_row, block_hash))
+}
+
+fn sign_certificate(
+    server: &QuibleRpcServerImpl,
+    details: CertificateSigningRequestDetails,
+) -> Result<SignedCertificate, ErrorObjectOwned> {
+    let signature = details.sign(server.node_signer_key).map_err(|err| {
+        ErrorObjectOwned::owned::<String>(
+            CALL_EXECUTION_FAILED_CODE,
+            "call execution failed: failed to sign",
+            Some(err.to_string()),
+        )
+    })?;
+
+    Ok(SignedCertificate { details, signature })
+}
+
+// proves the object and claim against the state root signed
+// by the block certificate, so no signature is made here. The
+// object row must match the object's leaf as of the block.
+async fn issue_proof_certificate(
+    server: &QuibleRpcServerImpl,
+    object_id: [u8; 32],
+    claim: Vec<u8>,
+    object_row: ObjectRow,
+    block_height: u64,
+) -> Result<ProofCertificate, ErrorObjectOwned> {
+    let block_certificate = server.get_block_certificate(Some(block_height)).await?;
+
+    let transaction_hash = decode_hash(&object_row.transaction_hash).map_err(|err| {
+        ErrorObjectOwned::owned(
+            CALL_EXECUTION_FAILED_CODE,
+            "call execution failed: failed to decode transaction hash",
+            Some(err.to_string()),
+        )
+    })?;
+
+    let mut state_tree = StateTree::new(
+        QuibleStateNodeStoreImpl {
+            db: server.db.clone(),
+        },
+        block_certificate.state_root,
+    );
+
+    let proofs = async {
+        let object_proof = state_tree.prove(&object_state_key(&object_id)).await?;
+        let claim_proof = state_tree
+            .prove(&claim_state_key(&object_id, &claim))
+            .await?;
+
+        anyhow::Ok((object_proof, claim_proof))
+    };
+
+    let (object_proof, claim_proof) = proofs.await.map_err(|err| {
+        ErrorObjectOwned::owned(
+            CALL_EXECUTION_FAILED_CODE,
+            "call execution failed: failed to prove state",
+            Some(err.to_string()),
+        )
+    })?;
+
+    let certificate = ProofCertificate {
+        block_certificate,
+        object_id,
+        claim,
+        lifespan: object_row.cert_ttl,
+        transaction_hash,
+        output_index: object_row.output_index,
+        object_proof,
+        claim_proof,
+    };
+
+    // the object or claim may have changed since the block
+    // the certificate is anchored to
+    if !cert::verify_proof_certificate_state(&certificate) {
+        return Err(ErrorObjectOwned::owned(
+            CALL_EXECUTION_FAILED_CODE,
+            "call execution failed: could not find identity or claim at the requested block",
+            None as Option<String>,
+        ));
+    }
+
+    Ok(certificate)
+}
+
+// transaction moving the origin output to a temporary owner,
+// signed by the node
+fn sign_intermediate_faucet_transaction(
+    server: &QuibleRpcServerImpl,
+    node_signing_key: &SigningKey,
+    origin_outpoint: &TransactionOutpoint,
+    temporary_owner_address: Address,
+    faucet_value: u64,
+) -> Result<Transaction, ErrorObjectOwned> {
+    let owner_address = Address::from_private_key(node_signing_key);
+
+    let unsigned_intermediate_faucet_transaction_inputs = vec![TransactionInput {
         outpoint: origin_outpoint.clone(),
         signature_script: vec![],
     }];
 
     let unsigned_intermediate_faucet_transaction_outputs = vec![TransactionOutput::Value {
-        value,
+        value: faucet_value,
         pubkey_script: vec![
             TransactionOpCode::Dup,
             TransactionOpCode::Push {
@@ -870,7 +2331,8 @@ async fn generate_intermediate_faucet_output(
         ],
     }];
 
-    let unsigned_intermediate_faucet_transaction = Transaction::Version1 {
+    let unsigned_intermediate_faucet_transaction = Transaction::Version2 {
+        chain_id: server.node_config.chain_id,
         inputs: unsigned_intermediate_faucet_transaction_inputs.clone(),
         outputs: unsigned_intermediate_faucet_transaction_outputs.clone(),
         locktime: 0,
@@ -899,7 +2361,8 @@ async fn generate_intermediate_faucet_output(
     })?
     .to_vec();
 
-    let signed_intermediate_faucet_transaction = Transaction::Version1 {
+    let signed_intermediate_faucet_transaction = Transaction::Version2 {
+        chain_id: server.node_config.chain_id,
         inputs: unsigned_intermediate_faucet_transaction_inputs
             .iter()
             .map(|input| TransactionInput {
@@ -918,6 +2381,141 @@ async fn generate_intermediate_faucet_output(
         locktime: 0,
     };
 
+    Ok(signed_intermediate_faucet_transaction)
+}
+
+async fn generate_intermediate_faucet_output(
+    server: &QuibleRpcServerImpl,
+) -> Result<(), ErrorObjectOwned> {
+    let temporary_owner_signing_key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+    let temporary_owner_signing_key_hex = hex::encode(temporary_owner_signing_key.to_bytes());
+    let temporary_owner_address = Address::from_private_key(&temporary_owner_signing_key);
+
+    // TODO: https://linear.app/quible/issue/QUI-111/refactor-db-schema-and-signing-key-usage
+    //
+    // parsing the signing key like this should happen
+    // before the QuibleRpcServerImpl is instantiated
+    let node_signing_key = SigningKey::from_slice(&server.node_signer_key).map_err(|err| {
+        ErrorObjectOwned::owned(
+            CALL_EXECUTION_FAILED_CODE,
+            "call execution failed: failed to parse node signer key",
+            Some(err.to_string()),
+        )
+    })?;
+
+    let owner_address = Address::from_private_key(&node_signing_key);
+    let owner_address_hex = hex::encode(owner_address);
+
+    // the node's signature alone can only spend outputs locked
+    // to its own address, not multisig outputs it is part of
+    let owner_pubkey_script = vec![
+        TransactionOpCode::Dup,
+        TransactionOpCode::Push {
+            data: owner_address.into_array().to_vec(),
+        },
+        TransactionOpCode::EqualVerify,
+        TransactionOpCode::CheckEip191SigVerify,
+    ];
+
+    // the faucet transaction is included in the next block at
+    // the earliest, so coinbase outputs must be mature by then
+    let (next_block_height, _) = fetch_next_block_position(&server.db).await.map_err(|err| {
+        ErrorObjectOwned::owned(
+            CALL_EXECUTION_FAILED_CODE,
+            "call execution failed: database query error",
+            Some(err.to_string()),
+        )
+    })?;
+
+    let result = server
+        .db
+        .query(
+            "\n\
+                SELECT * FROM transaction_outputs\n\
+                WHERE owners CONTAINS $owner\n\
+                AND spent = false\n\
+                AND output_type = \"Value\"\n\
+                AND output.Value.pubkey_script = $pubkey_script\n\
+                AND (coinbase = false OR block_height + $coinbase_maturity <= $next_block_height)\n\
+                AND count(<-spending<-intermediate_faucet_outputs) = 0
+                LIMIT 1",
+        )
+        .bind(("owner", owner_address_hex))
+        .bind(("pubkey_script", owner_pubkey_script))
+        .bind(("coinbase_maturity", server.node_config.coinbase_maturity))
+        .bind(("next_block_height", next_block_height))
+        .await;
+
+    let output_rows: Vec<TransactionOutputRow> = result
+        .and_then(|mut response| response.take(0))
+        .map_err(|err| {
+        ErrorObjectOwned::owned(
+            CALL_EXECUTION_FAILED_CODE,
+            "call execution failed: failed to fetch unspent value outputs",
+            Some(err.to_string()),
+        )
+    })?;
+
+    let missing_output_err = ErrorObjectOwned::owned(
+        CALL_EXECUTION_FAILED_CODE,
+        "call execution failed: no value outputs available",
+        None as Option<String>,
+    );
+
+    let (origin_outpoint, origin_output) = match &output_rows[..] {
+        [row, ..] => {
+            let mut transaction_hash = [0u8; 32];
+            hex::decode_to_slice(row.transaction_hash.clone(), &mut transaction_hash).map_err(
+                |err| {
+                    ErrorObjectOwned::owned(
+                        CALL_EXECUTION_FAILED_CODE,
+                        "call execution failed: failed to decode transaction hash hex",
+                        Some(err.to_string()),
+                    )
+                },
+            )?;
+
+            let outpoint = TransactionOutpoint {
+                txid: transaction_hash,
+                index: row.output_index,
+            };
+
+            Ok((outpoint, row.output.clone()))
+        }
+
+        _ => Err(missing_output_err.clone()),
+    }?;
+
+    let TransactionOutput::Value { value, .. } = origin_output else {
+        return Err(missing_output_err);
+    };
+
+    let sign_with_value = |faucet_value| {
+        sign_intermediate_faucet_transaction(
+            server,
+            &node_signing_key,
+            &origin_outpoint,
+            temporary_owner_address,
+            faucet_value,
+        )
+    };
+
+    // the fee is paid out of the origin output. Postcard encodes
+    // the value as a varint, so the transaction only shrinks once
+    // the fee is taken out of it.
+    let (_, full_value_transaction_row) = format_pending_transaction_row(sign_with_value(value)?)?;
+    let faucet_value = full_value_transaction_row
+        .size
+        .checked_mul(server.node_config.minimum_fee_rate)
+        .and_then(|fee| value.checked_sub(fee))
+        .ok_or(ErrorObjectOwned::owned(
+            CALL_EXECUTION_FAILED_CODE,
+            "call execution failed: value output cannot cover the faucet fee",
+            None as Option<String>,
+        ))?;
+
+    let signed_intermediate_faucet_transaction = sign_with_value(faucet_value)?;
+
     let (_, signed_intermediate_faucet_transaction_row) =
         format_pending_transaction_row(signed_intermediate_faucet_transaction)?;
 
@@ -985,6 +2583,7 @@ async fn run_derive_server(
     node_signer_key: [u8; 32],
     db: &Arc<Surreal<AnyDb>>,
     port: u16,
+    node_config: &NodeConfig,
 ) -> anyhow::Result<SocketAddr> {
     let cors = CorsLayer::new()
         // Allow `POST` when accessing the resource
@@ -1004,6 +2603,7 @@ async fn run_derive_server(
         QuibleRpcServerImpl {
             db: db.clone(),
             node_signer_key,
+            node_config: node_config.clone(),
         }
         .into_rpc(),
     );
@@ -1048,17 +2648,51 @@ async fn main() -> anyhow::Result<()> {
 
     let leader_addr = env::var("QUIBLE_LEADER_MULTIADDR").ok();
 
+    let node_config = NodeConfig {
+        minimum_fee_rate: env::var("QUIBLE_MIN_FEE_RATE")
+            .unwrap_or_else(|_| "0".to_owned())
+            .parse()?,
+        replace_by_fee: env::var("QUIBLE_REPLACE_BY_FEE")
+            .unwrap_or_else(|_| "true".to_owned())
+            .parse()?,
+        chain_id: env::var("QUIBLE_CHAIN_ID")
+            .unwrap_or_else(|_| "0".to_owned())
+            .parse()?,
+        chain_id_activation_height: env::var("QUIBLE_CHAIN_ID_ACTIVATION_HEIGHT")
+            .ok()
+            .map(|height| height.parse())
+            .transpose()?,
+        reward_schedule: RewardSchedule {
+            initial_reward: env::var("QUIBLE_INITIAL_BLOCK_REWARD")
+                .unwrap_or_else(|_| "5".to_owned())
+                .parse()?,
+            halving_interval: env::var("QUIBLE_REWARD_HALVING_INTERVAL")
+                .unwrap_or_else(|_| "0".to_owned())
+                .parse()?,
+            supply_cap: env::var("QUIBLE_SUPPLY_CAP")
+                .ok()
+                .map(|supply_cap| supply_cap.parse())
+                .transpose()?,
+        },
+        coinbase_maturity: env::var("QUIBLE_COINBASE_MATURITY")
+            .unwrap_or_else(|_| "0".to_owned())
+            .parse()?,
+        ..Default::default()
+    };
+
     // surrealdb init
     let db = any::connect(endpoint).await?;
     db.use_ns("quible").use_db("quible_node").await?;
     db::schema::initialize_db(&db).await?;
+    db::migrations::migrate_legacy_rows(&db).await?;
 
     if let None = leader_addr {
         db::schema::initialize_tracker_db(&db).await?;
     }
 
     let db_arc = Arc::new(db);
-    let server_addr = run_derive_server(signing_key_decoded, &db_arc, rpc_port).await?;
+    let server_addr =
+        run_derive_server(signing_key_decoded, &db_arc, rpc_port, &node_config).await?;
     let url = format!("http://{}", server_addr);
     println!("server listening at {}", url);
 
@@ -1101,7 +2735,7 @@ async fn main() -> anyhow::Result<()> {
         _ => {}
     }
 
-    let result = propose_block(&db_arc, &signing_key).await;
+    let result = propose_block(&db_arc, &signing_key, &node_config).await;
 
     if let Err(e) = result {
         eprintln!("Error in propose_block: {:#?}", e);
@@ -1110,6 +2744,7 @@ async fn main() -> anyhow::Result<()> {
     generate_intermediate_faucet_output(&QuibleRpcServerImpl {
         db: db_arc.clone(),
         node_signer_key: signing_key_decoded,
+        node_config: node_config.clone(),
     })
     .await?;
 
@@ -1118,7 +2753,7 @@ async fn main() -> anyhow::Result<()> {
             _ = sleep_until(block_timestamp + SLOT_DURATION) => {
                 block_timestamp = block_timestamp + SLOT_DURATION;
 
-                let result = propose_block(&db_arc, &signing_key).await;
+                let result = propose_block(&db_arc, &signing_key, &node_config).await;
 
                 if let Err(e) = result {
                     eprintln!("Error in propose_block: {:#?}", e);
@@ -1165,21 +2800,38 @@ async fn main() -> anyhow::Result<()> {
 #[cfg(test)]
 mod tests {
     use super::{db, run_derive_server};
-    use crate::db::types::{BlockRow, ObjectRow, PendingTransactionRow};
+    use crate::cert::types::{Certificate, CertificateSigningRequestDetails};
+    use crate::cert::verify_proof_certificate;
+    use crate::db::types::{
+        BlockRow, ObjectClaimRow, ObjectRow, PendingTransactionRow, TransactionOutputRow,
+    };
     use crate::quible_ecdsa_utils::{recover_signer_unchecked, sign_message};
     use crate::rpc::QuibleRpcClient;
-    use crate::tx::engine::compute_object_id;
+    use crate::state::tree::{verify_state_proof, EMPTY_ROOT};
+    use crate::state::{claim_state_key, claim_state_value, object_state_key, object_state_value};
+    use crate::tx::eip712::hash_typed_data;
+    use crate::tx::engine::{compute_object_id, validate_block_header};
+    use crate::tx::error::TransactionValidationError;
+    use crate::tx::merkle::verify_merkle_proof;
+    use crate::tx::reward::RewardSchedule;
     use crate::tx::types::{
         Hashable, ObjectIdentifier, ObjectMode, Transaction, TransactionInput, TransactionOpCode,
         TransactionOutpoint, TransactionOutput,
     };
-    use crate::{generate_intermediate_faucet_output, propose_block, QuibleRpcServerImpl};
+    use crate::types::{BlockHeightPayload, ObjectChangeEntry};
+    use crate::{
+        fetch_confirmed_object_claim_managers, fetch_transaction_output_row,
+        format_pending_transaction_row, generate_intermediate_faucet_output, load_state_tree,
+        propose_block, NodeConfig, QuibleRpcServerImpl,
+    };
     use alloy_primitives::{Address, B256};
     use anyhow::anyhow;
     use jsonrpsee::http_client::HttpClient;
     use k256::ecdsa::SigningKey;
     use std::sync::Arc;
     use surrealdb::engine::any;
+    use surrealdb::engine::any::Any as AnyDb;
+    use surrealdb::Surreal;
 
     #[tokio::test]
     async fn test_send_transaction() -> anyhow::Result<()> {
@@ -1194,6 +2846,7 @@ mod tests {
             hex_literal::hex!("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"),
             &db_arc,
             0,
+            &NodeConfig::default(),
         )
         .await?;
         let url = format!("http://{}", server_addr);
@@ -1242,11 +2895,12 @@ mod tests {
             hex_literal::hex!("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80");
         let node_signing_key = SigningKey::from_slice(&node_signing_key_bytes)?;
 
-        let server_addr = run_derive_server(node_signing_key_bytes, &db_arc, 0).await?;
+        let server_addr =
+            run_derive_server(node_signing_key_bytes, &db_arc, 0, &NodeConfig::default()).await?;
         let url = format!("http://{}", server_addr);
         println!("server listening at {}", url);
 
-        propose_block(&db_arc, &node_signing_key).await?;
+        propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
 
         // Query pending transactions from SurrealDB
         let block_rows: Vec<BlockRow> = db_arc.select("blocks").await?;
@@ -1276,7 +2930,8 @@ mod tests {
             hex_literal::hex!("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80");
         let node_signing_key = SigningKey::from_slice(&node_signing_key_bytes)?;
 
-        let server_addr = run_derive_server(node_signing_key_bytes, &db_arc, 0).await?;
+        let server_addr =
+            run_derive_server(node_signing_key_bytes, &db_arc, 0, &NodeConfig::default()).await?;
         let url = format!("http://{}", server_addr);
         println!("server listening at {}", url);
         let client = HttpClient::builder().build(url)?;
@@ -1309,12 +2964,9 @@ mod tests {
             .send_transaction(sample_transaction.clone())
             .await
             .unwrap();
-        client
-            .send_transaction(sample_invalid_transaction.clone())
-            .await
-            .unwrap();
+        insert_pending_transaction(&db_arc, sample_invalid_transaction.clone()).await?;
 
-        propose_block(&db_arc, &node_signing_key).await?;
+        propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
 
         // Query pending transactions from SurrealDB
         let block_rows: Vec<BlockRow> = db_arc.select("blocks").await?;
@@ -1350,7 +3002,7 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn transactions_can_spend_outputs_from_previous_blocks() -> anyhow::Result<()> {
+    async fn rejects_invalid_transactions_at_admission() -> anyhow::Result<()> {
         // Initialize SurrealDB
         let db = any::connect("memory").await?;
         db.use_ns("quible").use_db("quible_node").await?;
@@ -1360,101 +3012,1963 @@ mod tests {
 
         let node_signing_key_bytes =
             hex_literal::hex!("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80");
-        let node_signing_key = SigningKey::from_slice(&node_signing_key_bytes)?;
 
-        let server_addr = run_derive_server(node_signing_key_bytes, &db_arc, 0).await?;
+        let server_addr =
+            run_derive_server(node_signing_key_bytes, &db_arc, 0, &NodeConfig::default()).await?;
         let url = format!("http://{}", server_addr);
         println!("server listening at {}", url);
-
-        let block_row = propose_block(&db_arc, &node_signing_key).await?;
-
-        let coinbase_transaction_hash = match &block_row.transactions[..] {
-            [(hash, _)] => Ok(*hash),
-            _ => Err(anyhow!("missing coinbase transaction")),
-        }?;
-
         let client = HttpClient::builder().build(url)?;
 
-        let sample_first_transaction = &mut Transaction::Version1 {
+        let sample_invalid_transaction = Transaction::Version1 {
             inputs: vec![TransactionInput {
                 outpoint: TransactionOutpoint {
-                    txid: coinbase_transaction_hash,
+                    txid: [0u8; 32],
                     index: 0,
                 },
                 signature_script: vec![],
             }],
-            outputs: vec![TransactionOutput::Value {
-                value: 0,
-                pubkey_script: vec![],
-            }],
+            outputs: vec![],
             locktime: 0,
         };
 
-        let signature = sign_message(
-            B256::from_slice(&node_signing_key_bytes),
-            sample_first_transaction.hash_eip191()?.into(),
-        )?
-        .to_vec();
+        match client.send_transaction(sample_invalid_transaction).await {
+            Err(jsonrpsee::core::client::error::Error::Call(err)) => {
+                assert_eq!(
+                    err.message(),
+                    "call execution failed: transaction rejected (transaction hash not found!)"
+                );
 
-        match sample_first_transaction {
-            Transaction::Version1 { inputs, .. } => {
-                for input in inputs.iter_mut() {
-                    *input = TransactionInput {
-                        outpoint: input.clone().outpoint,
-                        signature_script: vec![
-                            TransactionOpCode::Push {
-                                data: signature.clone(),
-                            },
-                            TransactionOpCode::Push {
-                                data: Address::from_private_key(&node_signing_key)
-                                    .into_array()
-                                    .to_vec(),
-                            },
-                        ],
+                let error: TransactionValidationError =
+                    serde_json::from_str(err.data().map_or("null", |data| data.get()))?;
+                assert_eq!(error, TransactionValidationError::MissingOutpoint);
+
+                Ok(())
+            }
+
+            _ => Err(anyhow!("expected response to be Err(Call(_))")),
+        }?;
+
+        let pending_transaction_rows: Vec<PendingTransactionRow> =
+            db_arc.select("pending_transactions").await?;
+
+        assert_eq!(pending_transaction_rows.len(), 0);
+
+        Ok(())
+    }
+
+    async fn submit_conflicting_transactions(
+        node_config: &NodeConfig,
+        values: &[u64],
+        concurrently: bool,
+    ) -> anyhow::Result<(
+        Arc<Surreal<AnyDb>>,
+        Vec<(
+            Transaction,
+            Result<(), jsonrpsee::core::client::error::Error>,
+        )>,
+    )> {
+        // Initialize SurrealDB
+        let db = any::connect("memory").await?;
+        db.use_ns("quible").use_db("quible_node").await?;
+        db::schema::initialize_db(&db).await?;
+
+        let db_arc = Arc::new(db);
+
+        let node_signing_key_bytes =
+            hex_literal::hex!("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80");
+        let node_signing_key = SigningKey::from_slice(&node_signing_key_bytes)?;
+
+        let server_addr =
+            run_derive_server(node_signing_key_bytes, &db_arc, 0, node_config).await?;
+        let url = format!("http://{}", server_addr);
+        println!("server listening at {}", url);
+        let client = HttpClient::builder().build(url)?;
+
+        let block_row = propose_block(&db_arc, &node_signing_key, node_config).await?;
+
+        let coinbase_transaction_hash = match &block_row.transactions[..] {
+            [(hash, _)] => Ok(*hash),
+            _ => Err(anyhow!("missing coinbase transaction")),
+        }?;
+
+        // every transaction spends the 5 unit coinbase output,
+        // paying whatever it does not send on as a fee
+        let mut transactions = vec![];
+        for value in values {
+            let transaction = &mut Transaction::Version1 {
+                inputs: vec![TransactionInput {
+                    outpoint: TransactionOutpoint {
+                        txid: coinbase_transaction_hash,
+                        index: 0,
+                    },
+                    signature_script: vec![],
+                }],
+                outputs: vec![TransactionOutput::Value {
+                    value: *value,
+                    pubkey_script: vec![],
+                }],
+                locktime: 0,
+            };
+
+            sign_transaction_inputs(transaction, &node_signing_key)?;
+            transactions.push(transaction.clone());
+        }
+
+        let results = if concurrently {
+            futures::future::join_all(
+                transactions
+                    .iter()
+                    .map(|transaction| client.send_transaction(transaction.clone())),
+            )
+            .await
+        } else {
+            let mut results = vec![];
+            for transaction in &transactions {
+                results.push(client.send_transaction(transaction.clone()).await);
+            }
+            results
+        };
+
+        Ok((db_arc, transactions.into_iter().zip(results).collect()))
+    }
+
+    #[tokio::test]
+    async fn rejects_conflicting_transactions_at_admission() -> anyhow::Result<()> {
+        let node_config = NodeConfig {
+            replace_by_fee: false,
+            ..Default::default()
+        };
+
+        let (db_arc, results) =
+            submit_conflicting_transactions(&node_config, &[4, 2], false).await?;
+
+        match &results[..] {
+            [(first_transaction, Ok(())), (_, Err(jsonrpsee::core::client::error::Error::Call(err)))] =>
+            {
+                assert_eq!(
+                    err.message(),
+                    "call execution failed: transaction rejected (transaction conflicts with a pending transaction)"
+                );
+
+                let pending_transaction_rows: Vec<PendingTransactionRow> =
+                    db_arc.select("pending_transactions").await?;
+
+                match &pending_transaction_rows[..] {
+                    [row] => {
+                        assert_eq!(row.hash, hex::encode(first_transaction.hash_eip191()?));
+                        Ok(())
                     }
+
+                    _ => Err(anyhow!("unexpected number of pending transactions")),
                 }
             }
-        }
 
-        client
-            .send_transaction(sample_first_transaction.clone())
-            .await
-            .unwrap();
+            _ => Err(anyhow!("expected the second transaction to be rejected")),
+        }?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn admits_one_of_concurrent_conflicting_transactions() -> anyhow::Result<()> {
+        let node_config = NodeConfig {
+            replace_by_fee: false,
+            ..Default::default()
+        };
+
+        let (db_arc, results) =
+            submit_conflicting_transactions(&node_config, &[4, 3, 2], true).await?;
+
+        let admitted: Vec<&Transaction> = results
+            .iter()
+            .filter(|(_, result)| result.is_ok())
+            .map(|(transaction, _)| transaction)
+            .collect();
+
+        let pending_transaction_rows: Vec<PendingTransactionRow> =
+            db_arc.select("pending_transactions").await?;
+
+        match (&admitted[..], &pending_transaction_rows[..]) {
+            ([transaction], [row]) => {
+                assert_eq!(row.hash, hex::encode(transaction.hash_eip191()?));
+                Ok(())
+            }
+
+            _ => Err(anyhow!("expected exactly one transaction to be admitted")),
+        }?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn validates_non_final_transactions_at_admission() -> anyhow::Result<()> {
+        // Initialize SurrealDB
+        let db = any::connect("memory").await?;
+        db.use_ns("quible").use_db("quible_node").await?;
+        db::schema::initialize_db(&db).await?;
+
+        let db_arc = Arc::new(db);
+
+        let node_signing_key_bytes =
+            hex_literal::hex!("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80");
+        let node_signing_key = SigningKey::from_slice(&node_signing_key_bytes)?;
+
+        let server_addr =
+            run_derive_server(node_signing_key_bytes, &db_arc, 0, &NodeConfig::default()).await?;
+        let url = format!("http://{}", server_addr);
+        println!("server listening at {}", url);
+        let client = HttpClient::builder().build(url)?;
+
+        let block_row = propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+        let coinbase_transaction_hash = block_row.transactions[0].0;
+
+        // an unsigned spend far in the future must not be able
+        // to hold the outpoint in the mempool
+        let freezing_transaction = Transaction::Version1 {
+            inputs: vec![TransactionInput {
+                outpoint: TransactionOutpoint {
+                    txid: coinbase_transaction_hash,
+                    index: 0,
+                },
+                signature_script: vec![],
+            }],
+            outputs: vec![],
+            locktime: 1_000_000,
+        };
+
+        match client.send_transaction(freezing_transaction).await {
+            Err(jsonrpsee::core::client::error::Error::Call(err)) => {
+                assert!(err
+                    .message()
+                    .starts_with("call execution failed: transaction rejected"));
+                Ok(())
+            }
+
+            _ => Err(anyhow!("expected response to be Err(Call(_))")),
+        }?;
+
+        let pending_transaction_rows: Vec<PendingTransactionRow> =
+            db_arc.select("pending_transactions").await?;
+        assert_eq!(pending_transaction_rows.len(), 0);
+
+        // a signed one may still wait for its locktime
+        let mut locked_transaction = Transaction::Version1 {
+            inputs: vec![TransactionInput {
+                outpoint: TransactionOutpoint {
+                    txid: coinbase_transaction_hash,
+                    index: 0,
+                },
+                signature_script: vec![],
+            }],
+            outputs: vec![TransactionOutput::Value {
+                value: 5,
+                pubkey_script: vec![],
+            }],
+            locktime: 1_000_000,
+        };
+        sign_transaction_inputs(&mut locked_transaction, &node_signing_key)?;
+
+        client.send_transaction(locked_transaction).await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn replaces_pending_transactions_paying_higher_fees() -> anyhow::Result<()> {
+        // fees of 1, then 3, then 2
+        let (db_arc, results) =
+            submit_conflicting_transactions(&NodeConfig::default(), &[4, 2, 3], false).await?;
+
+        match &results[..] {
+            [(_, Ok(())), (replacement_transaction, Ok(())), (_, Err(jsonrpsee::core::client::error::Error::Call(err)))] =>
+            {
+                assert_eq!(
+                    err.message(),
+                    "call execution failed: transaction rejected (replacement transaction fee too low)"
+                );
+
+                let pending_transaction_rows: Vec<PendingTransactionRow> =
+                    db_arc.select("pending_transactions").await?;
+
+                match &pending_transaction_rows[..] {
+                    [row] => {
+                        assert_eq!(
+                            row.hash,
+                            hex::encode(replacement_transaction.hash_eip191()?)
+                        );
+                        Ok(())
+                    }
+
+                    _ => Err(anyhow!("unexpected number of pending transactions")),
+                }
+            }
+
+            _ => Err(anyhow!("unexpected admission results")),
+        }?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn records_rejection_reasons_for_invalid_transactions() -> anyhow::Result<()> {
+        // Initialize SurrealDB
+        let db = any::connect("memory").await?;
+        db.use_ns("quible").use_db("quible_node").await?;
+        db::schema::initialize_db(&db).await?;
+
+        let db_arc = Arc::new(db);
+
+        let node_signing_key_bytes =
+            hex_literal::hex!("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80");
+        let node_signing_key = SigningKey::from_slice(&node_signing_key_bytes)?;
+
+        let server_addr =
+            run_derive_server(node_signing_key_bytes, &db_arc, 0, &NodeConfig::default()).await?;
+        let url = format!("http://{}", server_addr);
+        println!("server listening at {}", url);
+        let client = HttpClient::builder().build(url)?;
+
+        let sample_invalid_transaction = Transaction::Version1 {
+            inputs: vec![TransactionInput {
+                outpoint: TransactionOutpoint {
+                    txid: [0u8; 32],
+                    index: 0,
+                },
+                signature_script: vec![],
+            }],
+            outputs: vec![],
+            locktime: 0,
+        };
+
+        let transaction_hash = sample_invalid_transaction.hash_eip191()?;
+
+        match client.get_transaction_rejection(transaction_hash).await {
+            Err(jsonrpsee::core::client::error::Error::Call(err)) => {
+                assert_eq!(
+                    err.message(),
+                    "call execution failed: no rejection recorded for transaction"
+                );
+                Ok(())
+            }
+
+            _ => Err(anyhow!("expected response to be Err(Call(_))")),
+        }?;
+
+        insert_pending_transaction(&db_arc, sample_invalid_transaction.clone()).await?;
+
+        propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+
+        let rejection = client.get_transaction_rejection(transaction_hash).await?;
+
+        assert_eq!(rejection.transaction_hash, transaction_hash);
+        assert_eq!(rejection.error, TransactionValidationError::MissingOutpoint);
+        assert_eq!(rejection.code, 4);
+        assert_eq!(rejection.message, "transaction hash not found!");
+        assert_eq!(rejection.block_height, 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn transactions_can_spend_outputs_from_previous_blocks() -> anyhow::Result<()> {
+        // Initialize SurrealDB
+        let db = any::connect("memory").await?;
+        db.use_ns("quible").use_db("quible_node").await?;
+        db::schema::initialize_db(&db).await?;
+
+        let db_arc = Arc::new(db);
+
+        let node_signing_key_bytes =
+            hex_literal::hex!("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80");
+        let node_signing_key = SigningKey::from_slice(&node_signing_key_bytes)?;
+
+        let server_addr =
+            run_derive_server(node_signing_key_bytes, &db_arc, 0, &NodeConfig::default()).await?;
+        let url = format!("http://{}", server_addr);
+        println!("server listening at {}", url);
+
+        let block_row = propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+
+        let coinbase_transaction_hash = match &block_row.transactions[..] {
+            [(hash, _)] => Ok(*hash),
+            _ => Err(anyhow!("missing coinbase transaction")),
+        }?;
+
+        let client = HttpClient::builder().build(url)?;
+
+        let sample_first_transaction = &mut Transaction::Version1 {
+            inputs: vec![TransactionInput {
+                outpoint: TransactionOutpoint {
+                    txid: coinbase_transaction_hash,
+                    index: 0,
+                },
+                signature_script: vec![],
+            }],
+            outputs: vec![TransactionOutput::Value {
+                value: 0,
+                pubkey_script: vec![],
+            }],
+            locktime: 0,
+        };
+
+        let signature = sign_message(
+            B256::from_slice(&node_signing_key_bytes),
+            sample_first_transaction.hash_eip191()?.into(),
+        )?
+        .to_vec();
+
+        for input in sample_first_transaction.inputs_mut().iter_mut() {
+            *input = TransactionInput {
+                outpoint: input.clone().outpoint,
+                signature_script: vec![
+                    TransactionOpCode::Push {
+                        data: signature.clone(),
+                    },
+                    TransactionOpCode::Push {
+                        data: Address::from_private_key(&node_signing_key)
+                            .into_array()
+                            .to_vec(),
+                    },
+                ],
+            }
+        }
+
+        client
+            .send_transaction(sample_first_transaction.clone())
+            .await
+            .unwrap();
+
+        propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+
+        // Query pending transactions from SurrealDB
+        let mut result = db_arc
+            .query("SELECT * FROM blocks ORDER BY height ASC")
+            .await?;
+        let block_rows: Vec<BlockRow> = result.take(0)?;
+
+        match &block_rows[..] {
+            [_, second_block_row] => match second_block_row.transactions[..] {
+                [_, (transaction_hash, _)] => {
+                    assert_eq!(transaction_hash, sample_first_transaction.hash_eip191()?);
+                    Ok(())
+                }
+
+                _ => {
+                    dbg!(second_block_row);
+                    Err(anyhow!("unexpected number of transactions"))
+                }
+            },
+
+            _ => Err(anyhow!("unexpected number of block rows")),
+        }?;
+
+        let pending_transaction_rows: Vec<PendingTransactionRow> =
+            db_arc.select("pending_transactions").await?;
+
+        assert_eq!(pending_transaction_rows.len(), 0);
+
+        Ok(())
+    }
+
+    // writes a transaction straight into the mempool, bypassing
+    // admission checks, so that block-time validation is exercised
+    async fn insert_pending_transaction(
+        db_arc: &Arc<Surreal<AnyDb>>,
+        transaction: Transaction,
+    ) -> anyhow::Result<()> {
+        let (_, mut pending_transaction_row) =
+            format_pending_transaction_row(transaction).map_err(|err| anyhow!(err.to_string()))?;
+
+        // bypasses admission, including its outpoint claims, so
+        // that conflicting transactions can be pending together
+        pending_transaction_row.outpoints = vec![];
+
+        db_arc
+            .create::<Vec<PendingTransactionRow>>("pending_transactions")
+            .content(pending_transaction_row)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn fetch_object_claims(
+        db_arc: &Arc<Surreal<AnyDb>>,
+        object_id_raw: [u8; 32],
+    ) -> anyhow::Result<Vec<Vec<u8>>> {
+        let claim_rows: Vec<ObjectClaimRow> = db_arc
+            .query("SELECT * FROM object_claims WHERE object_id = $object_id ORDER BY claim")
+            .bind(("object_id", hex::encode(object_id_raw)))
+            .await?
+            .take(0)?;
+
+        Ok(claim_rows
+            .into_iter()
+            .map(|row| hex::decode(row.claim))
+            .collect::<Result<Vec<Vec<u8>>, hex::FromHexError>>()?)
+    }
+
+    fn sign_transaction_inputs(
+        transaction: &mut Transaction,
+        signing_key: &SigningKey,
+    ) -> anyhow::Result<()> {
+        let signature = sign_message(
+            B256::from_slice(&signing_key.to_bytes()[..]),
+            transaction.hash_eip191()?.into(),
+        )?
+        .to_vec();
+
+        for input in transaction.inputs_mut().iter_mut() {
+            *input = TransactionInput {
+                outpoint: input.clone().outpoint,
+                signature_script: vec![
+                    TransactionOpCode::Push {
+                        data: signature.clone(),
+                    },
+                    TransactionOpCode::Push {
+                        data: Address::from_private_key(signing_key).into_array().to_vec(),
+                    },
+                ],
+            }
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn accepts_eip712_signed_transactions() -> anyhow::Result<()> {
+        // Initialize SurrealDB
+        let db = any::connect("memory").await?;
+        db.use_ns("quible").use_db("quible_node").await?;
+        db::schema::initialize_db(&db).await?;
+
+        let db_arc = Arc::new(db);
+
+        let node_signing_key_bytes =
+            hex_literal::hex!("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80");
+        let node_signing_key = SigningKey::from_slice(&node_signing_key_bytes)?;
+        let node_address = Address::from_private_key(&node_signing_key);
+
+        let server_addr =
+            run_derive_server(node_signing_key_bytes, &db_arc, 0, &NodeConfig::default()).await?;
+        let url = format!("http://{}", server_addr);
+        println!("server listening at {}", url);
+        let client = HttpClient::builder().build(url)?;
+
+        let coinbase_block =
+            propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+        let (coinbase_transaction_hash, _) = coinbase_block.transactions[0];
+
+        let mut locking_transaction = Transaction::Version1 {
+            inputs: vec![TransactionInput {
+                outpoint: TransactionOutpoint {
+                    txid: coinbase_transaction_hash,
+                    index: 0,
+                },
+                signature_script: vec![],
+            }],
+            outputs: vec![TransactionOutput::Value {
+                value: 5,
+                pubkey_script: vec![
+                    TransactionOpCode::Dup,
+                    TransactionOpCode::Push {
+                        data: node_address.to_vec(),
+                    },
+                    TransactionOpCode::EqualVerify,
+                    TransactionOpCode::CheckEip712SigVerify,
+                ],
+            }],
+            locktime: 0,
+        };
+        sign_transaction_inputs(&mut locking_transaction, &node_signing_key)?;
+        client.send_transaction(locking_transaction.clone()).await?;
+        propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+
+        let mut spending_transaction = Transaction::Version1 {
+            inputs: vec![TransactionInput {
+                outpoint: TransactionOutpoint {
+                    txid: locking_transaction.hash_eip191()?,
+                    index: 0,
+                },
+                signature_script: vec![],
+            }],
+            outputs: vec![TransactionOutput::Value {
+                value: 5,
+                pubkey_script: vec![],
+            }],
+            locktime: 0,
+        };
+
+        let typed_data = client
+            .get_transaction_typed_data(spending_transaction.clone())
+            .await?;
+        assert_eq!(typed_data["primaryType"], "Transaction");
+        assert_eq!(typed_data["message"]["outputs"][0]["value"], "5");
+
+        let signature = sign_message(
+            B256::from_slice(&node_signing_key.to_bytes()[..]),
+            hash_typed_data(&typed_data)?.into(),
+        )?
+        .to_vec();
+        spending_transaction.inputs_mut()[0].signature_script = vec![
+            TransactionOpCode::Push { data: signature },
+            TransactionOpCode::Push {
+                data: node_address.to_vec(),
+            },
+        ];
+        client
+            .send_transaction(spending_transaction.clone())
+            .await?;
+
+        let block = propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+        assert_eq!(block.transactions.len(), 2);
+        assert_eq!(block.transactions[1].0, spending_transaction.hash_eip191()?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn mints_coinbase_rewards_by_schedule() -> anyhow::Result<()> {
+        // Initialize SurrealDB
+        let db = any::connect("memory").await?;
+        db.use_ns("quible").use_db("quible_node").await?;
+        db::schema::initialize_db(&db).await?;
+
+        let db_arc = Arc::new(db);
+
+        let node_signing_key_bytes =
+            hex_literal::hex!("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80");
+        let node_signing_key = SigningKey::from_slice(&node_signing_key_bytes)?;
+
+        let node_config = NodeConfig {
+            reward_schedule: RewardSchedule {
+                initial_reward: 8,
+                halving_interval: 2,
+                supply_cap: Some(20),
+            },
+            coinbase_maturity: 2,
+            ..Default::default()
+        };
+
+        let server_addr =
+            run_derive_server(node_signing_key_bytes, &db_arc, 0, &node_config).await?;
+        let url = format!("http://{}", server_addr);
+        println!("server listening at {}", url);
+        let client = HttpClient::builder().build(url)?;
+
+        let coinbase_value = |block: &BlockRow| match &block.transactions[0].1.outputs()[..] {
+            [TransactionOutput::Value { value, .. }] => Ok(*value),
+            _ => Err(anyhow!("unexpected coinbase outputs")),
+        };
+
+        let first_block = propose_block(&db_arc, &node_signing_key, &node_config).await?;
+        assert_eq!(coinbase_value(&first_block)?, 8);
+        let (coinbase_transaction_hash, _) = first_block.transactions[0];
+
+        let mut spending_transaction = Transaction::Version1 {
+            inputs: vec![TransactionInput {
+                outpoint: TransactionOutpoint {
+                    txid: coinbase_transaction_hash,
+                    index: 0,
+                },
+                signature_script: vec![],
+            }],
+            outputs: vec![TransactionOutput::Value {
+                value: 8,
+                pubkey_script: vec![],
+            }],
+            locktime: 0,
+        };
+        sign_transaction_inputs(&mut spending_transaction, &node_signing_key)?;
+
+        // the next block is only one block after the coinbase
+        match client.send_transaction(spending_transaction.clone()).await {
+            Err(jsonrpsee::core::client::error::Error::Call(err)) => {
+                let error: TransactionValidationError =
+                    serde_json::from_str(err.data().map_or("null", |data| data.get()))?;
+                assert_eq!(error, TransactionValidationError::ImmatureCoinbaseSpend);
+
+                Ok(())
+            }
+
+            _ => Err(anyhow!("expected response to be Err(Call(_))")),
+        }?;
+
+        let second_block = propose_block(&db_arc, &node_signing_key, &node_config).await?;
+        assert_eq!(coinbase_value(&second_block)?, 8);
+
+        client
+            .send_transaction(spending_transaction.clone())
+            .await?;
+
+        let third_block = propose_block(&db_arc, &node_signing_key, &node_config).await?;
+        assert_eq!(third_block.transactions.len(), 2);
+        assert_eq!(coinbase_value(&third_block)?, 4);
+
+        // the supply cap is reached after the third block
+        let fourth_block = propose_block(&db_arc, &node_signing_key, &node_config).await?;
+        assert_eq!(coinbase_value(&fourth_block)?, 0);
+
+        let reward = client
+            .get_block_reward(BlockHeightPayload { height: 2 })
+            .await?;
+        assert_eq!(reward.reward, 4);
+        assert_eq!(reward.supply_before, 16);
+
+        let reward = client
+            .get_block_reward(BlockHeightPayload { height: 3 })
+            .await?;
+        assert_eq!(reward.reward, 0);
+        assert_eq!(reward.supply_before, 20);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn serves_transaction_inclusion_proofs() -> anyhow::Result<()> {
+        // Initialize SurrealDB
+        let db = any::connect("memory").await?;
+        db.use_ns("quible").use_db("quible_node").await?;
+        db::schema::initialize_db(&db).await?;
+
+        let db_arc = Arc::new(db);
+
+        let node_signing_key_bytes =
+            hex_literal::hex!("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80");
+        let node_signing_key = SigningKey::from_slice(&node_signing_key_bytes)?;
+
+        let server_addr =
+            run_derive_server(node_signing_key_bytes, &db_arc, 0, &NodeConfig::default()).await?;
+        let url = format!("http://{}", server_addr);
+        println!("server listening at {}", url);
+        let client = HttpClient::builder().build(url)?;
+
+        let block_row = propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+        let coinbase_transaction_hash = block_row.transactions[0].0;
+
+        let mut spending_transaction = Transaction::Version1 {
+            inputs: vec![TransactionInput {
+                outpoint: TransactionOutpoint {
+                    txid: coinbase_transaction_hash,
+                    index: 0,
+                },
+                signature_script: vec![],
+            }],
+            outputs: vec![TransactionOutput::Value {
+                value: 5,
+                pubkey_script: vec![],
+            }],
+            locktime: 0,
+        };
+        sign_transaction_inputs(&mut spending_transaction, &node_signing_key)?;
+
+        client
+            .send_transaction(spending_transaction.clone())
+            .await?;
+        let block_row = propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+        assert_eq!(block_row.transactions.len(), 2);
+
+        validate_block_header(
+            &block_row.header,
+            &block_row
+                .transactions
+                .iter()
+                .map(|(_, transaction)| transaction.clone())
+                .collect::<Vec<Transaction>>(),
+        )?;
+
+        for (transaction_hash, _) in &block_row.transactions {
+            let payload = client
+                .get_transaction_inclusion_proof(*transaction_hash)
+                .await?;
+
+            assert_eq!(payload.block_height, 1);
+            assert_eq!(payload.block_hash, block_row.header.hash()?);
+            assert_eq!(payload.merkle_root, block_row.header.merkle_root());
+            assert!(verify_merkle_proof(
+                transaction_hash,
+                &payload.proof,
+                &payload.merkle_root
+            ));
+        }
+
+        assert!(client
+            .get_transaction_inclusion_proof([0u8; 32])
+            .await
+            .is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn signs_block_certificates() -> anyhow::Result<()> {
+        // Initialize SurrealDB
+        let db = any::connect("memory").await?;
+        db.use_ns("quible").use_db("quible_node").await?;
+        db::schema::initialize_db(&db).await?;
+
+        let db_arc = Arc::new(db);
+
+        let node_signing_key_bytes =
+            hex_literal::hex!("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80");
+        let node_signing_key = SigningKey::from_slice(&node_signing_key_bytes)?;
+
+        let server_addr =
+            run_derive_server(node_signing_key_bytes, &db_arc, 0, &NodeConfig::default()).await?;
+        let url = format!("http://{}", server_addr);
+        println!("server listening at {}", url);
+        let client = HttpClient::builder().build(url)?;
+
+        assert!(client.get_block_certificate(None).await.is_err());
+
+        let first_block_row =
+            propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+        let second_block_row =
+            propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+
+        for (block_height, block_row) in [
+            (None, &second_block_row),
+            (Some(0), &first_block_row),
+            (Some(1), &second_block_row),
+        ] {
+            let certificate = client.get_block_certificate(block_height).await?;
+
+            assert_eq!(certificate.block_hash, block_row.header.hash()?);
+            assert_eq!(Some(certificate.state_root), block_row.header.state_root());
+            assert_eq!(certificate.timestamp, block_row.header.timestamp());
+            assert_eq!(
+                certificate.recover_signer()?,
+                Address::from_private_key(&node_signing_key)
+            );
+        }
+
+        // the signature only holds for the signed state
+        let mut certificate = client.get_block_certificate(None).await?;
+        certificate.timestamp += 1;
+        assert_ne!(
+            certificate.recover_signer()?,
+            Address::from_private_key(&node_signing_key)
+        );
+
+        assert!(client.get_block_certificate(Some(2)).await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rejects_double_spends_across_transactions_in_same_block() -> anyhow::Result<()> {
+        // Initialize SurrealDB
+        let db = any::connect("memory").await?;
+        db.use_ns("quible").use_db("quible_node").await?;
+        db::schema::initialize_db(&db).await?;
+
+        let db_arc = Arc::new(db);
+
+        let node_signing_key_bytes =
+            hex_literal::hex!("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80");
+        let node_signing_key = SigningKey::from_slice(&node_signing_key_bytes)?;
+
+        let server_addr =
+            run_derive_server(node_signing_key_bytes, &db_arc, 0, &NodeConfig::default()).await?;
+        let url = format!("http://{}", server_addr);
+        println!("server listening at {}", url);
+
+        let block_row = propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+
+        let coinbase_transaction_hash = match &block_row.transactions[..] {
+            [(hash, _)] => Ok(*hash),
+            _ => Err(anyhow!("missing coinbase transaction")),
+        }?;
+
+        for value in [0, 1] {
+            let transaction = &mut Transaction::Version1 {
+                inputs: vec![TransactionInput {
+                    outpoint: TransactionOutpoint {
+                        txid: coinbase_transaction_hash,
+                        index: 0,
+                    },
+                    signature_script: vec![],
+                }],
+                outputs: vec![TransactionOutput::Value {
+                    value,
+                    pubkey_script: vec![],
+                }],
+                locktime: 0,
+            };
+
+            sign_transaction_inputs(transaction, &node_signing_key)?;
+
+            insert_pending_transaction(&db_arc, transaction.clone()).await?;
+        }
+
+        let second_block_row =
+            propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+
+        assert_eq!(second_block_row.transactions.len(), 2);
+
+        let pending_transaction_rows: Vec<PendingTransactionRow> =
+            db_arc.select("pending_transactions").await?;
+
+        assert_eq!(pending_transaction_rows.len(), 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn marks_spent_outputs_and_rejects_spending_them_again() -> anyhow::Result<()> {
+        // Initialize SurrealDB
+        let db = any::connect("memory").await?;
+        db.use_ns("quible").use_db("quible_node").await?;
+        db::schema::initialize_db(&db).await?;
+
+        let db_arc = Arc::new(db);
+
+        let node_signing_key_bytes =
+            hex_literal::hex!("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80");
+        let node_signing_key = SigningKey::from_slice(&node_signing_key_bytes)?;
+
+        let server_addr =
+            run_derive_server(node_signing_key_bytes, &db_arc, 0, &NodeConfig::default()).await?;
+        let url = format!("http://{}", server_addr);
+        println!("server listening at {}", url);
+
+        let block_row = propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+
+        let coinbase_transaction_hash = match &block_row.transactions[..] {
+            [(hash, _)] => Ok(*hash),
+            _ => Err(anyhow!("missing coinbase transaction")),
+        }?;
+
+        let client = HttpClient::builder().build(url)?;
+
+        let create_spending_transaction = |value: u64| -> anyhow::Result<Transaction> {
+            let transaction = &mut Transaction::Version1 {
+                inputs: vec![TransactionInput {
+                    outpoint: TransactionOutpoint {
+                        txid: coinbase_transaction_hash,
+                        index: 0,
+                    },
+                    signature_script: vec![],
+                }],
+                outputs: vec![TransactionOutput::Value {
+                    value,
+                    pubkey_script: vec![],
+                }],
+                locktime: 0,
+            };
+
+            sign_transaction_inputs(transaction, &node_signing_key)?;
+
+            Ok(transaction.clone())
+        };
+
+        let first_transaction = create_spending_transaction(0)?;
+        client.send_transaction(first_transaction.clone()).await?;
+
+        let second_block_row =
+            propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+
+        assert_eq!(second_block_row.transactions.len(), 2);
+
+        let mut result = db_arc
+            .query("SELECT * FROM transaction_outputs WHERE transaction_hash = $hash")
+            .bind(("hash", hex::encode(coinbase_transaction_hash)))
+            .await?;
+        let output_rows: Vec<TransactionOutputRow> = result.take(0)?;
+
+        match &output_rows[..] {
+            [output_row] => {
+                assert!(output_row.spent);
+                assert_eq!(
+                    output_row.spending_transaction_hash,
+                    Some(hex::encode(first_transaction.hash_eip191()?))
+                );
+                assert_eq!(output_row.spent_at_height, Some(second_block_row.height));
+
+                Ok(())
+            }
+
+            _ => Err(anyhow!("unexpected number of outputs")),
+        }?;
+
+        // bypasses admission, so that only the spent flag stored
+        // by the previous block can catch the re-spend
+        let respending_transaction = create_spending_transaction(1)?;
+        insert_pending_transaction(&db_arc, respending_transaction.clone()).await?;
+
+        let third_block_row =
+            propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+
+        assert_eq!(third_block_row.transactions.len(), 1);
+
+        let rejection = client
+            .get_transaction_rejection(respending_transaction.hash_eip191()?)
+            .await?;
+        assert_eq!(rejection.message, "cannot spend output twice");
+        assert_eq!(rejection.block_height, third_block_row.height);
+
+        let pending_transaction_rows: Vec<PendingTransactionRow> =
+            db_arc.select("pending_transactions").await?;
+
+        assert_eq!(pending_transaction_rows.len(), 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn prefers_higher_fee_rates_and_pays_fees_to_coinbase() -> anyhow::Result<()> {
+        // Initialize SurrealDB
+        let db = any::connect("memory").await?;
+        db.use_ns("quible").use_db("quible_node").await?;
+        db::schema::initialize_db(&db).await?;
+
+        let db_arc = Arc::new(db);
+
+        let node_signing_key_bytes =
+            hex_literal::hex!("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80");
+        let node_signing_key = SigningKey::from_slice(&node_signing_key_bytes)?;
+
+        let server_addr =
+            run_derive_server(node_signing_key_bytes, &db_arc, 0, &NodeConfig::default()).await?;
+        let url = format!("http://{}", server_addr);
+        println!("server listening at {}", url);
+
+        let block_row = propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+
+        let coinbase_transaction_hash = match &block_row.transactions[..] {
+            [(hash, _)] => Ok(*hash),
+            _ => Err(anyhow!("missing coinbase transaction")),
+        }?;
+
+        // both transactions spend the same output, so only the
+        // one paying the higher fee (5 - 2 = 3) can be included
+        let mut transactions = vec![];
+        for value in [4, 2] {
+            let transaction = &mut Transaction::Version1 {
+                inputs: vec![TransactionInput {
+                    outpoint: TransactionOutpoint {
+                        txid: coinbase_transaction_hash,
+                        index: 0,
+                    },
+                    signature_script: vec![],
+                }],
+                outputs: vec![TransactionOutput::Value {
+                    value,
+                    pubkey_script: vec![],
+                }],
+                locktime: 0,
+            };
+
+            sign_transaction_inputs(transaction, &node_signing_key)?;
+
+            insert_pending_transaction(&db_arc, transaction.clone()).await?;
+            transactions.push(transaction.clone());
+        }
+
+        let second_block_row =
+            propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+
+        match &second_block_row.transactions[..] {
+            [(_, coinbase_transaction), (transaction_hash, _)] => {
+                assert_eq!(*transaction_hash, transactions[1].hash_eip191()?);
+
+                match &coinbase_transaction.outputs()[..] {
+                    [TransactionOutput::Value { value, .. }] => {
+                        assert_eq!(*value, 5 + 3);
+                        Ok(())
+                    }
+
+                    _ => Err(anyhow!("unexpected coinbase outputs")),
+                }
+            }
+
+            _ => Err(anyhow!("unexpected number of transactions")),
+        }?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn leaves_transactions_that_do_not_fit_for_the_next_block() -> anyhow::Result<()> {
+        // Initialize SurrealDB
+        let db = any::connect("memory").await?;
+        db.use_ns("quible").use_db("quible_node").await?;
+        db::schema::initialize_db(&db).await?;
+
+        let db_arc = Arc::new(db);
+
+        let node_signing_key_bytes =
+            hex_literal::hex!("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80");
+        let node_signing_key = SigningKey::from_slice(&node_signing_key_bytes)?;
+
+        let server_addr =
+            run_derive_server(node_signing_key_bytes, &db_arc, 0, &NodeConfig::default()).await?;
+        let url = format!("http://{}", server_addr);
+        println!("server listening at {}", url);
+        let client = HttpClient::builder().build(url)?;
+
+        let transactions: Vec<Transaction> = (0..3u8)
+            .map(|index| Transaction::Version1 {
+                inputs: vec![],
+                outputs: vec![TransactionOutput::Value {
+                    value: 0,
+                    pubkey_script: vec![TransactionOpCode::Push { data: vec![index] }],
+                }],
+                locktime: 0,
+            })
+            .collect();
+
+        for transaction in transactions.clone() {
+            client.send_transaction(transaction).await?;
+        }
+
+        let pending_transaction_rows: Vec<PendingTransactionRow> =
+            db_arc.select("pending_transactions").await?;
+
+        let transaction_size = transactions[0].encoded_size()?;
+        for row in pending_transaction_rows {
+            assert_eq!(row.size, transaction_size);
+        }
+
+        let node_config = NodeConfig {
+            maximum_block_size: transaction_size * 2,
+            ..Default::default()
+        };
+
+        let block_row = propose_block(&db_arc, &node_signing_key, &node_config).await?;
+
+        assert_eq!(block_row.transactions.len(), 1 + 2);
+
+        let pending_transaction_rows: Vec<PendingTransactionRow> =
+            db_arc.select("pending_transactions").await?;
+
+        assert_eq!(pending_transaction_rows.len(), 1);
+
+        let block_row = propose_block(&db_arc, &node_signing_key, &node_config).await?;
+
+        assert_eq!(block_row.transactions.len(), 1 + 1);
+
+        let pending_transaction_rows: Vec<PendingTransactionRow> =
+            db_arc.select("pending_transactions").await?;
+
+        assert_eq!(pending_transaction_rows.len(), 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn keeps_locked_transactions_pending_until_final() -> anyhow::Result<()> {
+        // Initialize SurrealDB
+        let db = any::connect("memory").await?;
+        db.use_ns("quible").use_db("quible_node").await?;
+        db::schema::initialize_db(&db).await?;
+
+        let db_arc = Arc::new(db);
+
+        let node_signing_key_bytes =
+            hex_literal::hex!("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80");
+        let node_signing_key = SigningKey::from_slice(&node_signing_key_bytes)?;
+
+        let server_addr =
+            run_derive_server(node_signing_key_bytes, &db_arc, 0, &NodeConfig::default()).await?;
+        let url = format!("http://{}", server_addr);
+        println!("server listening at {}", url);
+        let client = HttpClient::builder().build(url)?;
+
+        let locked_transaction = Transaction::Version1 {
+            inputs: vec![],
+            outputs: vec![TransactionOutput::Value {
+                value: 0,
+                pubkey_script: vec![],
+            }],
+            locktime: 1,
+        };
+
+        client.send_transaction(locked_transaction.clone()).await?;
+
+        for _ in 0..2 {
+            let block_row =
+                propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+
+            assert_eq!(block_row.transactions.len(), 1);
+        }
+
+        let pending_transaction_rows: Vec<PendingTransactionRow> =
+            db_arc.select("pending_transactions").await?;
+
+        assert_eq!(pending_transaction_rows.len(), 1);
+
+        let block_row = propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+
+        match &block_row.transactions[..] {
+            [_, (transaction_hash, _)] => {
+                assert_eq!(*transaction_hash, locked_transaction.hash_eip191()?);
+                Ok(())
+            }
+
+            _ => Err(anyhow!("unexpected number of transactions")),
+        }?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn simulates_transactions_without_touching_the_mempool() -> anyhow::Result<()> {
+        // Initialize SurrealDB
+        let db = any::connect("memory").await?;
+        db.use_ns("quible").use_db("quible_node").await?;
+        db::schema::initialize_db(&db).await?;
+
+        let db_arc = Arc::new(db);
+
+        let node_signing_key_bytes =
+            hex_literal::hex!("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80");
+        let node_signing_key = SigningKey::from_slice(&node_signing_key_bytes)?;
+
+        let server_addr =
+            run_derive_server(node_signing_key_bytes, &db_arc, 0, &NodeConfig::default()).await?;
+        let url = format!("http://{}", server_addr);
+        println!("server listening at {}", url);
+        let client = HttpClient::builder().build(url)?;
+
+        let coinbase_block =
+            propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+        let (coinbase_transaction_hash, _) = coinbase_block.transactions[0];
+
+        let inputs = vec![TransactionInput {
+            outpoint: TransactionOutpoint {
+                txid: coinbase_transaction_hash,
+                index: 0,
+            },
+            signature_script: vec![],
+        }];
+
+        let object_id_raw = compute_object_id(inputs.clone(), 0)?;
+        let mut sample_transaction = Transaction::Version1 {
+            inputs,
+            outputs: vec![TransactionOutput::Object {
+                object_id: ObjectIdentifier {
+                    raw: object_id_raw,
+                    mode: ObjectMode::Fresh,
+                },
+                data_script: vec![
+                    TransactionOpCode::Insert {
+                        data: vec![1, 2, 3],
+                    },
+                    TransactionOpCode::SetCertTTL { data: 3600 },
+                ],
+                pubkey_script: vec![],
+            }],
+            locktime: 0,
+        };
+
+        sign_transaction_inputs(&mut sample_transaction, &node_signing_key)?;
+
+        let simulation = client
+            .simulate_transaction(sample_transaction.clone())
+            .await?;
+
+        assert!(simulation.success);
+        assert_eq!(
+            simulation.transaction_hash,
+            sample_transaction.hash_eip191()?
+        );
+        assert_eq!(simulation.fee, Some(5));
+        assert_eq!(simulation.error, None);
+        assert_eq!(
+            simulation.object_changes,
+            vec![ObjectChangeEntry {
+                object_id: object_id_raw,
+                created: true,
+                deleted_all_claims: false,
+                inserted_claims: vec![vec![1, 2, 3]],
+                deleted_claims: vec![],
+                cert_ttl: Some(3600),
+                granted_claim_managers: vec![],
+                revoked_claim_managers: vec![],
+            }]
+        );
+
+        // spending an output that does not exist reports the
+        // same typed reason a block proposal would record
+        let missing_outpoint_transaction = Transaction::Version1 {
+            inputs: vec![TransactionInput {
+                outpoint: TransactionOutpoint {
+                    txid: [0u8; 32],
+                    index: 0,
+                },
+                signature_script: vec![],
+            }],
+            outputs: vec![],
+            locktime: 0,
+        };
+
+        let simulation = client
+            .simulate_transaction(missing_outpoint_transaction)
+            .await?;
+
+        assert!(!simulation.success);
+        assert_eq!(simulation.fee, None);
+        assert_eq!(simulation.object_changes, vec![]);
+        assert_eq!(
+            simulation.error,
+            Some(TransactionValidationError::MissingOutpoint)
+        );
+
+        let pending_transaction_rows: Vec<PendingTransactionRow> =
+            db_arc.select("pending_transactions").await?;
+        assert_eq!(pending_transaction_rows.len(), 0);
+
+        let object_rows: Vec<ObjectRow> = db_arc.select("objects").await?;
+        assert_eq!(object_rows.len(), 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn accepts_transactions_with_object_outputs() -> anyhow::Result<()> {
+        // Initialize SurrealDB
+        let db = any::connect("memory").await?;
+        db.use_ns("quible").use_db("quible_node").await?;
+        db::schema::initialize_db(&db).await?;
+
+        let db_arc = Arc::new(db);
+
+        let node_signing_key_bytes =
+            hex_literal::hex!("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80");
+        let node_signing_key = SigningKey::from_slice(&node_signing_key_bytes)?;
+
+        let server_addr =
+            run_derive_server(node_signing_key_bytes, &db_arc, 0, &NodeConfig::default()).await?;
+        let url = format!("http://{}", server_addr);
+        println!("server listening at {}", url);
+        let client = HttpClient::builder().build(url)?;
+        // let signer_secret = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let object_id_raw = compute_object_id(vec![], 0)?;
+        let sample_transaction = Transaction::Version1 {
+            inputs: vec![],
+            outputs: vec![TransactionOutput::Object {
+                object_id: ObjectIdentifier {
+                    raw: object_id_raw,
+                    mode: ObjectMode::Fresh,
+                },
+                data_script: vec![
+                    TransactionOpCode::Insert {
+                        data: vec![1, 2, 3],
+                    },
+                    TransactionOpCode::DeleteAll,
+                    TransactionOpCode::Insert {
+                        data: vec![4, 5, 6],
+                    },
+                    TransactionOpCode::Delete {
+                        data: vec![4, 5, 6],
+                    },
+                    TransactionOpCode::Insert {
+                        data: vec![7, 8, 9],
+                    },
+                ],
+                pubkey_script: vec![],
+            }],
+            locktime: 0,
+        };
+
+        client
+            .send_transaction(sample_transaction.clone())
+            .await
+            .unwrap();
+
+        propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+
+        // Query pending transactions from SurrealDB
+        let block_rows: Vec<BlockRow> = db_arc.select("blocks").await?;
+
+        assert_eq!(block_rows.len(), 1);
+        for row in block_rows {
+            println!("Block Hash: {}", row.hash);
+            println!(
+                "Block Header: {}",
+                serde_json::to_string_pretty(&row.header)?
+            );
+
+            match row.transactions[..] {
+                [_, (first_transaction_hash, _)] => {
+                    assert_eq!(first_transaction_hash, sample_transaction.hash_eip191()?);
+                    Ok(())
+                }
+
+                _ => Err(anyhow!("unexpected number of transactions")),
+            }?;
+        }
+
+        let pending_transaction_rows: Vec<PendingTransactionRow> =
+            db_arc.select("pending_transactions").await?;
+
+        assert_eq!(pending_transaction_rows.len(), 0);
+
+        let object_rows: Vec<ObjectRow> = db_arc.select("objects").await?;
+
+        match &object_rows[..] {
+            [object_row] => {
+                assert_eq!(object_row.object_id, hex::encode(object_id_raw));
+                assert_eq!(
+                    fetch_object_claims(&db_arc, object_id_raw).await?,
+                    vec![vec![7, 8, 9]]
+                );
+
+                Ok(())
+            }
+
+            _ => Err(anyhow!("unexpected number of objects")),
+        }?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn tracks_object_outpoints_and_destroys_spent_objects() -> anyhow::Result<()> {
+        // Initialize SurrealDB
+        let db = any::connect("memory").await?;
+        db.use_ns("quible").use_db("quible_node").await?;
+        db::schema::initialize_db(&db).await?;
+
+        let db_arc = Arc::new(db);
+
+        let node_signing_key_bytes =
+            hex_literal::hex!("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80");
+        let node_signing_key = SigningKey::from_slice(&node_signing_key_bytes)?;
+        let recipient_signing_key = SigningKey::random(&mut rand::thread_rng());
+
+        let server_addr =
+            run_derive_server(node_signing_key_bytes, &db_arc, 0, &NodeConfig::default()).await?;
+        let url = format!("http://{}", server_addr);
+        println!("server listening at {}", url);
+        let client = HttpClient::builder().build(url)?;
+
+        let create_pubkey_script = |signing_key: &SigningKey| {
+            vec![
+                TransactionOpCode::Dup,
+                TransactionOpCode::Push {
+                    data: Address::from_private_key(signing_key).into_array().to_vec(),
+                },
+                TransactionOpCode::EqualVerify,
+                TransactionOpCode::CheckEip191SigVerify,
+            ]
+        };
+
+        let fetch_object_row = |object_id_raw: [u8; 32]| {
+            let db_arc = db_arc.clone();
+            async move {
+                let object_row: Option<ObjectRow> = db_arc
+                    .select(("objects", hex::encode(object_id_raw)))
+                    .await?;
+                anyhow::Ok(object_row)
+            }
+        };
+
+        let object_id_raw = compute_object_id(vec![], 0)?;
+        let creating_transaction = Transaction::Version1 {
+            inputs: vec![],
+            outputs: vec![TransactionOutput::Object {
+                object_id: ObjectIdentifier {
+                    raw: object_id_raw,
+                    mode: ObjectMode::Fresh,
+                },
+                data_script: vec![TransactionOpCode::Insert {
+                    data: vec![1, 2, 3],
+                }],
+                pubkey_script: create_pubkey_script(&node_signing_key),
+            }],
+            locktime: 0,
+        };
+        let creating_transaction_hash = creating_transaction.hash_eip191()?;
+
+        client.send_transaction(creating_transaction).await?;
+        propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+
+        let object_row = fetch_object_row(object_id_raw)
+            .await?
+            .ok_or(anyhow!("object was not created"))?;
+        assert_eq!(
+            object_row.transaction_hash,
+            hex::encode(creating_transaction_hash)
+        );
+        assert_eq!(object_row.output_index, 0);
+        assert_eq!(
+            object_row.owners,
+            vec![hex::encode(Address::from_private_key(&node_signing_key))]
+        );
+
+        // transferring the object moves it to the new outpoint
+        // and owner, keeping its claims
+        let mut transferring_transaction = Transaction::Version1 {
+            inputs: vec![TransactionInput {
+                outpoint: TransactionOutpoint {
+                    txid: creating_transaction_hash,
+                    index: 0,
+                },
+                signature_script: vec![],
+            }],
+            outputs: vec![TransactionOutput::Object {
+                object_id: ObjectIdentifier {
+                    raw: object_id_raw,
+                    mode: ObjectMode::Existing { permit_index: 0 },
+                },
+                data_script: vec![],
+                pubkey_script: create_pubkey_script(&recipient_signing_key),
+            }],
+            locktime: 0,
+        };
+        sign_transaction_inputs(&mut transferring_transaction, &node_signing_key)?;
+        let transferring_transaction_hash = transferring_transaction.hash_eip191()?;
+
+        client.send_transaction(transferring_transaction).await?;
+        propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+
+        let object_row = fetch_object_row(object_id_raw)
+            .await?
+            .ok_or(anyhow!("object was destroyed by transfer"))?;
+        assert_eq!(
+            object_row.transaction_hash,
+            hex::encode(transferring_transaction_hash)
+        );
+        assert_eq!(
+            object_row.owners,
+            vec![hex::encode(Address::from_private_key(
+                &recipient_signing_key
+            ))]
+        );
+        assert_eq!(
+            fetch_object_claims(&db_arc, object_id_raw).await?,
+            vec![vec![1, 2, 3]]
+        );
+
+        // spending the object without a successor destroys it
+        let mut destroying_transaction = Transaction::Version1 {
+            inputs: vec![TransactionInput {
+                outpoint: TransactionOutpoint {
+                    txid: transferring_transaction_hash,
+                    index: 0,
+                },
+                signature_script: vec![],
+            }],
+            outputs: vec![],
+            locktime: 0,
+        };
+        sign_transaction_inputs(&mut destroying_transaction, &recipient_signing_key)?;
+
+        client.send_transaction(destroying_transaction).await?;
+        propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+
+        assert!(fetch_object_row(object_id_raw).await?.is_none());
+        assert_eq!(
+            fetch_object_claims(&db_arc, object_id_raw).await?,
+            Vec::<Vec<u8>>::new()
+        );
+
+        match client
+            .request_certificate(object_id_raw, vec![1, 2, 3], None, None)
+            .await
+        {
+            Err(jsonrpsee::core::client::error::Error::Call(err)) => {
+                assert_eq!(
+                    err.message(),
+                    "call execution failed: could not find identity or claim"
+                );
+                Ok(())
+            }
+
+            _ => Err(anyhow!("expected response to be Err(Call(_))")),
+        }?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn stores_object_claims_as_a_set() -> anyhow::Result<()> {
+        // Initialize SurrealDB
+        let db = any::connect("memory").await?;
+        db.use_ns("quible").use_db("quible_node").await?;
+        db::schema::initialize_db(&db).await?;
+
+        let db_arc = Arc::new(db);
+
+        let node_signing_key_bytes =
+            hex_literal::hex!("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80");
+        let node_signing_key = SigningKey::from_slice(&node_signing_key_bytes)?;
+
+        let server_addr =
+            run_derive_server(node_signing_key_bytes, &db_arc, 0, &NodeConfig::default()).await?;
+        let url = format!("http://{}", server_addr);
+        println!("server listening at {}", url);
+        let client = HttpClient::builder().build(url)?;
+
+        let object_id_raw = compute_object_id(vec![], 0)?;
+        let creating_transaction = Transaction::Version1 {
+            inputs: vec![],
+            outputs: vec![TransactionOutput::Object {
+                object_id: ObjectIdentifier {
+                    raw: object_id_raw,
+                    mode: ObjectMode::Fresh,
+                },
+                data_script: vec![
+                    TransactionOpCode::Insert { data: vec![1] },
+                    TransactionOpCode::Insert { data: vec![1] },
+                    TransactionOpCode::Insert { data: vec![2] },
+                    TransactionOpCode::Delete { data: vec![3] },
+                ],
+                pubkey_script: vec![
+                    TransactionOpCode::Dup,
+                    TransactionOpCode::Push {
+                        data: Address::from_private_key(&node_signing_key)
+                            .into_array()
+                            .to_vec(),
+                    },
+                    TransactionOpCode::EqualVerify,
+                    TransactionOpCode::CheckEip191SigVerify,
+                ],
+            }],
+            locktime: 0,
+        };
+        let creating_transaction_hash = creating_transaction.hash_eip191()?;
+
+        client.send_transaction(creating_transaction).await?;
+        propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+
+        // inserting a member twice, or deleting a missing
+        // member, leaves the set unchanged
+        assert_eq!(
+            fetch_object_claims(&db_arc, object_id_raw).await?,
+            vec![vec![1], vec![2]]
+        );
+
+        let mut updating_transaction = Transaction::Version1 {
+            inputs: vec![TransactionInput {
+                outpoint: TransactionOutpoint {
+                    txid: creating_transaction_hash,
+                    index: 0,
+                },
+                signature_script: vec![],
+            }],
+            outputs: vec![TransactionOutput::Object {
+                object_id: ObjectIdentifier {
+                    raw: object_id_raw,
+                    mode: ObjectMode::Existing { permit_index: 0 },
+                },
+                data_script: vec![
+                    TransactionOpCode::DeleteAll,
+                    TransactionOpCode::Insert { data: vec![4] },
+                ],
+                pubkey_script: vec![],
+            }],
+            locktime: 0,
+        };
+        sign_transaction_inputs(&mut updating_transaction, &node_signing_key)?;
+
+        client.send_transaction(updating_transaction).await?;
+        propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+
+        assert_eq!(
+            fetch_object_claims(&db_arc, object_id_raw).await?,
+            vec![vec![4]]
+        );
+
+        client
+            .request_certificate(object_id_raw, vec![4], None, None)
+            .await?;
+        assert!(client
+            .request_certificate(object_id_raw, vec![1], None, None)
+            .await
+            .is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn lets_claim_managers_insert_and_delete_claims() -> anyhow::Result<()> {
+        // Initialize SurrealDB
+        let db = any::connect("memory").await?;
+        db.use_ns("quible").use_db("quible_node").await?;
+        db::schema::initialize_db(&db).await?;
+
+        let db_arc = Arc::new(db);
+
+        let node_signing_key_bytes =
+            hex_literal::hex!("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80");
+        let node_signing_key = SigningKey::from_slice(&node_signing_key_bytes)?;
+        let manager_signing_key = SigningKey::random(&mut rand::thread_rng());
+        let manager_address = Address::from_private_key(&manager_signing_key)
+            .into_array()
+            .to_vec();
+
+        let server_addr =
+            run_derive_server(node_signing_key_bytes, &db_arc, 0, &NodeConfig::default()).await?;
+        let url = format!("http://{}", server_addr);
+        println!("server listening at {}", url);
+        let client = HttpClient::builder().build(url)?;
+
+        let owner_pubkey_script = vec![
+            TransactionOpCode::Dup,
+            TransactionOpCode::Push {
+                data: Address::from_private_key(&node_signing_key)
+                    .into_array()
+                    .to_vec(),
+            },
+            TransactionOpCode::EqualVerify,
+            TransactionOpCode::CheckEip191SigVerify,
+        ];
+
+        let object_id_raw = compute_object_id(vec![], 0)?;
+        let creating_transaction = Transaction::Version1 {
+            inputs: vec![],
+            outputs: vec![TransactionOutput::Object {
+                object_id: ObjectIdentifier {
+                    raw: object_id_raw,
+                    mode: ObjectMode::Fresh,
+                },
+                data_script: vec![
+                    TransactionOpCode::Insert { data: vec![1] },
+                    TransactionOpCode::GrantClaimManager {
+                        data: manager_address.clone(),
+                    },
+                ],
+                pubkey_script: owner_pubkey_script.clone(),
+            }],
+            locktime: 0,
+        };
+        let creating_transaction_hash = creating_transaction.hash_eip191()?;
+
+        client.send_transaction(creating_transaction).await?;
+        propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+
+        let managing_transaction = |txid: [u8; 32], pubkey_script: Vec<TransactionOpCode>| {
+            let mut transaction = Transaction::Version1 {
+                inputs: vec![TransactionInput {
+                    outpoint: TransactionOutpoint { txid, index: 0 },
+                    signature_script: vec![],
+                }],
+                outputs: vec![TransactionOutput::Object {
+                    object_id: ObjectIdentifier {
+                        raw: object_id_raw,
+                        mode: ObjectMode::Existing { permit_index: 0 },
+                    },
+                    data_script: vec![
+                        TransactionOpCode::Delete { data: vec![1] },
+                        TransactionOpCode::Insert { data: vec![2] },
+                    ],
+                    pubkey_script,
+                }],
+                locktime: 0,
+            };
+            sign_transaction_inputs(&mut transaction, &manager_signing_key)?;
+
+            anyhow::Ok(transaction)
+        };
+
+        // claim managers cannot transfer the object
+        match client
+            .send_transaction(managing_transaction(creating_transaction_hash, vec![])?)
+            .await
+        {
+            Err(jsonrpsee::core::client::error::Error::Call(err)) => {
+                let reason: TransactionValidationError =
+                    serde_json::from_str(err.data().unwrap().get())?;
+                assert_eq!(reason, TransactionValidationError::UnauthorizedObjectUpdate);
+            }
+            result => panic!("expected a call error, got {:?}", result),
+        }
+
+        let updating_transaction =
+            managing_transaction(creating_transaction_hash, owner_pubkey_script.clone())?;
+        let updating_transaction_hash = updating_transaction.hash_eip191()?;
+
+        client.send_transaction(updating_transaction).await?;
+        propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+
+        assert_eq!(
+            fetch_object_claims(&db_arc, object_id_raw).await?,
+            vec![vec![2]]
+        );
+
+        // once revoked, the manager can no longer spend the object
+        let mut revoking_transaction = Transaction::Version1 {
+            inputs: vec![TransactionInput {
+                outpoint: TransactionOutpoint {
+                    txid: updating_transaction_hash,
+                    index: 0,
+                },
+                signature_script: vec![],
+            }],
+            outputs: vec![TransactionOutput::Object {
+                object_id: ObjectIdentifier {
+                    raw: object_id_raw,
+                    mode: ObjectMode::Existing { permit_index: 0 },
+                },
+                data_script: vec![TransactionOpCode::RevokeClaimManager {
+                    data: manager_address.clone(),
+                }],
+                pubkey_script: owner_pubkey_script.clone(),
+            }],
+            locktime: 0,
+        };
+        sign_transaction_inputs(&mut revoking_transaction, &node_signing_key)?;
+        let revoking_transaction_hash = revoking_transaction.hash_eip191()?;
+
+        client.send_transaction(revoking_transaction).await?;
+        propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+
+        assert!(
+            fetch_confirmed_object_claim_managers(&db_arc, object_id_raw)
+                .await?
+                .is_empty()
+        );
+        assert!(client
+            .send_transaction(managing_transaction(
+                revoking_transaction_hash,
+                owner_pubkey_script
+            )?)
+            .await
+            .is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn commits_object_state_in_block_headers() -> anyhow::Result<()> {
+        // Initialize SurrealDB
+        let db = any::connect("memory").await?;
+        db.use_ns("quible").use_db("quible_node").await?;
+        db::schema::initialize_db(&db).await?;
+
+        let db_arc = Arc::new(db);
+
+        let node_signing_key_bytes =
+            hex_literal::hex!("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80");
+        let node_signing_key = SigningKey::from_slice(&node_signing_key_bytes)?;
+
+        let server_addr =
+            run_derive_server(node_signing_key_bytes, &db_arc, 0, &NodeConfig::default()).await?;
+        let url = format!("http://{}", server_addr);
+        println!("server listening at {}", url);
+        let client = HttpClient::builder().build(url)?;
+
+        let owner_pubkey_script = vec![
+            TransactionOpCode::Dup,
+            TransactionOpCode::Push {
+                data: Address::from_private_key(&node_signing_key)
+                    .into_array()
+                    .to_vec(),
+            },
+            TransactionOpCode::EqualVerify,
+            TransactionOpCode::CheckEip191SigVerify,
+        ];
+
+        let object_id_raw = compute_object_id(vec![], 0)?;
+        let creating_transaction = Transaction::Version1 {
+            inputs: vec![],
+            outputs: vec![TransactionOutput::Object {
+                object_id: ObjectIdentifier {
+                    raw: object_id_raw,
+                    mode: ObjectMode::Fresh,
+                },
+                data_script: vec![
+                    TransactionOpCode::Insert { data: vec![1] },
+                    TransactionOpCode::Insert { data: vec![2] },
+                ],
+                pubkey_script: owner_pubkey_script.clone(),
+            }],
+            locktime: 0,
+        };
+        let creating_transaction_hash = creating_transaction.hash_eip191()?;
+
+        client.send_transaction(creating_transaction).await?;
+        let block_row = propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+
+        let state_root = block_row
+            .header
+            .state_root()
+            .ok_or(anyhow!("block header does not commit to state"))?;
+        let mut state_tree = load_state_tree(&db_arc, Some(&block_row)).await?;
+
+        let claim_key = claim_state_key(&object_id_raw, &[1]);
+        let proof = state_tree.prove(&claim_key).await?;
+        assert!(verify_state_proof(
+            &state_root,
+            &claim_key,
+            Some(&claim_state_value(&[1])),
+            &proof
+        ));
+
+        let missing_claim_key = claim_state_key(&object_id_raw, &[3]);
+        let proof = state_tree.prove(&missing_claim_key).await?;
+        assert!(verify_state_proof(
+            &state_root,
+            &missing_claim_key,
+            None,
+            &proof
+        ));
 
-        propose_block(&db_arc, &node_signing_key).await?;
+        assert_eq!(
+            state_tree.get(&object_state_key(&object_id_raw)).await?,
+            Some(object_state_value(&creating_transaction_hash, 0, 86400))
+        );
 
-        // Query pending transactions from SurrealDB
-        let mut result = db_arc
-            .query("SELECT * FROM blocks ORDER BY height ASC")
-            .await?;
-        let block_rows: Vec<BlockRow> = result.take(0)?;
+        let mut updating_transaction = Transaction::Version1 {
+            inputs: vec![TransactionInput {
+                outpoint: TransactionOutpoint {
+                    txid: creating_transaction_hash,
+                    index: 0,
+                },
+                signature_script: vec![],
+            }],
+            outputs: vec![TransactionOutput::Object {
+                object_id: ObjectIdentifier {
+                    raw: object_id_raw,
+                    mode: ObjectMode::Existing { permit_index: 0 },
+                },
+                data_script: vec![
+                    TransactionOpCode::Delete { data: vec![1] },
+                    TransactionOpCode::Insert { data: vec![3] },
+                    TransactionOpCode::SetCertTTL { data: 3600 },
+                ],
+                pubkey_script: owner_pubkey_script.clone(),
+            }],
+            locktime: 0,
+        };
+        sign_transaction_inputs(&mut updating_transaction, &node_signing_key)?;
+        let updating_transaction_hash = updating_transaction.hash_eip191()?;
 
-        match &block_rows[..] {
-            [_, second_block_row] => match second_block_row.transactions[..] {
-                [_, (transaction_hash, _)] => {
-                    assert_eq!(transaction_hash, sample_first_transaction.hash_eip191()?);
-                    Ok(())
-                }
+        client.send_transaction(updating_transaction).await?;
+        let block_row = propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
 
-                _ => {
-                    dbg!(second_block_row);
-                    Err(anyhow!("unexpected number of transactions"))
-                }
-            },
+        // the incrementally updated root matches a tree built
+        // from scratch out of the stored objects and claims
+        assert_eq!(
+            block_row.header.state_root(),
+            Some(load_state_tree(&db_arc, None).await?.root)
+        );
 
-            _ => Err(anyhow!("unexpected number of block rows")),
-        }?;
+        let mut destroying_transaction = Transaction::Version1 {
+            inputs: vec![TransactionInput {
+                outpoint: TransactionOutpoint {
+                    txid: updating_transaction_hash,
+                    index: 0,
+                },
+                signature_script: vec![],
+            }],
+            outputs: vec![],
+            locktime: 0,
+        };
+        sign_transaction_inputs(&mut destroying_transaction, &node_signing_key)?;
 
-        let pending_transaction_rows: Vec<PendingTransactionRow> =
-            db_arc.select("pending_transactions").await?;
+        client.send_transaction(destroying_transaction).await?;
+        let block_row = propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
 
-        assert_eq!(pending_transaction_rows.len(), 0);
+        assert_eq!(block_row.header.state_root(), Some(EMPTY_ROOT));
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn accepts_transactions_with_object_outputs() -> anyhow::Result<()> {
+    async fn issues_valid_certificates_for_valid_requests() -> anyhow::Result<()> {
         // Initialize SurrealDB
         let db = any::connect("memory").await?;
         db.use_ns("quible").use_db("quible_node").await?;
@@ -1462,11 +4976,14 @@ mod tests {
 
         let db_arc = Arc::new(db);
 
-        let node_signing_key_bytes =
+        let server_signing_key_bytes =
             hex_literal::hex!("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80");
-        let node_signing_key = SigningKey::from_slice(&node_signing_key_bytes)?;
 
-        let server_addr = run_derive_server(node_signing_key_bytes, &db_arc, 0).await?;
+        let server_signing_key = k256::ecdsa::SigningKey::from_slice(&server_signing_key_bytes)?;
+
+        let server_addr =
+            run_derive_server(server_signing_key_bytes, &db_arc, 0, &NodeConfig::default()).await?;
+
         let url = format!("http://{}", server_addr);
         println!("server listening at {}", url);
         let client = HttpClient::builder().build(url)?;
@@ -1483,73 +5000,323 @@ mod tests {
                     TransactionOpCode::Insert {
                         data: vec![1, 2, 3],
                     },
-                    TransactionOpCode::DeleteAll,
-                    TransactionOpCode::Insert {
-                        data: vec![4, 5, 6],
-                    },
-                    TransactionOpCode::Delete {
-                        data: vec![4, 5, 6],
-                    },
-                    TransactionOpCode::Insert {
-                        data: vec![7, 8, 9],
-                    },
+                    TransactionOpCode::SetCertTTL { data: 3600 },
                 ],
                 pubkey_script: vec![],
             }],
             locktime: 0,
         };
 
-        client
-            .send_transaction(sample_transaction.clone())
+        client.send_transaction(sample_transaction.clone()).await?;
+
+        let block_row = propose_block(&db_arc, &server_signing_key, &NodeConfig::default()).await?;
+        let timestamp = block_row.header.timestamp();
+
+        // a later block must not change a certificate
+        // anchored to an earlier height
+        propose_block(&db_arc, &server_signing_key, &NodeConfig::default()).await?;
+
+        let Certificate::Signed(cert) = client
+            .request_certificate(object_id_raw, vec![1, 2, 3], Some(0), None)
+            .await?
+        else {
+            return Err(anyhow!("expected a signed certificate"));
+        };
+
+        assert_eq!(cert.details.object_id(), object_id_raw);
+        assert!(matches!(
+            &cert.details,
+            CertificateSigningRequestDetails::Membership { claim, .. } if claim == &vec![1, 2, 3]
+        ));
+        assert_eq!(cert.details.block_height(), 0);
+        assert_eq!(hex::encode(cert.details.block_hash()), block_row.hash);
+        assert_eq!(cert.details.expires_at(), timestamp + 3600);
+        assert_eq!(
+            recover_signer_unchecked(&cert.signature.raw, &cert.details.hash()?)?,
+            Address::from_private_key(&server_signing_key)
+        );
+
+        let Certificate::Signed(latest_cert) = client
+            .request_certificate(object_id_raw, vec![1, 2, 3], None, None)
+            .await?
+        else {
+            return Err(anyhow!("expected a signed certificate"));
+        };
+        assert_eq!(latest_cert.details.block_height(), 1);
+
+        match client
+            .request_certificate(object_id_raw, vec![1, 2, 3], Some(2), None)
             .await
-            .unwrap();
+        {
+            Err(jsonrpsee::core::client::error::Error::Call(err)) => {
+                assert_eq!(err.message(), "call execution failed: unknown block height");
+                Ok(())
+            }
 
-        propose_block(&db_arc, &node_signing_key).await?;
+            _ => Err(anyhow!("expected response to be Err(Call(_))")),
+        }?;
 
-        // Query pending transactions from SurrealDB
-        let block_rows: Vec<BlockRow> = db_arc.select("blocks").await?;
+        Ok(())
+    }
 
-        assert_eq!(block_rows.len(), 1);
-        for row in block_rows {
-            println!("Block Hash: {}", row.hash);
-            println!(
-                "Block Header: {}",
-                serde_json::to_string_pretty(&row.header)?
-            );
+    #[tokio::test]
+    async fn reads_certificate_state_as_of_the_requested_block() -> anyhow::Result<()> {
+        // Initialize SurrealDB
+        let db = any::connect("memory").await?;
+        db.use_ns("quible").use_db("quible_node").await?;
+        db::schema::initialize_db(&db).await?;
 
-            match row.transactions[..] {
-                [_, (first_transaction_hash, _)] => {
-                    assert_eq!(first_transaction_hash, sample_transaction.hash_eip191()?);
-                    Ok(())
-                }
+        let db_arc = Arc::new(db);
 
-                _ => Err(anyhow!("unexpected number of transactions")),
-            }?;
-        }
+        let node_signing_key_bytes =
+            hex_literal::hex!("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80");
+        let node_signing_key = SigningKey::from_slice(&node_signing_key_bytes)?;
 
-        let pending_transaction_rows: Vec<PendingTransactionRow> =
-            db_arc.select("pending_transactions").await?;
+        let server_addr =
+            run_derive_server(node_signing_key_bytes, &db_arc, 0, &NodeConfig::default()).await?;
+        let url = format!("http://{}", server_addr);
+        println!("server listening at {}", url);
+        let client = HttpClient::builder().build(url)?;
 
-        assert_eq!(pending_transaction_rows.len(), 0);
+        let object_id_raw = compute_object_id(vec![], 0)?;
+        let creating_transaction = Transaction::Version1 {
+            inputs: vec![],
+            outputs: vec![TransactionOutput::Object {
+                object_id: ObjectIdentifier {
+                    raw: object_id_raw,
+                    mode: ObjectMode::Fresh,
+                },
+                data_script: vec![
+                    TransactionOpCode::Insert { data: vec![1] },
+                    TransactionOpCode::SetCertTTL { data: 3600 },
+                ],
+                pubkey_script: vec![
+                    TransactionOpCode::Dup,
+                    TransactionOpCode::Push {
+                        data: Address::from_private_key(&node_signing_key)
+                            .into_array()
+                            .to_vec(),
+                    },
+                    TransactionOpCode::EqualVerify,
+                    TransactionOpCode::CheckEip191SigVerify,
+                ],
+            }],
+            locktime: 0,
+        };
+        let creating_transaction_hash = creating_transaction.hash_eip191()?;
 
-        let object_rows: Vec<ObjectRow> = db_arc.select("objects").await?;
+        client.send_transaction(creating_transaction).await?;
+        let first_block_row =
+            propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
 
-        match &object_rows[..] {
-            [object_row] => {
-                assert_eq!(object_row.object_id, hex::encode(object_id_raw));
-                assert_eq!(object_row.claims, vec![vec![7, 8, 9]]);
+        let mut updating_transaction = Transaction::Version1 {
+            inputs: vec![TransactionInput {
+                outpoint: TransactionOutpoint {
+                    txid: creating_transaction_hash,
+                    index: 0,
+                },
+                signature_script: vec![],
+            }],
+            outputs: vec![TransactionOutput::Object {
+                object_id: ObjectIdentifier {
+                    raw: object_id_raw,
+                    mode: ObjectMode::Existing { permit_index: 0 },
+                },
+                data_script: vec![
+                    TransactionOpCode::Insert { data: vec![2] },
+                    TransactionOpCode::SetCertTTL { data: 60 },
+                ],
+                pubkey_script: vec![],
+            }],
+            locktime: 0,
+        };
+        sign_transaction_inputs(&mut updating_transaction, &node_signing_key)?;
+
+        client.send_transaction(updating_transaction).await?;
+        let block_row = propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+
+        let Certificate::Signed(cert) = client
+            .request_certificate(object_id_raw, vec![2], None, None)
+            .await?
+        else {
+            return Err(anyhow!("expected a signed certificate"));
+        };
+        assert_eq!(cert.details.expires_at(), block_row.header.timestamp() + 60);
+
+        let expect_call_error = |response: Result<(), jsonrpsee::core::client::error::Error>,
+                                 message: &str| match response {
+            Err(jsonrpsee::core::client::error::Error::Call(err)) => {
+                assert_eq!(err.message(), message);
+                Ok(())
+            }
+
+            _ => Err(anyhow!("expected response to be Err(Call(_))")),
+        };
+
+        // the claim was added after the first block
+        expect_call_error(
+            client
+                .request_certificate(object_id_raw, vec![2], Some(0), None)
+                .await
+                .map(|_| ()),
+            "call execution failed: could not find identity or claim",
+        )?;
+
+        // the TTL is the one set as of the first block
+        let Certificate::Signed(cert) = client
+            .request_certificate(object_id_raw, vec![1], Some(0), None)
+            .await?
+        else {
+            return Err(anyhow!("expected a signed certificate"));
+        };
+        assert_eq!(
+            cert.details.expires_at(),
+            first_block_row.header.timestamp() + 3600
+        );
+
+        let Certificate::Proof(cert) = client
+            .request_certificate(object_id_raw, vec![1], Some(0), Some(true))
+            .await?
+        else {
+            return Err(anyhow!("expected a proof certificate"));
+        };
+        assert_eq!(cert.lifespan, 3600);
+        assert_eq!(cert.transaction_hash, creating_transaction_hash);
+        assert!(crate::cert::verify_proof_certificate_state(&cert));
+
+        let cert = client
+            .request_non_membership_certificate(object_id_raw, vec![2], Some(0))
+            .await?;
+        assert_eq!(
+            cert.details.expires_at(),
+            first_block_row.header.timestamp() + 3600
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn issues_proof_certificates() -> anyhow::Result<()> {
+        // Initialize SurrealDB
+        let db = any::connect("memory").await?;
+        db.use_ns("quible").use_db("quible_node").await?;
+        db::schema::initialize_db(&db).await?;
+
+        let db_arc = Arc::new(db);
+
+        let node_signing_key_bytes =
+            hex_literal::hex!("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80");
+        let node_signing_key = SigningKey::from_slice(&node_signing_key_bytes)?;
+        let node_address = Address::from_private_key(&node_signing_key);
+
+        let server_addr =
+            run_derive_server(node_signing_key_bytes, &db_arc, 0, &NodeConfig::default()).await?;
+        let url = format!("http://{}", server_addr);
+        println!("server listening at {}", url);
+        let client = HttpClient::builder().build(url)?;
+
+        let object_id_raw = compute_object_id(vec![], 0)?;
+        let creating_transaction = Transaction::Version1 {
+            inputs: vec![],
+            outputs: vec![TransactionOutput::Object {
+                object_id: ObjectIdentifier {
+                    raw: object_id_raw,
+                    mode: ObjectMode::Fresh,
+                },
+                data_script: vec![
+                    TransactionOpCode::Insert {
+                        data: vec![1, 2, 3],
+                    },
+                    TransactionOpCode::Insert { data: vec![4] },
+                    TransactionOpCode::SetCertTTL { data: 3600 },
+                ],
+                pubkey_script: vec![],
+            }],
+            locktime: 0,
+        };
+
+        client.send_transaction(creating_transaction).await?;
+        let first_block_row =
+            propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+        let second_block_row =
+            propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+        let timestamp = second_block_row.header.timestamp();
+
+        let Certificate::Proof(certificate) = client
+            .request_certificate(object_id_raw, vec![1, 2, 3], None, Some(true))
+            .await?
+        else {
+            return Err(anyhow!("expected a proof certificate"));
+        };
+
+        assert_eq!(
+            certificate.block_certificate.block_hash,
+            second_block_row.header.hash()?
+        );
+        assert_eq!(certificate.object_id, object_id_raw);
+        assert_eq!(certificate.claim, vec![1, 2, 3]);
+        assert_eq!(certificate.lifespan, 3600);
+        assert_eq!(certificate.expires_at(), timestamp + 3600);
+
+        verify_proof_certificate(&certificate, node_address, timestamp)?;
+        verify_proof_certificate(&certificate, node_address, timestamp + 3600)?;
+        assert!(verify_proof_certificate(&certificate, node_address, timestamp + 3601).is_err());
+        assert!(verify_proof_certificate(&certificate, Address::ZERO, timestamp).is_err());
+
+        // the lifespan is committed to by the object's leaf, and
+        // the proof only holds for the claim it was made for
+        let mut tampered_certificate = certificate.clone();
+        tampered_certificate.lifespan += 1;
+        assert!(verify_proof_certificate(&tampered_certificate, node_address, timestamp).is_err());
+
+        let mut tampered_certificate = certificate.clone();
+        tampered_certificate.claim = vec![4];
+        assert!(verify_proof_certificate(&tampered_certificate, node_address, timestamp).is_err());
+
+        let Certificate::Proof(certificate) = client
+            .request_certificate(object_id_raw, vec![4], Some(0), Some(true))
+            .await?
+        else {
+            return Err(anyhow!("expected a proof certificate"));
+        };
 
+        assert_eq!(
+            certificate.block_certificate.block_hash,
+            first_block_row.header.hash()?
+        );
+        verify_proof_certificate(
+            &certificate,
+            node_address,
+            first_block_row.header.timestamp(),
+        )?;
+
+        assert!(matches!(
+            client
+                .request_certificate(object_id_raw, vec![1, 2, 3], None, Some(false))
+                .await?,
+            Certificate::Signed(_)
+        ));
+
+        match client
+            .request_certificate(object_id_raw, vec![5], None, Some(true))
+            .await
+        {
+            Err(jsonrpsee::core::client::error::Error::Call(err)) => {
+                assert_eq!(
+                    err.message(),
+                    "call execution failed: could not find identity or claim"
+                );
                 Ok(())
             }
 
-            _ => Err(anyhow!("unexpected number of objects")),
+            _ => Err(anyhow!("expected response to be Err(Call(_))")),
         }?;
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn issues_valid_certificates_for_valid_requests() -> anyhow::Result<()> {
+    async fn issues_non_membership_certificates() -> anyhow::Result<()> {
         // Initialize SurrealDB
         let db = any::connect("memory").await?;
         db.use_ns("quible").use_db("quible_node").await?;
@@ -1557,48 +5324,148 @@ mod tests {
 
         let db_arc = Arc::new(db);
 
-        let server_signing_key_bytes =
+        let node_signing_key_bytes =
             hex_literal::hex!("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80");
+        let node_signing_key = SigningKey::from_slice(&node_signing_key_bytes)?;
+        let node_address = Address::from_private_key(&node_signing_key);
 
-        let server_signing_key = k256::ecdsa::SigningKey::from_slice(&server_signing_key_bytes)?;
-
-        let server_addr = run_derive_server(server_signing_key_bytes, &db_arc, 0).await?;
-
+        let server_addr =
+            run_derive_server(node_signing_key_bytes, &db_arc, 0, &NodeConfig::default()).await?;
         let url = format!("http://{}", server_addr);
         println!("server listening at {}", url);
         let client = HttpClient::builder().build(url)?;
-        // let signer_secret = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+
+        let owner_pubkey_script = vec![
+            TransactionOpCode::Dup,
+            TransactionOpCode::Push {
+                data: node_address.into_array().to_vec(),
+            },
+            TransactionOpCode::EqualVerify,
+            TransactionOpCode::CheckEip191SigVerify,
+        ];
+
         let object_id_raw = compute_object_id(vec![], 0)?;
-        let sample_transaction = Transaction::Version1 {
+        let creating_transaction = Transaction::Version1 {
             inputs: vec![],
             outputs: vec![TransactionOutput::Object {
                 object_id: ObjectIdentifier {
                     raw: object_id_raw,
                     mode: ObjectMode::Fresh,
                 },
-                data_script: vec![TransactionOpCode::Insert {
-                    data: vec![1, 2, 3],
-                }],
-                pubkey_script: vec![],
+                data_script: vec![
+                    TransactionOpCode::Insert {
+                        data: vec![1, 2, 3],
+                    },
+                    TransactionOpCode::SetCertTTL { data: 3600 },
+                ],
+                pubkey_script: owner_pubkey_script.clone(),
             }],
             locktime: 0,
         };
+        let creating_transaction_hash = creating_transaction.hash_eip191()?;
 
-        client.send_transaction(sample_transaction.clone()).await?;
-
-        propose_block(&db_arc, &server_signing_key).await?;
+        client.send_transaction(creating_transaction).await?;
+        let block_row = propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
 
         let cert = client
-            .request_certificate(object_id_raw, vec![1, 2, 3])
+            .request_non_membership_certificate(object_id_raw, vec![4], None)
             .await?;
 
-        assert_eq!(cert.details.object_id, object_id_raw);
-        assert_eq!(cert.details.claim, vec![1, 2, 3]);
+        let CertificateSigningRequestDetails::NonMembership {
+            object_id,
+            excluded_claim,
+            block_height,
+            block_hash,
+            expires_at,
+        } = cert.details.clone()
+        else {
+            return Err(anyhow!("expected non-membership details"));
+        };
+
+        assert_eq!(object_id, object_id_raw);
+        assert_eq!(excluded_claim, vec![4]);
+        assert_eq!(block_height, 0);
+        assert_eq!(block_hash, block_row.header.hash()?);
+        assert_eq!(expires_at, block_row.header.timestamp() + 3600);
         assert_eq!(
             recover_signer_unchecked(&cert.signature.raw, &cert.details.hash()?)?,
-            Address::from_private_key(&server_signing_key)
+            node_address
         );
 
+        // the signature cannot be passed off as one for the
+        // claim being present
+        let membership_details = CertificateSigningRequestDetails::Membership {
+            object_id,
+            claim: excluded_claim,
+            block_height,
+            block_hash,
+            expires_at,
+        };
+        assert_ne!(membership_details.hash()?, cert.details.hash()?);
+
+        let expect_call_error = |response: Result<_, jsonrpsee::core::client::error::Error>,
+                                 message: &str| match response {
+            Err(jsonrpsee::core::client::error::Error::Call(err)) => {
+                assert_eq!(err.message(), message);
+                Ok(())
+            }
+
+            _ => Err(anyhow!("expected response to be Err(Call(_))")),
+        };
+
+        expect_call_error(
+            client
+                .request_non_membership_certificate(object_id_raw, vec![1, 2, 3], None)
+                .await,
+            "call execution failed: claim is present in identity",
+        )?;
+
+        expect_call_error(
+            client
+                .request_non_membership_certificate([0u8; 32], vec![4], None)
+                .await,
+            "call execution failed: could not find identity",
+        )?;
+
+        // once the claim is deleted it can be certified absent,
+        // but not as of a block where it was still present
+        let mut deleting_transaction = Transaction::Version1 {
+            inputs: vec![TransactionInput {
+                outpoint: TransactionOutpoint {
+                    txid: creating_transaction_hash,
+                    index: 0,
+                },
+                signature_script: vec![],
+            }],
+            outputs: vec![TransactionOutput::Object {
+                object_id: ObjectIdentifier {
+                    raw: object_id_raw,
+                    mode: ObjectMode::Existing { permit_index: 0 },
+                },
+                data_script: vec![TransactionOpCode::Delete {
+                    data: vec![1, 2, 3],
+                }],
+                pubkey_script: owner_pubkey_script,
+            }],
+            locktime: 0,
+        };
+        sign_transaction_inputs(&mut deleting_transaction, &node_signing_key)?;
+
+        client.send_transaction(deleting_transaction).await?;
+        propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+
+        let cert = client
+            .request_non_membership_certificate(object_id_raw, vec![1, 2, 3], None)
+            .await?;
+        assert_eq!(cert.details.block_height(), 1);
+
+        expect_call_error(
+            client
+                .request_non_membership_certificate(object_id_raw, vec![1, 2, 3], Some(0))
+                .await,
+            "call execution failed: claim is present in identity",
+        )?;
+
         Ok(())
     }
 
@@ -1619,6 +5486,7 @@ mod tests {
             server_signing_key.to_bytes().as_slice().try_into()?,
             &db_arc,
             0,
+            &NodeConfig::default(),
         )
         .await?;
         let url = format!("http://{}", server_addr);
@@ -1643,10 +5511,10 @@ mod tests {
 
         client.send_transaction(sample_transaction.clone()).await?;
 
-        propose_block(&db_arc, &server_signing_key).await?;
+        propose_block(&db_arc, &server_signing_key, &NodeConfig::default()).await?;
 
         let failure_response = client
-            .request_certificate(object_id_raw, vec![4, 5, 6])
+            .request_certificate(object_id_raw, vec![4, 5, 6], None, None)
             .await;
 
         match failure_response {
@@ -1680,12 +5548,13 @@ mod tests {
 
         let user_signing_key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
 
-        let server_addr = run_derive_server(server_signing_key_bytes, &db_arc, 0).await?;
+        let server_addr =
+            run_derive_server(server_signing_key_bytes, &db_arc, 0, &NodeConfig::default()).await?;
         let url = format!("http://{}", server_addr);
         println!("server listening at {}", url);
         let client = HttpClient::builder().build(url)?;
 
-        let block_row = propose_block(&db_arc, &server_signing_key).await?;
+        let block_row = propose_block(&db_arc, &server_signing_key, &NodeConfig::default()).await?;
 
         let coinbase_transaction_hash = match &block_row.transactions[..] {
             [(hash, _)] => Ok(*hash),
@@ -1723,27 +5592,23 @@ mod tests {
         )?
         .to_vec();
 
-        match sample_transaction {
-            Transaction::Version1 { inputs, .. } => {
-                for input in inputs.iter_mut() {
-                    *input = TransactionInput {
-                        outpoint: input.clone().outpoint,
-                        signature_script: vec![
-                            TransactionOpCode::Push {
-                                data: signature.clone(),
-                            },
-                            TransactionOpCode::Push {
-                                data: owner_address.into_array().to_vec(),
-                            },
-                        ],
-                    }
-                }
+        for input in sample_transaction.inputs_mut().iter_mut() {
+            *input = TransactionInput {
+                outpoint: input.clone().outpoint,
+                signature_script: vec![
+                    TransactionOpCode::Push {
+                        data: signature.clone(),
+                    },
+                    TransactionOpCode::Push {
+                        data: owner_address.into_array().to_vec(),
+                    },
+                ],
             }
         }
 
         client.send_transaction(sample_transaction.clone()).await?;
 
-        propose_block(&db_arc, &server_signing_key).await?;
+        propose_block(&db_arc, &server_signing_key, &NodeConfig::default()).await?;
 
         let payload = client
             .fetch_unspent_value_outputs_by_owner(user_address.into_array())
@@ -1770,6 +5635,272 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn fetches_unspent_value_outputs_for_multisig_participants() -> anyhow::Result<()> {
+        // Initialize SurrealDB
+        let db = any::connect("memory").await?;
+        db.use_ns("quible").use_db("quible_node").await?;
+        db::schema::initialize_db(&db).await?;
+
+        let db_arc = Arc::new(db);
+
+        let node_signing_key_bytes =
+            hex_literal::hex!("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80");
+        let node_signing_key = SigningKey::from_slice(&node_signing_key_bytes)?;
+
+        let server_addr =
+            run_derive_server(node_signing_key_bytes, &db_arc, 0, &NodeConfig::default()).await?;
+        let url = format!("http://{}", server_addr);
+        println!("server listening at {}", url);
+        let client = HttpClient::builder().build(url)?;
+
+        let block_row = propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+        let coinbase_transaction_hash = block_row.transactions[0].0;
+
+        let participant_addresses = (0..2)
+            .map(|_| Address::from_private_key(&SigningKey::random(&mut rand::thread_rng())))
+            .collect::<Vec<_>>();
+
+        let mut multisig_transaction = Transaction::Version1 {
+            inputs: vec![TransactionInput {
+                outpoint: TransactionOutpoint {
+                    txid: coinbase_transaction_hash,
+                    index: 0,
+                },
+                signature_script: vec![],
+            }],
+            outputs: vec![TransactionOutput::Value {
+                value: 5,
+                pubkey_script: vec![
+                    TransactionOpCode::Push {
+                        data: participant_addresses
+                            .iter()
+                            .flat_map(|address| address.into_array())
+                            .collect(),
+                    },
+                    TransactionOpCode::CheckMultiSigVerify { data: 2 },
+                ],
+            }],
+            locktime: 0,
+        };
+        sign_transaction_inputs(&mut multisig_transaction, &node_signing_key)?;
+
+        client
+            .send_transaction(multisig_transaction.clone())
+            .await?;
+        propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+
+        for participant_address in participant_addresses {
+            let payload = client
+                .fetch_unspent_value_outputs_by_owner(participant_address.into_array())
+                .await?;
+
+            assert_eq!(payload.total_value, 5);
+            assert_eq!(payload.outputs.len(), 1);
+            assert_eq!(
+                payload.outputs[0].outpoint.txid,
+                multisig_transaction.hash_eip191()?
+            );
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn pays_the_minimum_fee_for_faucet_outputs() -> anyhow::Result<()> {
+        // Initialize SurrealDB
+        let db = any::connect("memory").await?;
+        db.use_ns("quible").use_db("quible_node").await?;
+        db::schema::initialize_db(&db).await?;
+
+        let db_arc = Arc::new(db);
+
+        let node_signing_key_bytes =
+            hex_literal::hex!("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80");
+        let node_signing_key = SigningKey::from_slice(&node_signing_key_bytes)?;
+
+        let node_config = NodeConfig {
+            minimum_fee_rate: 2,
+            reward_schedule: RewardSchedule {
+                initial_reward: 1000,
+                ..RewardSchedule::default()
+            },
+            ..NodeConfig::default()
+        };
+
+        propose_block(&db_arc, &node_signing_key, &node_config).await?;
+
+        generate_intermediate_faucet_output(&QuibleRpcServerImpl {
+            db: db_arc.clone(),
+            node_signer_key: node_signing_key_bytes,
+            node_config: node_config.clone(),
+        })
+        .await?;
+
+        let block_row = propose_block(&db_arc, &node_signing_key, &node_config).await?;
+
+        // the faucet transaction is included, not rejected for
+        // paying no fee
+        let [_, (faucet_transaction_hash, faucet_transaction)] = &block_row.transactions[..] else {
+            return Err(anyhow!("expected the faucet transaction in the block"));
+        };
+
+        let faucet_output_row = fetch_transaction_output_row(
+            &db_arc,
+            &TransactionOutpoint {
+                txid: *faucet_transaction_hash,
+                index: 0,
+            },
+        )
+        .await?
+        .ok_or(anyhow!("expected the faucet output"))?;
+
+        let TransactionOutput::Value { value, .. } = faucet_output_row.output else {
+            return Err(anyhow!("expected a value output"));
+        };
+
+        let fee = 1000 - value;
+        assert!(fee > 0);
+        assert!(fee >= faucet_transaction.encoded_size()? * 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn funds_faucet_outputs_only_from_mature_outputs() -> anyhow::Result<()> {
+        // Initialize SurrealDB
+        let db = any::connect("memory").await?;
+        db.use_ns("quible").use_db("quible_node").await?;
+        db::schema::initialize_db(&db).await?;
+
+        let db_arc = Arc::new(db);
+
+        let node_signing_key_bytes =
+            hex_literal::hex!("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80");
+        let node_signing_key = SigningKey::from_slice(&node_signing_key_bytes)?;
+
+        let node_config = NodeConfig {
+            coinbase_maturity: 2,
+            ..NodeConfig::default()
+        };
+
+        let server = QuibleRpcServerImpl {
+            db: db_arc.clone(),
+            node_signer_key: node_signing_key_bytes,
+            node_config: node_config.clone(),
+        };
+
+        // the first coinbase could only be spent from the block
+        // at height 2
+        propose_block(&db_arc, &node_signing_key, &node_config).await?;
+
+        match generate_intermediate_faucet_output(&server).await {
+            Err(err) => {
+                assert_eq!(
+                    err.message(),
+                    "call execution failed: no value outputs available"
+                );
+                Ok(())
+            }
+
+            _ => Err(anyhow!("expected the immature coinbase to be skipped")),
+        }?;
+
+        propose_block(&db_arc, &node_signing_key, &node_config).await?;
+        generate_intermediate_faucet_output(&server).await?;
+
+        let block_row = propose_block(&db_arc, &node_signing_key, &node_config).await?;
+        assert_eq!(block_row.height, 2);
+        assert_eq!(block_row.transactions.len(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn funds_faucet_outputs_only_from_node_owned_outputs() -> anyhow::Result<()> {
+        // Initialize SurrealDB
+        let db = any::connect("memory").await?;
+        db.use_ns("quible").use_db("quible_node").await?;
+        db::schema::initialize_db(&db).await?;
+
+        let db_arc = Arc::new(db);
+
+        let node_signing_key_bytes =
+            hex_literal::hex!("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80");
+        let node_signing_key = SigningKey::from_slice(&node_signing_key_bytes)?;
+        let node_address = Address::from_private_key(&node_signing_key);
+
+        let server_addr =
+            run_derive_server(node_signing_key_bytes, &db_arc, 0, &NodeConfig::default()).await?;
+        let url = format!("http://{}", server_addr);
+        println!("server listening at {}", url);
+        let client = HttpClient::builder().build(url)?;
+
+        let block_row = propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+        let coinbase_transaction_hash = block_row.transactions[0].0;
+
+        // the node is a participant, but cannot spend the
+        // output alone
+        let participant_address =
+            Address::from_private_key(&SigningKey::random(&mut rand::thread_rng()));
+        let mut multisig_transaction = Transaction::Version1 {
+            inputs: vec![TransactionInput {
+                outpoint: TransactionOutpoint {
+                    txid: coinbase_transaction_hash,
+                    index: 0,
+                },
+                signature_script: vec![],
+            }],
+            outputs: vec![TransactionOutput::Value {
+                value: 5,
+                pubkey_script: vec![
+                    TransactionOpCode::Push {
+                        data: [node_address.into_array(), participant_address.into_array()]
+                            .concat(),
+                    },
+                    TransactionOpCode::CheckMultiSigVerify { data: 2 },
+                ],
+            }],
+            locktime: 0,
+        };
+        sign_transaction_inputs(&mut multisig_transaction, &node_signing_key)?;
+
+        client.send_transaction(multisig_transaction).await?;
+        let block_row = propose_block(&db_arc, &node_signing_key, &NodeConfig::default()).await?;
+
+        let server = QuibleRpcServerImpl {
+            db: db_arc.clone(),
+            node_signer_key: node_signing_key_bytes,
+            node_config: NodeConfig::default(),
+        };
+
+        generate_intermediate_faucet_output(&server).await?;
+
+        let spent_output_hashes: Vec<String> = db_arc
+            .query("SELECT VALUE ->spending->transaction_outputs.transaction_hash FROM intermediate_faucet_outputs")
+            .await?
+            .take::<Vec<Vec<String>>>(0)?
+            .concat();
+        assert_eq!(
+            spent_output_hashes,
+            vec![hex::encode(block_row.transactions[0].0)]
+        );
+
+        match generate_intermediate_faucet_output(&server).await {
+            Err(err) => {
+                assert_eq!(
+                    err.message(),
+                    "call execution failed: no value outputs available"
+                );
+                Ok(())
+            }
+
+            _ => Err(anyhow!("expected the multisig output to be skipped")),
+        }?;
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn fails_to_find_faucet_outputs_when_none_are_generated() -> anyhow::Result<()> {
         // Initialize SurrealDB
@@ -1787,13 +5918,14 @@ mod tests {
             server_signer_key.to_bytes().as_slice().try_into()?,
             &db_arc,
             0,
+            &NodeConfig::default(),
         )
         .await?;
         let url = format!("http://{}", server_addr);
         println!("server listening at {}", url);
         let client = HttpClient::builder().build(url)?;
 
-        let _ = propose_block(&db_arc, &server_signer_key).await?;
+        let _ = propose_block(&db_arc, &server_signer_key, &NodeConfig::default()).await?;
 
         let result = client.request_faucet_output().await;
 
@@ -1827,22 +5959,24 @@ mod tests {
         let faucet_user_signing_key = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
         let faucet_user_address = Address::from_private_key(&faucet_user_signing_key);
 
-        let server_addr = run_derive_server(server_signer_key_bytes, &db_arc, 0).await?;
+        let server_addr =
+            run_derive_server(server_signer_key_bytes, &db_arc, 0, &NodeConfig::default()).await?;
         let url = format!("http://{}", server_addr);
         println!("server listening at {}", url);
         let client = HttpClient::builder().build(url)?;
 
-        let _ = propose_block(&db_arc, &server_signer_key).await?;
+        let _ = propose_block(&db_arc, &server_signer_key, &NodeConfig::default()).await?;
 
         generate_intermediate_faucet_output(&QuibleRpcServerImpl {
             db: db_arc.clone(),
             node_signer_key: server_signer_key_bytes,
+            node_config: NodeConfig::default(),
         })
         .await?;
 
         // extra block proposal ensures that the
         // intermediate transaction is executed
-        let _ = propose_block(&db_arc, &server_signer_key).await?;
+        let _ = propose_block(&db_arc, &server_signer_key, &NodeConfig::default()).await?;
 
         let faucet_payload = client.request_faucet_output().await?;
 
@@ -1875,27 +6009,23 @@ mod tests {
         )?
         .to_vec();
 
-        match sample_transaction {
-            Transaction::Version1 { inputs, .. } => {
-                for input in inputs.iter_mut() {
-                    *input = TransactionInput {
-                        outpoint: input.clone().outpoint,
-                        signature_script: vec![
-                            TransactionOpCode::Push {
-                                data: signature.clone(),
-                            },
-                            TransactionOpCode::Push {
-                                data: faucet_owner_address.into_array().to_vec(),
-                            },
-                        ],
-                    }
-                }
+        for input in sample_transaction.inputs_mut().iter_mut() {
+            *input = TransactionInput {
+                outpoint: input.clone().outpoint,
+                signature_script: vec![
+                    TransactionOpCode::Push {
+                        data: signature.clone(),
+                    },
+                    TransactionOpCode::Push {
+                        data: faucet_owner_address.into_array().to_vec(),
+                    },
+                ],
             }
         }
 
         client.send_transaction(sample_transaction.clone()).await?;
 
-        propose_block(&db_arc, &server_signer_key).await?;
+        propose_block(&db_arc, &server_signer_key, &NodeConfig::default()).await?;
 
         let payload = client
             .fetch_unspent_value_outputs_by_owner(faucet_user_address.into_array())
@@ -2,9 +2,20 @@ use alloy_primitives::{keccak256, Address, B256, U256};
 
 use secp256k1::{
     ecdsa::{RecoverableSignature, RecoveryId},
-    Error, Message, PublicKey, Secp256k1, SecretKey,
+    Error, Message, PublicKey, Secp256k1, SecretKey, SECP256K1,
 };
 
+/// Sighash flag committing a signature to every input and every output. This is what a plain
+/// 65-byte signature commits to.
+pub const SIGHASH_ALL: u8 = 0x01;
+
+/// Sighash flag committing a signature only to the output at the same index as the signed input.
+pub const SIGHASH_SINGLE: u8 = 0x03;
+
+/// Sighash modifier committing a signature only to the signed input, so that other parties can
+/// add inputs of their own.
+pub const SIGHASH_ANYONECANPAY: u8 = 0x80;
+
 /// Recovers the address of the sender using secp256k1 pubkey recovery.
 ///
 /// Converts the public key into an ethereum address by hashing the public key with keccak256.
@@ -17,7 +28,9 @@ pub fn recover_signer_unchecked(sig: &[u8; 65], msg: &[u8; 32]) -> Result<Addres
         RecoveryId::from_i32((sig[64] as i32) - 27)?,
     )?;
 
-    let public = Secp256k1::new().recover_ecdsa(&Message::from_digest(*msg), &sig)?;
+    // the global context avoids building precomputation
+    // tables on every call
+    let public = SECP256K1.recover_ecdsa(&Message::from_digest(*msg), &sig)?;
     Ok(public_key_to_address(public))
 }
 
@@ -40,6 +53,36 @@ pub fn sign_message(secret: B256, message: B256) -> Result<[u8; 65], Error> {
     Ok(sig)
 }
 
+/// Signs message with the given secret key and appends the sighash flags to the signature.
+pub fn sign_message_with_sighash_flags(
+    secret: B256,
+    message: B256,
+    sighash_flags: u8,
+) -> Result<[u8; 66], Error> {
+    let mut sig = [0u8; 66];
+    sig[..65].copy_from_slice(&sign_message(secret, message)?);
+    sig[65] = sighash_flags;
+
+    Ok(sig)
+}
+
+/// Splits a signature into the 65-byte signature and its sighash flags.
+///
+/// Plain 65-byte signatures are treated as [`SIGHASH_ALL`]. Returns `None` when the signature
+/// has the wrong length or carries unknown flags.
+pub fn split_sighash_flags(sig: &[u8]) -> Option<([u8; 65], u8)> {
+    let (sig, sighash_flags) = match sig.len() {
+        65 => (sig, SIGHASH_ALL),
+        66 => (&sig[..65], sig[65]),
+        _ => return None,
+    };
+
+    match sighash_flags & !SIGHASH_ANYONECANPAY {
+        SIGHASH_ALL | SIGHASH_SINGLE => Some((sig.try_into().ok()?, sighash_flags)),
+        _ => None,
+    }
+}
+
 /// Converts a public key into an ethereum address by hashing the encoded public key with
 /// keccak256.
 pub fn public_key_to_address(public: PublicKey) -> Address {
@@ -51,6 +94,8 @@ pub fn public_key_to_address(public: PublicKey) -> Address {
 mod tests {
     use crate::quible_ecdsa_utils::{
         public_key_to_address, recover_signer_unchecked, sign_message,
+        sign_message_with_sighash_flags, split_sighash_flags, SIGHASH_ALL, SIGHASH_ANYONECANPAY,
+        SIGHASH_SINGLE,
     };
     use alloy_primitives::{keccak256, B256};
     use rand;
@@ -97,4 +142,31 @@ mod tests {
         assert_eq!(recover_signer_unchecked(&sig, &hash).ok(), Some(signer));
         assert_eq!(sig, web_signature);
     }
+
+    #[test]
+    fn sighash_flags_round_trip() {
+        let (secret, public) = secp256k1::generate_keypair(&mut rand::thread_rng());
+        let signer = public_key_to_address(public);
+        let secret = B256::from_slice(&secret.secret_bytes()[..]);
+        let hash = keccak256(b"hello world");
+
+        let plain_signature = sign_message(secret, hash).expect("sign message");
+        assert_eq!(
+            split_sighash_flags(&plain_signature),
+            Some((plain_signature, SIGHASH_ALL))
+        );
+
+        let flagged_signature =
+            sign_message_with_sighash_flags(secret, hash, SIGHASH_SINGLE | SIGHASH_ANYONECANPAY)
+                .expect("sign message");
+        let (signature, sighash_flags) =
+            split_sighash_flags(&flagged_signature).expect("valid flags");
+        assert_eq!(sighash_flags, SIGHASH_SINGLE | SIGHASH_ANYONECANPAY);
+        assert_eq!(recover_signer_unchecked(&signature, &hash), Ok(signer));
+
+        let mut unknown_flags = flagged_signature;
+        unknown_flags[65] = 0x02;
+        assert_eq!(split_sighash_flags(&unknown_flags), None);
+        assert_eq!(split_sighash_flags(&flagged_signature[..64]), None);
+    }
 }
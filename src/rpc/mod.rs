@@ -7,7 +7,9 @@ use jsonrpsee::types::ErrorObjectOwned;
 use crate::cert;
 use crate::tx::types::Transaction;
 use crate::types::{
-    self, BlockDetailsPayload, BlockHeightPayload, FaucetOutputPayload, ValueOutputsPayload,
+    self, BlockDetailsPayload, BlockHeightPayload, BlockRewardPayload, FaucetOutputPayload,
+    TransactionInclusionProofPayload, TransactionRejectionPayload, TransactionSimulationPayload,
+    ValueOutputsPayload,
 };
 
 #[rpc(server, client, namespace = "quible")]
@@ -19,6 +21,22 @@ pub trait QuibleRpc {
     #[method(name = "sendRawTransaction")]
     async fn send_raw_transaction(&self, raw_transaction: String) -> Result<(), ErrorObjectOwned>;
 
+    // executes the transaction against the confirmed state
+    // without adding it to the mempool
+    #[method(name = "simulateTransaction")]
+    async fn simulate_transaction(
+        &self,
+        transaction: Transaction,
+    ) -> Result<TransactionSimulationPayload, ErrorObjectOwned>;
+
+    // EIP-712 typed data that CHECKEIP712SIGVERIFY signatures
+    // commit to, ready to pass to eth_signTypedData_v4
+    #[method(name = "getTransactionTypedData")]
+    async fn get_transaction_typed_data(
+        &self,
+        transaction: Transaction,
+    ) -> Result<serde_json::Value, ErrorObjectOwned>;
+
     #[method(name = "checkHealth")]
     async fn check_health(&self) -> Result<types::HealthCheckResponse, ErrorObjectOwned>;
 
@@ -27,8 +45,23 @@ pub trait QuibleRpc {
         &self,
         object_id: [u8; 32],
         claim: Vec<u8>,
-        // requested_at_block_height: u64
-        // TODO: https://linear.app/quible/issue/QUI-106/generate-expiration-dates-corresponding-to-request-block-numbers
+        // defaults to the latest block
+        requested_at_block_height: Option<u64>,
+        // defaults to a certificate signed by the node, rather
+        // than one proven against the block certificate
+        include_proof: Option<bool>,
+    ) -> Result<cert::types::Certificate, ErrorObjectOwned>;
+
+    // certificate that the claim is not in the object's claim
+    // set as of the block, expiring as certificates requested
+    // with requestCertificate do
+    #[method(name = "requestNonMembershipCertificate")]
+    async fn request_non_membership_certificate(
+        &self,
+        object_id: [u8; 32],
+        claim: Vec<u8>,
+        // defaults to the latest block
+        requested_at_block_height: Option<u64>,
     ) -> Result<cert::types::SignedCertificate, ErrorObjectOwned>;
 
     #[method(name = "fetchUnspentValueOutputsByOwner")]
@@ -48,4 +81,34 @@ pub trait QuibleRpc {
         &self,
         height_payload: BlockHeightPayload,
     ) -> Result<BlockDetailsPayload, ErrorObjectOwned>;
+
+    // reward minted by the coinbase at the given height under
+    // the node's reward schedule
+    #[method(name = "getBlockReward")]
+    async fn get_block_reward(
+        &self,
+        height_payload: BlockHeightPayload,
+    ) -> Result<BlockRewardPayload, ErrorObjectOwned>;
+
+    // merkle proof that a confirmed transaction is committed
+    // to by the header of the block including it
+    #[method(name = "getTransactionInclusionProof")]
+    async fn get_transaction_inclusion_proof(
+        &self,
+        transaction_hash: [u8; 32],
+    ) -> Result<TransactionInclusionProofPayload, ErrorObjectOwned>;
+
+    // the node's signature of the state of the block at the
+    // given height, or of the latest block
+    #[method(name = "getBlockCertificate")]
+    async fn get_block_certificate(
+        &self,
+        block_height: Option<u64>,
+    ) -> Result<cert::types::BlockCertificate, ErrorObjectOwned>;
+
+    #[method(name = "getTransactionRejection")]
+    async fn get_transaction_rejection(
+        &self,
+        transaction_hash: [u8; 32],
+    ) -> Result<TransactionRejectionPayload, ErrorObjectOwned>;
 }
@@ -0,0 +1,40 @@
+pub mod tree;
+
+use alloy_primitives::keccak256;
+
+// the state tree holds one leaf per object, committing to its
+// current outpoint and certificate TTL, and one leaf per claim
+// of each object. Keys are prefixed by kind so that object and
+// claim leaves can never collide.
+
+pub fn object_state_key(object_id: &[u8; 32]) -> [u8; 32] {
+    let mut data = vec![0u8];
+    data.extend_from_slice(object_id);
+
+    keccak256(data).0
+}
+
+pub fn claim_state_key(object_id: &[u8; 32], claim: &[u8]) -> [u8; 32] {
+    let mut data = vec![1u8];
+    data.extend_from_slice(object_id);
+    data.extend_from_slice(claim);
+
+    keccak256(data).0
+}
+
+// integers are encoded big-endian, as abi.encodePacked does
+pub fn object_state_value(
+    transaction_hash: &[u8; 32],
+    output_index: u64,
+    cert_ttl: u64,
+) -> [u8; 32] {
+    let mut data = transaction_hash.to_vec();
+    data.extend_from_slice(&output_index.to_be_bytes());
+    data.extend_from_slice(&cert_ttl.to_be_bytes());
+
+    keccak256(data).0
+}
+
+pub fn claim_state_value(claim: &[u8]) -> [u8; 32] {
+    keccak256(claim).0
+}
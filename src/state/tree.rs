@@ -0,0 +1,409 @@
+use alloy_primitives::keccak256;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+// sparse merkle tree over 256-bit keys. A subtree holding no
+// leaves hashes to zero, and a subtree holding a single leaf
+// is that leaf, so leaves sit at the shortest prefix of their
+// key that no other key shares. The shape of the tree, and so
+// its root, only depends on the leaves it holds.
+//
+// Nodes are stored by hash and never deleted, so that proofs
+// can be produced against any root the tree has had.
+
+pub const EMPTY_ROOT: [u8; 32] = [0u8; 32];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateLeaf {
+    pub key: [u8; 32],
+    pub value: [u8; 32],
+}
+
+impl StateLeaf {
+    pub fn hash(&self) -> [u8; 32] {
+        let mut data = vec![0u8];
+        data.extend_from_slice(&self.key);
+        data.extend_from_slice(&self.value);
+
+        keccak256(data).0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum StateNode {
+    Leaf(StateLeaf),
+    Internal { left: [u8; 32], right: [u8; 32] },
+}
+
+impl StateNode {
+    pub fn hash(&self) -> [u8; 32] {
+        match self {
+            StateNode::Leaf(leaf) => leaf.hash(),
+            StateNode::Internal { left, right } => hash_internal(left, right),
+        }
+    }
+}
+
+fn hash_internal(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut data = vec![1u8];
+    data.extend_from_slice(left);
+    data.extend_from_slice(right);
+
+    keccak256(data).0
+}
+
+// bit of the key that chooses between the children of a node
+// at the given depth, most significant bit first
+fn key_bit(key: &[u8; 32], depth: usize) -> bool {
+    key[depth / 8] & (0x80 >> (depth % 8)) != 0
+}
+
+fn common_prefix_length(left: &[u8; 32], right: &[u8; 32]) -> usize {
+    (0..256)
+        .find(|depth| key_bit(left, *depth) != key_bit(right, *depth))
+        .unwrap_or(256)
+}
+
+// hash of a node at the given depth whose child on the side of
+// the key is `node` and whose other child is `sibling`
+fn hash_with_sibling(
+    key: &[u8; 32],
+    depth: usize,
+    node: &[u8; 32],
+    sibling: &[u8; 32],
+) -> [u8; 32] {
+    if key_bit(key, depth) {
+        hash_internal(sibling, node)
+    } else {
+        hash_internal(node, sibling)
+    }
+}
+
+#[async_trait]
+pub trait StateNodeStore {
+    async fn fetch_node(&mut self, hash: [u8; 32]) -> anyhow::Result<Option<StateNode>>;
+
+    async fn store_node(&mut self, node: &StateNode) -> anyhow::Result<()>;
+}
+
+// siblings along the path of a key, from the root down, and
+// the leaf the path ends at, if it does not end at an empty
+// subtree. The key is in the tree if and only if that leaf
+// has the same key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateProof {
+    pub siblings: Vec<[u8; 32]>,
+    pub leaf: Option<StateLeaf>,
+}
+
+impl StateProof {
+    pub fn value(&self, key: &[u8; 32]) -> Option<[u8; 32]> {
+        self.leaf
+            .as_ref()
+            .filter(|leaf| &leaf.key == key)
+            .map(|leaf| leaf.value)
+    }
+}
+
+// checks that the key holds the given value under the given
+// root, or is absent from it if the value is None
+pub fn verify_state_proof(
+    root: &[u8; 32],
+    key: &[u8; 32],
+    value: Option<&[u8; 32]>,
+    proof: &StateProof,
+) -> bool {
+    let depth = proof.siblings.len();
+    if depth > 256 {
+        return false;
+    }
+
+    let node = match (value, &proof.leaf) {
+        (Some(value), Some(leaf)) if &leaf.key == key && &leaf.value == value => leaf.hash(),
+        (None, None) => EMPTY_ROOT,
+
+        // another key can only end the path of this one if the
+        // key is absent and both share the path so far
+        (None, Some(leaf)) if &leaf.key != key && common_prefix_length(&leaf.key, key) >= depth => {
+            leaf.hash()
+        }
+
+        _ => return false,
+    };
+
+    let computed_root = proof
+        .siblings
+        .iter()
+        .enumerate()
+        .rev()
+        .fold(node, |node, (depth, sibling)| {
+            hash_with_sibling(key, depth, &node, sibling)
+        });
+
+    &computed_root == root
+}
+
+pub struct StateTree<S: StateNodeStore> {
+    pub store: S,
+    pub root: [u8; 32],
+}
+
+impl<S: StateNodeStore> StateTree<S> {
+    pub fn new(store: S, root: [u8; 32]) -> Self {
+        StateTree { store, root }
+    }
+
+    async fn fetch_existing_node(&mut self, hash: [u8; 32]) -> anyhow::Result<StateNode> {
+        self.store
+            .fetch_node(hash)
+            .await?
+            .ok_or(anyhow::anyhow!("missing state node {}", hex::encode(hash)))
+    }
+
+    pub async fn prove(&mut self, key: &[u8; 32]) -> anyhow::Result<StateProof> {
+        let mut siblings = vec![];
+        let mut node_hash = self.root;
+
+        while node_hash != EMPTY_ROOT {
+            match self.fetch_existing_node(node_hash).await? {
+                StateNode::Leaf(leaf) => {
+                    return Ok(StateProof {
+                        siblings,
+                        leaf: Some(leaf),
+                    });
+                }
+
+                StateNode::Internal { left, right } => {
+                    if key_bit(key, siblings.len()) {
+                        siblings.push(left);
+                        node_hash = right;
+                    } else {
+                        siblings.push(right);
+                        node_hash = left;
+                    }
+                }
+            }
+        }
+
+        Ok(StateProof {
+            siblings,
+            leaf: None,
+        })
+    }
+
+    pub async fn get(&mut self, key: &[u8; 32]) -> anyhow::Result<Option<[u8; 32]>> {
+        Ok(self.prove(key).await?.value(key))
+    }
+
+    // whether a node can replace a parent whose other child is
+    // empty: empty subtrees and single leaves move up the tree
+    async fn is_collapsible(&mut self, hash: [u8; 32]) -> anyhow::Result<bool> {
+        if hash == EMPTY_ROOT {
+            return Ok(true);
+        }
+
+        Ok(matches!(
+            self.fetch_existing_node(hash).await?,
+            StateNode::Leaf(_)
+        ))
+    }
+
+    async fn store_internal(
+        &mut self,
+        left: [u8; 32],
+        right: [u8; 32],
+    ) -> anyhow::Result<[u8; 32]> {
+        let node = StateNode::Internal { left, right };
+        self.store.store_node(&node).await?;
+
+        Ok(node.hash())
+    }
+
+    // rebuilds the path of a key from the given node up to the
+    // root, collapsing parents that are left with one leaf
+    async fn update_path(
+        &mut self,
+        key: &[u8; 32],
+        node: [u8; 32],
+        siblings: &[[u8; 32]],
+    ) -> anyhow::Result<[u8; 32]> {
+        let mut node = node;
+
+        for (depth, sibling) in siblings.iter().enumerate().rev() {
+            node = if *sibling == EMPTY_ROOT && self.is_collapsible(node).await? {
+                node
+            } else if node == EMPTY_ROOT && self.is_collapsible(*sibling).await? {
+                *sibling
+            } else if key_bit(key, depth) {
+                self.store_internal(*sibling, node).await?
+            } else {
+                self.store_internal(node, *sibling).await?
+            };
+        }
+
+        Ok(node)
+    }
+
+    pub async fn insert(&mut self, key: [u8; 32], value: [u8; 32]) -> anyhow::Result<()> {
+        let proof = self.prove(&key).await?;
+        let mut siblings = proof.siblings;
+
+        let new_leaf = StateNode::Leaf(StateLeaf { key, value });
+        self.store.store_node(&new_leaf).await?;
+
+        let node = match proof.leaf {
+            // the path ends at another key: both keys move down
+            // to where they first differ
+            Some(existing_leaf) if existing_leaf.key != key => {
+                let split_depth = common_prefix_length(&existing_leaf.key, &key);
+                siblings.resize(split_depth, EMPTY_ROOT);
+
+                if key_bit(&key, split_depth) {
+                    self.store_internal(existing_leaf.hash(), new_leaf.hash())
+                        .await?
+                } else {
+                    self.store_internal(new_leaf.hash(), existing_leaf.hash())
+                        .await?
+                }
+            }
+
+            _ => new_leaf.hash(),
+        };
+
+        self.root = self.update_path(&key, node, &siblings).await?;
+
+        Ok(())
+    }
+
+    pub async fn remove(&mut self, key: [u8; 32]) -> anyhow::Result<()> {
+        let proof = self.prove(&key).await?;
+        if proof.value(&key).is_none() {
+            return Ok(());
+        }
+
+        self.root = self.update_path(&key, EMPTY_ROOT, &proof.siblings).await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use alloy_primitives::keccak256;
+    use async_trait::async_trait;
+
+    use super::{verify_state_proof, StateNode, StateNodeStore, StateTree, EMPTY_ROOT};
+
+    #[derive(Default)]
+    struct MemoryStateNodeStore {
+        nodes: HashMap<[u8; 32], StateNode>,
+    }
+
+    #[async_trait]
+    impl StateNodeStore for MemoryStateNodeStore {
+        async fn fetch_node(&mut self, hash: [u8; 32]) -> anyhow::Result<Option<StateNode>> {
+            Ok(self.nodes.get(&hash).cloned())
+        }
+
+        async fn store_node(&mut self, node: &StateNode) -> anyhow::Result<()> {
+            self.nodes.insert(node.hash(), node.clone());
+            Ok(())
+        }
+    }
+
+    fn create_tree() -> StateTree<MemoryStateNodeStore> {
+        StateTree::new(MemoryStateNodeStore::default(), EMPTY_ROOT)
+    }
+
+    #[tokio::test]
+    async fn root_only_depends_on_leaves() -> anyhow::Result<()> {
+        let entries = (0..20u8)
+            .map(|index| (keccak256([index]).0, keccak256([index, index]).0))
+            .collect::<Vec<_>>();
+
+        let mut tree = create_tree();
+        for (key, value) in &entries {
+            tree.insert(*key, *value).await?;
+        }
+
+        let mut reversed_tree = create_tree();
+        for (key, value) in entries.iter().rev() {
+            reversed_tree.insert(*key, *value).await?;
+        }
+        assert_eq!(tree.root, reversed_tree.root);
+
+        // removing leaves restores the root of the tree that
+        // never held them
+        let mut partial_tree = create_tree();
+        for (key, value) in &entries[..5] {
+            partial_tree.insert(*key, *value).await?;
+        }
+        for (key, _) in &entries[5..] {
+            tree.remove(*key).await?;
+        }
+        assert_eq!(tree.root, partial_tree.root);
+
+        for (key, _) in &entries[..5] {
+            tree.remove(*key).await?;
+        }
+        assert_eq!(tree.root, EMPTY_ROOT);
+
+        // updating a value replaces the leaf
+        let mut tree = create_tree();
+        tree.insert(entries[0].0, entries[0].1).await?;
+        tree.insert(entries[0].0, entries[1].1).await?;
+        assert_eq!(tree.get(&entries[0].0).await?, Some(entries[1].1));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn proves_membership_and_non_membership() -> anyhow::Result<()> {
+        let mut tree = create_tree();
+
+        let missing_key = keccak256([0xff]).0;
+        let proof = tree.prove(&missing_key).await?;
+        assert!(verify_state_proof(&tree.root, &missing_key, None, &proof));
+
+        let entries = (0..10u8)
+            .map(|index| (keccak256([index]).0, keccak256([index, index]).0))
+            .collect::<Vec<_>>();
+        for (key, value) in &entries {
+            tree.insert(*key, *value).await?;
+        }
+
+        for (key, value) in &entries {
+            let proof = tree.prove(key).await?;
+            assert!(verify_state_proof(&tree.root, key, Some(value), &proof));
+            assert!(!verify_state_proof(&tree.root, key, None, &proof));
+            assert!(!verify_state_proof(
+                &tree.root,
+                key,
+                Some(&[0u8; 32]),
+                &proof
+            ));
+        }
+
+        let proof = tree.prove(&missing_key).await?;
+        assert!(verify_state_proof(&tree.root, &missing_key, None, &proof));
+        assert!(!verify_state_proof(
+            &tree.root,
+            &missing_key,
+            Some(&[0u8; 32]),
+            &proof
+        ));
+
+        // a proof for one key says nothing about another
+        let proof = tree.prove(&entries[0].0).await?;
+        assert!(!verify_state_proof(
+            &tree.root,
+            &entries[1].0,
+            Some(&entries[1].1),
+            &proof
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,401 @@
+use alloy_primitives::{keccak256, U256};
+use anyhow::anyhow;
+use serde_json::{json, Map, Value};
+
+use super::types::{ObjectMode, Transaction, TransactionOpCode, TransactionOutput};
+
+// EIP-712 typed data for transactions, which CHECKEIP712SIGVERIFY
+// signatures commit to. Unlike the postcard encoding signed by
+// CHECKSIGVERIFY, wallets can display typed data field by field.
+//
+// The domain deliberately has no chainId: wallets refuse to sign
+// for a chainId other than the EVM chain they are connected to.
+// The Quible chain id is part of the message instead.
+pub const DOMAIN_NAME: &str = "Quible";
+pub const DOMAIN_VERSION: &str = "1";
+
+fn transaction_types() -> Value {
+    json!({
+        "EIP712Domain": [
+            { "name": "name", "type": "string" },
+            { "name": "version", "type": "string" },
+        ],
+        "Transaction": [
+            { "name": "version", "type": "uint64" },
+            { "name": "chainId", "type": "uint64" },
+            { "name": "inputs", "type": "TransactionInput[]" },
+            { "name": "outputs", "type": "TransactionOutput[]" },
+            { "name": "locktime", "type": "uint64" },
+        ],
+        "TransactionInput": [
+            { "name": "txid", "type": "bytes32" },
+            { "name": "index", "type": "uint64" },
+        ],
+        "TransactionOutput": [
+            { "name": "kind", "type": "string" },
+            { "name": "value", "type": "uint64" },
+            { "name": "objectId", "type": "bytes32" },
+            { "name": "objectMode", "type": "string" },
+            { "name": "permitIndex", "type": "uint64" },
+            { "name": "dataScript", "type": "ScriptOperation[]" },
+            { "name": "pubkeyScript", "type": "ScriptOperation[]" },
+        ],
+        "ScriptOperation": [
+            { "name": "opcode", "type": "string" },
+            { "name": "data", "type": "bytes" },
+        ],
+    })
+}
+
+fn encode_hex(data: &[u8]) -> Value {
+    Value::String(format!("0x{}", hex::encode(data)))
+}
+
+// integers are encoded as decimal strings, since JSON numbers
+// cannot represent every u64 in javascript
+fn encode_u64(value: u64) -> Value {
+    Value::String(value.to_string())
+}
+
+// opcode names match the ones used in the documentation and
+// the SDK. Integer parameters are encoded big-endian.
+fn encode_opcode(opcode: &TransactionOpCode) -> Value {
+    let (name, data): (&str, Vec<u8>) = match opcode {
+        TransactionOpCode::Push { data } => ("PUSH", data.clone()),
+        TransactionOpCode::CheckEip191SigVerify => ("CHECKSIGVERIFY", vec![]),
+        TransactionOpCode::Dup => ("DUP", vec![]),
+        TransactionOpCode::EqualVerify => ("EQUALVERIFY", vec![]),
+        TransactionOpCode::Insert { data } => ("INSERT", data.clone()),
+        TransactionOpCode::Delete { data } => ("DELETE", data.clone()),
+        TransactionOpCode::DeleteAll => ("DELETEALL", vec![]),
+        TransactionOpCode::SetCertTTL { data } => ("SETCERTTTL", data.to_be_bytes().to_vec()),
+        TransactionOpCode::CheckLockTimeVerify { data } => {
+            ("CHECKLOCKTIMEVERIFY", data.to_be_bytes().to_vec())
+        }
+        TransactionOpCode::CheckSequenceVerify { data } => {
+            ("CHECKSEQUENCEVERIFY", data.to_be_bytes().to_vec())
+        }
+        TransactionOpCode::CheckEip712SigVerify => ("CHECKEIP712SIGVERIFY", vec![]),
+        TransactionOpCode::GrantClaimManager { data } => ("GRANTCLAIMMANAGER", data.clone()),
+        TransactionOpCode::RevokeClaimManager { data } => ("REVOKECLAIMMANAGER", data.clone()),
+        TransactionOpCode::CheckKeccakPreimageVerify => ("CHECKKECCAKPREIMAGEVERIFY", vec![]),
+        TransactionOpCode::If => ("IF", vec![]),
+        TransactionOpCode::Else => ("ELSE", vec![]),
+        TransactionOpCode::EndIf => ("ENDIF", vec![]),
+        TransactionOpCode::CheckMultiSigVerify { data } => {
+            ("CHECKMULTISIGVERIFY", data.to_be_bytes().to_vec())
+        }
+    };
+
+    json!({ "opcode": name, "data": encode_hex(&data) })
+}
+
+fn encode_script(script: &[TransactionOpCode]) -> Value {
+    Value::Array(script.iter().map(encode_opcode).collect())
+}
+
+fn encode_output(output: &TransactionOutput) -> Value {
+    match output {
+        TransactionOutput::Value {
+            value,
+            pubkey_script,
+        } => json!({
+            "kind": "value",
+            "value": encode_u64(*value),
+            "objectId": encode_hex(&[0u8; 32]),
+            "objectMode": "",
+            "permitIndex": encode_u64(0),
+            "dataScript": [],
+            "pubkeyScript": encode_script(pubkey_script),
+        }),
+
+        TransactionOutput::Object {
+            object_id,
+            data_script,
+            pubkey_script,
+        } => {
+            let (object_mode, permit_index) = match object_id.mode {
+                ObjectMode::Fresh => ("fresh", 0),
+                ObjectMode::Existing { permit_index } => ("existing", permit_index),
+            };
+
+            json!({
+                "kind": "object",
+                "value": encode_u64(0),
+                "objectId": encode_hex(&object_id.raw),
+                "objectMode": object_mode,
+                "permitIndex": encode_u64(permit_index),
+                "dataScript": encode_script(data_script),
+                "pubkeyScript": encode_script(pubkey_script),
+            })
+        }
+    }
+}
+
+// builds the eth_signTypedData_v4 payload for a transaction.
+// Signature scripts are left out, since a signature cannot
+// sign itself.
+pub fn transaction_typed_data(transaction: &Transaction) -> Value {
+    let version = match transaction {
+        Transaction::Version1 { .. } => 1,
+        Transaction::Version2 { .. } => 2,
+    };
+
+    let inputs = transaction
+        .inputs()
+        .iter()
+        .map(|input| {
+            json!({
+                "txid": encode_hex(&input.outpoint.txid),
+                "index": encode_u64(input.outpoint.index),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let outputs = transaction
+        .outputs()
+        .iter()
+        .map(encode_output)
+        .collect::<Vec<_>>();
+
+    json!({
+        "types": transaction_types(),
+        "primaryType": "Transaction",
+        "domain": {
+            "name": DOMAIN_NAME,
+            "version": DOMAIN_VERSION,
+        },
+        "message": {
+            "version": encode_u64(version),
+            "chainId": encode_u64(transaction.chain_id().unwrap_or(0)),
+            "inputs": inputs,
+            "outputs": outputs,
+            "locktime": encode_u64(transaction.locktime()),
+        },
+    })
+}
+
+// hash that CHECKEIP712SIGVERIFY signatures commit to
+pub fn compute_eip712_signing_hash(transaction: &Transaction) -> anyhow::Result<[u8; 32]> {
+    hash_typed_data(&transaction_typed_data(transaction))
+}
+
+fn struct_fields<'a>(types: &'a Map<String, Value>, name: &str) -> Option<Vec<(&'a str, &'a str)>> {
+    types.get(name)?.as_array().map(|fields| {
+        fields
+            .iter()
+            .filter_map(|field| Some((field["name"].as_str()?, field["type"].as_str()?)))
+            .collect()
+    })
+}
+
+fn collect_dependencies<'a>(
+    types: &'a Map<String, Value>,
+    name: &'a str,
+    dependencies: &mut Vec<&'a str>,
+) {
+    let name = name.trim_end_matches("[]");
+    if dependencies.contains(&name) {
+        return;
+    }
+
+    let Some(fields) = struct_fields(types, name) else {
+        return;
+    };
+
+    dependencies.push(name);
+    for (_, field_type) in fields {
+        collect_dependencies(types, field_type, dependencies);
+    }
+}
+
+// the struct's own signature followed by the signatures of
+// every struct it references, sorted by name
+fn encode_type(types: &Map<String, Value>, name: &str) -> String {
+    let mut dependencies = vec![];
+    collect_dependencies(types, name, &mut dependencies);
+    dependencies[1..].sort();
+
+    dependencies
+        .into_iter()
+        .filter_map(|dependency| {
+            let fields = struct_fields(types, dependency)?
+                .into_iter()
+                .map(|(field_name, field_type)| format!("{} {}", field_type, field_name))
+                .collect::<Vec<_>>();
+
+            Some(format!("{}({})", dependency, fields.join(",")))
+        })
+        .collect()
+}
+
+fn decode_hex(value: &Value) -> anyhow::Result<Vec<u8>> {
+    let string = value
+        .as_str()
+        .ok_or(anyhow!("typed data: expected hex string"))?;
+    Ok(hex::decode(string.trim_start_matches("0x"))?)
+}
+
+fn decode_uint(value: &Value) -> anyhow::Result<U256> {
+    match value {
+        Value::Number(number) => number
+            .as_u64()
+            .map(U256::from)
+            .ok_or(anyhow!("typed data: expected unsigned integer")),
+        Value::String(string) => match string.strip_prefix("0x") {
+            Some(digits) => Ok(U256::from_str_radix(digits, 16)?),
+            None => Ok(U256::from_str_radix(string, 10)?),
+        },
+        _ => Err(anyhow!("typed data: expected unsigned integer")),
+    }
+}
+
+fn encode_value(
+    types: &Map<String, Value>,
+    value_type: &str,
+    value: &Value,
+) -> anyhow::Result<[u8; 32]> {
+    if let Some(item_type) = value_type.strip_suffix("[]") {
+        let items = value
+            .as_array()
+            .ok_or(anyhow!("typed data: expected array"))?;
+
+        let mut encoded_items = Vec::with_capacity(items.len() * 32);
+        for item in items {
+            encoded_items.extend_from_slice(&encode_value(types, item_type, item)?);
+        }
+
+        return Ok(keccak256(encoded_items).0);
+    }
+
+    if types.contains_key(value_type) {
+        return hash_struct(types, value_type, value);
+    }
+
+    let mut word = [0u8; 32];
+    match value_type {
+        "string" => {
+            let string = value
+                .as_str()
+                .ok_or(anyhow!("typed data: expected string"))?;
+            word = keccak256(string.as_bytes()).0;
+        }
+
+        "bytes" => word = keccak256(decode_hex(value)?).0,
+
+        "bool" => {
+            let flag = value
+                .as_bool()
+                .ok_or(anyhow!("typed data: expected bool"))?;
+            word[31] = flag as u8;
+        }
+
+        "address" => {
+            let address = decode_hex(value)?;
+            if address.len() != 20 {
+                return Err(anyhow!("typed data: address is not 20 bytes"));
+            }
+            word[12..].copy_from_slice(&address);
+        }
+
+        _ if value_type.starts_with("uint") => {
+            word = decode_uint(value)?.to_be_bytes::<32>();
+        }
+
+        _ if value_type.starts_with("bytes") => {
+            let bytes = decode_hex(value)?;
+            if bytes.len() > 32 {
+                return Err(anyhow!("typed data: fixed bytes longer than 32 bytes"));
+            }
+            word[..bytes.len()].copy_from_slice(&bytes);
+        }
+
+        _ => return Err(anyhow!("typed data: unsupported type {}", value_type)),
+    }
+
+    Ok(word)
+}
+
+fn hash_struct(types: &Map<String, Value>, name: &str, value: &Value) -> anyhow::Result<[u8; 32]> {
+    let fields = struct_fields(types, name).ok_or(anyhow!("typed data: unknown type {}", name))?;
+
+    let mut encoded = Vec::with_capacity((fields.len() + 1) * 32);
+    encoded.extend_from_slice(&keccak256(encode_type(types, name)).0);
+    for (field_name, field_type) in fields {
+        encoded.extend_from_slice(&encode_value(types, field_type, &value[field_name])?);
+    }
+
+    Ok(keccak256(encoded).0)
+}
+
+// hashes an eth_signTypedData_v4 payload the way wallets do
+pub fn hash_typed_data(typed_data: &Value) -> anyhow::Result<[u8; 32]> {
+    let types = typed_data["types"]
+        .as_object()
+        .ok_or(anyhow!("typed data: missing types"))?;
+    let primary_type = typed_data["primaryType"]
+        .as_str()
+        .ok_or(anyhow!("typed data: missing primary type"))?;
+
+    let mut encoded = vec![0x19, 0x01];
+    encoded.extend_from_slice(&hash_struct(types, "EIP712Domain", &typed_data["domain"])?);
+    encoded.extend_from_slice(&hash_struct(types, primary_type, &typed_data["message"])?);
+
+    Ok(keccak256(encoded).0)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::hash_typed_data;
+
+    #[test]
+    fn hashes_reference_typed_data() -> anyhow::Result<()> {
+        // example from the EIP-712 specification
+        let typed_data = json!({
+            "types": {
+                "EIP712Domain": [
+                    { "name": "name", "type": "string" },
+                    { "name": "version", "type": "string" },
+                    { "name": "chainId", "type": "uint256" },
+                    { "name": "verifyingContract", "type": "address" },
+                ],
+                "Person": [
+                    { "name": "name", "type": "string" },
+                    { "name": "wallet", "type": "address" },
+                ],
+                "Mail": [
+                    { "name": "from", "type": "Person" },
+                    { "name": "to", "type": "Person" },
+                    { "name": "contents", "type": "string" },
+                ],
+            },
+            "primaryType": "Mail",
+            "domain": {
+                "name": "Ether Mail",
+                "version": "1",
+                "chainId": 1,
+                "verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC",
+            },
+            "message": {
+                "from": {
+                    "name": "Cow",
+                    "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826",
+                },
+                "to": {
+                    "name": "Bob",
+                    "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB",
+                },
+                "contents": "Hello, Bob!",
+            },
+        });
+
+        assert_eq!(
+            hash_typed_data(&typed_data)?,
+            hex_literal::hex!("be609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2")
+        );
+
+        Ok(())
+    }
+}
@@ -2,12 +2,30 @@ use anyhow::anyhow;
 use async_trait::async_trait;
 use sha3::{Digest, Keccak256};
 
-use crate::{
-    quible_ecdsa_utils::recover_signer_unchecked,
-    tx::types::{ObjectMode, Transaction, TransactionInput, TransactionOpCode},
+use crate::tx::types::{
+    BlockHeader, Hashable, ObjectMode, Transaction, TransactionInput, TransactionOpCode,
 };
 
-use super::types::{Hashable, TransactionOutpoint, TransactionOutput};
+use super::eip712::compute_eip712_signing_hash;
+use super::error::TransactionValidationError;
+use super::merkle::compute_merkle_root;
+use super::script::{
+    compute_signing_hash, validate_data_script, validate_pubkey_script, verify_script,
+    ScriptChecker, TransactionScriptChecker,
+};
+use super::sigcache::{PreviewedTransaction, SignatureCache};
+use super::types::{TransactionOutpoint, TransactionOutput};
+
+// upper bound on the total encoded size of the
+// transactions in a block, excluding the coinbase
+pub const MAX_BLOCK_SIZE: u64 = 1_000_000;
+
+// upper bound on the encoded size of a single transaction
+pub const MAX_TRANSACTION_SIZE: u64 = 100_000;
+
+// locktimes below this value are block heights, and
+// locktimes at or above it are unix timestamps
+pub const LOCKTIME_THRESHOLD: u64 = 500_000_000;
 
 #[async_trait]
 pub trait ExecutionContext {
@@ -22,6 +40,11 @@ pub trait ExecutionContext {
         &mut self,
     ) -> anyhow::Result<Option<([u8; 32], Transaction)>>;
 
+    // pending transactions expected to be executed, whose
+    // signatures can be recovered ahead of time. Contexts
+    // that cannot look ahead may return none.
+    async fn preview_pending_transactions(&mut self) -> anyhow::Result<Vec<PreviewedTransaction>>;
+
     // looks up transaction output by outpoint. If not
     // found, an error is thrown. If found but is spent,
     // an error is thrown.
@@ -30,8 +53,54 @@ pub trait ExecutionContext {
         outpoint: TransactionOutpoint,
     ) -> anyhow::Result<TransactionOutput>;
 
-    // marks the transaction as valid in the execution context
-    async fn include_in_next_block(&mut self, transaction_hash: [u8; 32]) -> anyhow::Result<()>;
+    // height and timestamp of the block being built, which
+    // transaction locktimes are enforced against
+    fn block_height(&self) -> u64;
+    fn block_timestamp(&self) -> u64;
+
+    // looks up the height of the block that created the
+    // transaction output at the given outpoint
+    async fn fetch_output_block_height(
+        &mut self,
+        outpoint: TransactionOutpoint,
+    ) -> anyhow::Result<u64>;
+
+    // whether the transaction output at the given outpoint
+    // was created by a coinbase transaction
+    async fn fetch_output_is_coinbase(
+        &mut self,
+        outpoint: TransactionOutpoint,
+    ) -> anyhow::Result<bool>;
+
+    // number of blocks that must pass before a coinbase output
+    // can be spent. Zero allows spending it in the next block.
+    fn coinbase_maturity(&self) -> u64;
+
+    // addresses allowed to insert and delete the claims of the
+    // given object without being able to satisfy its pubkey
+    // script
+    async fn fetch_object_claim_managers(
+        &mut self,
+        object_id: [u8; 32],
+    ) -> anyhow::Result<Vec<[u8; 20]>>;
+
+    // minimum fee, in value units per encoded byte, that a
+    // transaction must pay in order to be included
+    fn minimum_fee_rate(&self) -> u64;
+
+    // chain that Version2 transactions must commit to, and the
+    // height from which transactions must commit to a chain.
+    // Without an activation height Version1 is always accepted.
+    fn chain_id(&self) -> u64;
+    fn chain_id_activation_height(&self) -> Option<u64>;
+
+    // marks the transaction as valid in the execution context,
+    // crediting its fee to the block's coinbase
+    async fn include_in_next_block(
+        &mut self,
+        transaction_hash: [u8; 32],
+        fee: u64,
+    ) -> anyhow::Result<()>;
 
     async fn record_invalid_transaction(
         &mut self,
@@ -58,26 +127,205 @@ pub fn compute_object_id(
         .map_err(|_| anyhow!("failed to slice Keccak256 hash to 32 bytes"))
 }
 
+// a transaction may only be included in a block once its
+// locktime has passed. A locktime of zero never locks.
+pub fn is_final_locktime(locktime: u64, block_height: u64, block_timestamp: u64) -> bool {
+    if locktime == 0 {
+        return true;
+    }
+
+    if locktime < LOCKTIME_THRESHOLD {
+        locktime < block_height
+    } else {
+        locktime < block_timestamp
+    }
+}
+
+// earliest block height and timestamp, from the given ones on,
+// at which a transaction with the given locktime is final
+pub fn final_block_position(locktime: u64, block_height: u64, block_timestamp: u64) -> (u64, u64) {
+    if locktime == 0 {
+        (block_height, block_timestamp)
+    } else if locktime < LOCKTIME_THRESHOLD {
+        (
+            block_height.max(locktime.saturating_add(1)),
+            block_timestamp,
+        )
+    } else {
+        (
+            block_height,
+            block_timestamp.max(locktime.saturating_add(1)),
+        )
+    }
+}
+
+// a transaction must commit to the chain it is executed on,
+// except for Version1 transactions before chain ids activate
+pub fn check_chain_id(
+    transaction: &Transaction,
+    chain_id: u64,
+    activation_height: Option<u64>,
+    block_height: u64,
+) -> Result<(), TransactionValidationError> {
+    match transaction.chain_id() {
+        Some(transaction_chain_id) if transaction_chain_id != chain_id => {
+            Err(TransactionValidationError::ChainIdMismatch)
+        }
+        Some(_) => Ok(()),
+        None => match activation_height {
+            Some(activation_height) if block_height >= activation_height => {
+                Err(TransactionValidationError::MissingChainId)
+            }
+            _ => Ok(()),
+        },
+    }
+}
+
+// whether the signature script authorizes spending an object
+// output as one of the object's claim managers, using the
+// same shape as a pay-to-address signature script
+async fn is_signed_by_claim_manager<C: ExecutionContext, S: ScriptChecker>(
+    context: &mut C,
+    object_id: [u8; 32],
+    signature_script: &[TransactionOpCode],
+    checker: &S,
+) -> anyhow::Result<bool> {
+    let [TransactionOpCode::Push { data: signature }, TransactionOpCode::Push { data: address }] =
+        signature_script
+    else {
+        return Ok(false);
+    };
+
+    let claim_managers = context.fetch_object_claim_managers(object_id).await?;
+    if !claim_managers
+        .iter()
+        .any(|claim_manager| claim_manager.as_slice() == address.as_slice())
+    {
+        return Ok(false);
+    }
+
+    Ok(checker.check_eip191_signature(signature, address).is_ok())
+}
+
+// checks that an object continuation permitted by a claim
+// manager only changes the object's claims
+fn check_claim_manager_update(
+    data_script: &[TransactionOpCode],
+    pubkey_script: &[TransactionOpCode],
+    permit_pubkey_script: &[TransactionOpCode],
+) -> Result<(), TransactionValidationError> {
+    let only_changes_claims = data_script.iter().all(|opcode| {
+        matches!(
+            opcode,
+            TransactionOpCode::Insert { .. } | TransactionOpCode::Delete { .. }
+        )
+    });
+
+    if !only_changes_claims || pubkey_script != permit_pubkey_script {
+        return Err(TransactionValidationError::UnauthorizedObjectUpdate);
+    }
+
+    Ok(())
+}
+
+// checks that a block header commits to the block's
+// transactions, given coinbase first
+pub fn validate_block_header(
+    block_header: &BlockHeader,
+    transactions: &[Transaction],
+) -> anyhow::Result<()> {
+    let transaction_hashes = transactions
+        .iter()
+        .map(|transaction| transaction.hash_eip191())
+        .collect::<anyhow::Result<Vec<[u8; 32]>>>()?;
+
+    if compute_merkle_root(&transaction_hashes) != block_header.merkle_root() {
+        return Err(anyhow!(
+            "block header merkle root does not match transactions"
+        ));
+    }
+
+    Ok(())
+}
+
+// checks that a block's coinbase transaction does not pay
+// out more than the block reward plus the fees collected
+// from the other transactions in the block
+pub fn validate_coinbase_transaction(
+    coinbase_transaction: &Transaction,
+    block_reward: u64,
+    collected_fees: u64,
+) -> anyhow::Result<()> {
+    let mut output_value = 0u64;
+    for output in coinbase_transaction.outputs() {
+        if let TransactionOutput::Value { value, .. } = output {
+            output_value = output_value
+                .checked_add(*value)
+                .ok_or(anyhow!("coinbase output value overflows"))?;
+        }
+    }
+
+    let maximum_value = block_reward
+        .checked_add(collected_fees)
+        .ok_or(anyhow!("block reward plus fees overflows"))?;
+
+    if output_value > maximum_value {
+        return Err(anyhow!(
+            "coinbase output value exceeds block reward plus fees"
+        ));
+    }
+
+    Ok(())
+}
+
 // pulls pending transactions from context and executes
 // them one-by-one until no more pending transactions
 // will fit in the block
 pub async fn collect_valid_block_transactions<C: ExecutionContext>(
     context: &mut C,
 ) -> anyhow::Result<()> {
+    // signature recovery dominates the cost of execution, so
+    // it is done up front and in parallel for the whole batch
+    let signature_cache =
+        SignatureCache::recover_batch(context.preview_pending_transactions().await?).await?;
+
     while let Some((transaction_hash, transaction)) =
         context.fetch_next_pending_transaction().await?
     {
-        let Transaction::Version1 {
-            inputs, outputs, ..
-        } = transaction.clone();
+        let inputs = transaction.inputs().clone();
+        let outputs = transaction.outputs().clone();
+        let locktime = transaction.locktime();
 
         let execute_transaction = async {
+            let transaction_size = transaction.encoded_size()?;
+            if transaction_size > MAX_TRANSACTION_SIZE {
+                return Err(TransactionValidationError::TransactionTooLarge.into());
+            }
+
+            check_chain_id(
+                &transaction,
+                context.chain_id(),
+                context.chain_id_activation_height(),
+                context.block_height(),
+            )?;
+
+            if !is_final_locktime(locktime, context.block_height(), context.block_timestamp()) {
+                return Err(TransactionValidationError::LocktimeNotReached.into());
+            }
+
+            let signing_hash = compute_signing_hash(&transaction)?;
+            let mut eip712_signing_hash = None;
+
             let mut spent_outpoints = Vec::<TransactionOutpoint>::new();
             let mut input_value = 0u64;
+
+            // indices of object inputs authorized by a claim
+            // manager rather than by the object's pubkey script
+            let mut claim_manager_permits = Vec::<usize>::new();
             let mut output_value = 0u64;
 
             for (
-                _index,
+                input_index,
                 TransactionInput {
                     outpoint,
                     signature_script,
@@ -86,23 +334,21 @@ pub async fn collect_valid_block_transactions<C: ExecutionContext>(
             {
                 if spent_outpoints.contains(&outpoint.clone()) {
                     // TODO: serialize outpoint details for error message
-                    return Err(anyhow!("cannot spend output twice"));
+                    return Err(TransactionValidationError::DoubleSpend.into());
                 }
 
                 let output_being_spent = context.fetch_unspent_output(outpoint.clone()).await?;
                 spent_outpoints.push(outpoint.clone());
 
-                let mut stack: Vec<Vec<u8>> = vec![];
-
-                for opcode in signature_script {
-                    match opcode {
-                        TransactionOpCode::Push { data } => {
-                            stack.push(data.clone());
-                        }
-
-                        _ => {
-                            return Err(anyhow!("only pushes are allowed in signature scripts"));
-                        }
+                if context.coinbase_maturity() > 0
+                    && context.fetch_output_is_coinbase(outpoint.clone()).await?
+                {
+                    let output_block_height =
+                        context.fetch_output_block_height(outpoint.clone()).await?;
+                    if context.block_height()
+                        < output_block_height.saturating_add(context.coinbase_maturity())
+                    {
+                        return Err(TransactionValidationError::ImmatureCoinbaseSpend.into());
                     }
                 }
 
@@ -111,155 +357,188 @@ pub async fn collect_valid_block_transactions<C: ExecutionContext>(
                     TransactionOutput::Object { pubkey_script, .. } => pubkey_script,
                 };
 
-                for opcode in pubkey_script {
-                    match opcode {
-                        TransactionOpCode::Dup => {
-                            let maybe_item = stack.pop();
-                            if let Some(item) = maybe_item {
-                                stack.push(item.clone());
-                                stack.push(item);
-                            }
-                        }
-
-                        TransactionOpCode::Push { data } => {
-                            stack.push(data.clone());
-                        }
-
-                        TransactionOpCode::EqualVerify => {
-                            let left_maybe = stack.pop();
-                            let right_maybe = stack.pop();
-
-                            match (left_maybe, right_maybe) {
-                                (Some(left), Some(right)) => {
-                                    if left != right {
-                                        return Err(anyhow!("pubkey script failed: EQUALVERIFY"));
-                                    }
-                                }
-
-                                _ => {
-                                    return Err(anyhow!("pubkey script failed: EQUALVERIFY"));
-                                }
-                            }
-                        }
-
-                        TransactionOpCode::CheckEip191SigVerify => {
-                            let pubkey_maybe = stack.pop();
-                            let sig_maybe = stack.pop();
-
-                            match (pubkey_maybe, sig_maybe) {
-                                (Some(pubkey), Some(sig)) => {
-                                    let signable_transaction = &mut transaction.to_owned();
-                                    match signable_transaction {
-                                        Transaction::Version1 { inputs, .. } => {
-                                            for input in inputs.iter_mut() {
-                                                *input = TransactionInput {
-                                                    outpoint: input.clone().outpoint,
-                                                    signature_script: vec![],
-                                                };
-                                            }
-                                        }
-                                    }
+                // only relative timelocks need to know when the
+                // spent output was created
+                let output_block_height = if pubkey_script
+                    .iter()
+                    .any(|opcode| matches!(opcode, TransactionOpCode::CheckSequenceVerify { .. }))
+                {
+                    Some(context.fetch_output_block_height(outpoint.clone()).await?)
+                } else {
+                    None
+                };
 
-                                    let signable_transaction_hash =
-                                        signable_transaction.hash_eip191()?;
-                                    let sig_slice: [u8; 65] = sig.try_into().map_err(|_| {
-                                        anyhow!("pubkey script failed (signature is not 65 bytes)")
-                                    })?;
-
-                                    let signer = recover_signer_unchecked(
-                                        &sig_slice,
-                                        &signable_transaction_hash,
-                                    )?;
-                                    let signer_slice = signer.into_array().to_vec();
-                                    if pubkey != signer_slice {
-                                        return Err(anyhow!(
-                                            "pubkey script failed (signer does not match pubkey)"
-                                        ));
-                                    }
-                                }
+                if eip712_signing_hash.is_none()
+                    && pubkey_script
+                        .iter()
+                        .any(|opcode| matches!(opcode, TransactionOpCode::CheckEip712SigVerify))
+                {
+                    eip712_signing_hash = Some(compute_eip712_signing_hash(&transaction)?);
+                }
 
-                                _ => {
-                                    return Err(anyhow!(
-                                        "pubkey script failed: CHECKSIGVERIFY when stack is empty"
-                                    ));
-                                }
-                            }
-                        }
+                let checker = TransactionScriptChecker {
+                    transaction: &transaction,
+                    input_index,
+                    signing_hash,
+                    eip712_signing_hash,
+                    signature_cache: &signature_cache,
+                    block_height: context.block_height(),
+                    output_block_height,
+                };
 
-                        _ => {}
+                if let Err(error) = verify_script(signature_script, &pubkey_script, &checker) {
+                    let TransactionOutput::Object { object_id, .. } = &output_being_spent else {
+                        return Err(error);
+                    };
+
+                    if !is_signed_by_claim_manager(
+                        context,
+                        object_id.raw,
+                        signature_script,
+                        &checker,
+                    )
+                    .await?
+                    {
+                        return Err(error);
                     }
+
+                    claim_manager_permits.push(input_index);
                 }
 
                 match output_being_spent {
                     TransactionOutput::Value { value, .. } => {
-                        input_value += value;
+                        input_value = input_value
+                            .checked_add(value)
+                            .ok_or(TransactionValidationError::ValueOverflow)?;
                     }
 
                     _ => {}
                 }
             }
 
+            // objects are linear: spending an object output either
+            // continues it in exactly one output or destroys it
+            let mut continued_object_ids = Vec::<[u8; 32]>::new();
+            let mut continued_permits = Vec::<usize>::new();
+
+            for output in outputs.iter() {
+                match output {
+                    TransactionOutput::Value { pubkey_script, .. } => {
+                        validate_pubkey_script(pubkey_script)?;
+                    }
+
+                    TransactionOutput::Object {
+                        data_script,
+                        pubkey_script,
+                        ..
+                    } => {
+                        validate_data_script(data_script)?;
+                        validate_pubkey_script(pubkey_script)?;
+                    }
+                }
+            }
+
             for (index, output) in outputs.iter().enumerate() {
                 match output {
                     TransactionOutput::Value { value, .. } => {
-                        output_value += value;
+                        output_value = output_value
+                            .checked_add(*value)
+                            .ok_or(TransactionValidationError::ValueOverflow)?;
                     }
 
-                    TransactionOutput::Object { object_id, .. } => {
-                        match object_id.mode {
-                            ObjectMode::Fresh => {
-                                let expected_object_id =
-                                    compute_object_id(inputs.clone(), index.try_into()?)?;
-
-                                if object_id.raw != expected_object_id {
-                                    return Err(anyhow!("object id invalid"));
-                                }
+                    TransactionOutput::Object {
+                        object_id,
+                        data_script,
+                        pubkey_script,
+                    } => match object_id.mode {
+                        ObjectMode::Fresh => {
+                            let expected_object_id =
+                                compute_object_id(inputs.clone(), index.try_into()?)?;
+
+                            if object_id.raw != expected_object_id {
+                                return Err(TransactionValidationError::InvalidObjectId.into());
                             }
+                        }
 
-                            ObjectMode::Existing { permit_index } => {
-                                let permit_index_usize: usize = permit_index.try_into()?;
+                        ObjectMode::Existing { permit_index } => {
+                            let permit_index_usize: usize = permit_index.try_into()?;
 
-                                match inputs.clone().get(permit_index_usize) {
-                                    Some(input) => {
-                                        let output_being_spent = context
-                                            .fetch_unspent_output(input.outpoint.clone())
-                                            .await?;
+                            match inputs.clone().get(permit_index_usize) {
+                                Some(input) => {
+                                    let output_being_spent = context
+                                        .fetch_unspent_output(input.outpoint.clone())
+                                        .await?;
+
+                                    match output_being_spent {
+                                        TransactionOutput::Value { .. } => {
+                                            return Err(
+                                                TransactionValidationError::NonObjectPermit.into(),
+                                            );
+                                        }
+
+                                        TransactionOutput::Object {
+                                            object_id: permit_object_id,
+                                            pubkey_script: permit_pubkey_script,
+                                            ..
+                                        } => {
+                                            if object_id.raw != permit_object_id.raw {
+                                                return Err(
+                                                    TransactionValidationError::ObjectIdMismatch
+                                                        .into(),
+                                                );
+                                            }
 
-                                        match output_being_spent {
-                                            TransactionOutput::Value { .. } => {
-                                                return Err(anyhow!(
-                                                    "non-object output cannot be used as a permit"
-                                                ));
+                                            if claim_manager_permits.contains(&permit_index_usize) {
+                                                check_claim_manager_update(
+                                                    data_script,
+                                                    pubkey_script,
+                                                    &permit_pubkey_script,
+                                                )?;
                                             }
 
-                                            TransactionOutput::Object {
-                                                object_id: permit_object_id,
-                                                ..
-                                            } => {
-                                                if object_id.raw != permit_object_id.raw {
-                                                    return Err(anyhow!("object id does not match permitted object id"));
-                                                }
+                                            if continued_object_ids.contains(&object_id.raw) {
+                                                return Err(TransactionValidationError::DuplicateObjectContinuation.into());
                                             }
+
+                                            continued_object_ids.push(object_id.raw);
+                                            continued_permits.push(permit_index_usize);
                                         }
                                     }
+                                }
 
-                                    None => {
-                                        return Err(anyhow!("permit index out of bounds"));
-                                    }
+                                None => {
+                                    return Err(TransactionValidationError::BadPermitIndex.into());
                                 }
                             }
                         }
-                    }
+                    },
                 }
             }
 
-            // TODO: https://linear.app/quible/issue/QUI-93/enforce-transaction-fees
-            dbg!(output_value, input_value);
+            // claim managers cannot destroy objects
+            if claim_manager_permits
+                .iter()
+                .any(|input_index| !continued_permits.contains(input_index))
+            {
+                return Err(TransactionValidationError::UnauthorizedObjectUpdate.into());
+            }
+
             if output_value > input_value {
-                return Err(anyhow!("output value exceeds input value"));
+                return Err(TransactionValidationError::OutputValueExceedsInput.into());
             }
 
-            context.include_in_next_block(transaction_hash).await?;
+            // the fee is implicit: whatever value the inputs
+            // carry that the outputs do not claim
+            let fee = input_value - output_value;
+            let minimum_fee = transaction_size
+                .checked_mul(context.minimum_fee_rate())
+                .ok_or(TransactionValidationError::ValueOverflow)?;
+
+            if fee < minimum_fee {
+                return Err(TransactionValidationError::FeeRateBelowMinimum.into());
+            }
+
+            context.include_in_next_block(transaction_hash, fee).await?;
 
             Ok(())
         };
@@ -279,15 +558,21 @@ mod tests {
     use std::collections::HashMap;
 
     use crate::quible_ecdsa_utils::sign_message;
+    use crate::tx::error::TransactionValidationError;
+    use crate::tx::merkle::compute_merkle_root;
+    use crate::tx::sigcache::PreviewedTransaction;
     use crate::tx::types::{
-        Hashable, ObjectIdentifier, ObjectMode, Transaction, TransactionInput, TransactionOpCode,
-        TransactionOutpoint, TransactionOutput,
+        BlockHeader, Hashable, ObjectIdentifier, ObjectMode, Transaction, TransactionInput,
+        TransactionOpCode, TransactionOutpoint, TransactionOutput,
     };
     use alloy_primitives::{Address, B256};
     use anyhow::anyhow;
     use async_trait::async_trait;
 
-    use super::{collect_valid_block_transactions, compute_object_id, ExecutionContext};
+    use super::{
+        collect_valid_block_transactions, compute_object_id, validate_block_header,
+        validate_coinbase_transaction, ExecutionContext, LOCKTIME_THRESHOLD, MAX_TRANSACTION_SIZE,
+    };
 
     struct TestingExecutionContext {
         pub transaction_map: HashMap<[u8; 32], Transaction>,
@@ -295,6 +580,17 @@ mod tests {
         pub spent_outpoints: Vec<TransactionOutpoint>,
         pub included_transactions: Vec<[u8; 32]>,
         pub failed_transactions: Vec<([u8; 32], anyhow::Error)>,
+        pub minimum_fee_rate: u64,
+        pub chain_id: u64,
+        pub chain_id_activation_height: Option<u64>,
+        pub collected_fees: u64,
+        pub block_height: u64,
+        pub block_timestamp: u64,
+        pub transaction_heights: HashMap<[u8; 32], u64>,
+        pub coinbase_transactions: Vec<[u8; 32]>,
+        pub coinbase_maturity: u64,
+        pub claim_managers: HashMap<[u8; 32], Vec<[u8; 20]>>,
+        pub preview_pending_transactions: bool,
     }
 
     fn create_context(
@@ -307,14 +603,15 @@ mod tests {
             .map(|tx| (tx.hash_eip191().unwrap(), tx.clone()))
             .collect();
         let mut spent_outpoints: Vec<TransactionOutpoint> = Vec::new();
+        let mut transaction_heights: HashMap<[u8; 32], u64> = HashMap::new();
 
         for transaction in history {
             let transaction_hash = transaction.clone().hash_eip191().unwrap();
             transaction_map.insert(transaction_hash, transaction.clone());
+            transaction_heights.insert(transaction_hash, 0);
 
-            let Transaction::Version1 { inputs, .. } = transaction;
-            for input in inputs {
-                spent_outpoints.push(input.outpoint);
+            for input in transaction.inputs() {
+                spent_outpoints.push(input.outpoint.clone());
             }
         }
 
@@ -324,6 +621,17 @@ mod tests {
             spent_outpoints,
             included_transactions: vec![],
             failed_transactions: vec![],
+            minimum_fee_rate: 0,
+            chain_id: 0,
+            chain_id_activation_height: None,
+            collected_fees: 0,
+            block_height: 1,
+            block_timestamp: 0,
+            transaction_heights,
+            coinbase_transactions: vec![],
+            coinbase_maturity: 0,
+            claim_managers: HashMap::new(),
+            preview_pending_transactions: true,
         }
     }
 
@@ -341,41 +649,131 @@ mod tests {
             Ok(entry)
         }
 
+        async fn preview_pending_transactions(
+            &mut self,
+        ) -> anyhow::Result<Vec<PreviewedTransaction>> {
+            if !self.preview_pending_transactions {
+                return Ok(vec![]);
+            }
+
+            let spent_pubkey_script = |outpoint: &TransactionOutpoint| {
+                let output = self
+                    .transaction_map
+                    .get(&outpoint.txid)?
+                    .outputs()
+                    .get(usize::try_from(outpoint.index).ok()?)?;
+
+                match output {
+                    TransactionOutput::Value { pubkey_script, .. }
+                    | TransactionOutput::Object { pubkey_script, .. } => {
+                        Some(pubkey_script.clone())
+                    }
+                }
+            };
+
+            Ok(self
+                .mempool
+                .iter()
+                .map(|(_, transaction)| PreviewedTransaction {
+                    transaction: transaction.clone(),
+                    spent_pubkey_scripts: transaction
+                        .inputs()
+                        .iter()
+                        .map(|input| spent_pubkey_script(&input.outpoint))
+                        .collect(),
+                })
+                .collect())
+        }
+
         async fn fetch_unspent_output(
             &mut self,
             outpoint: TransactionOutpoint,
         ) -> anyhow::Result<TransactionOutput> {
             if self.spent_outpoints.contains(&outpoint) {
-                return Err(anyhow!("cannot spend output twice"));
+                return Err(TransactionValidationError::DoubleSpend.into());
             }
 
             let transaction = self
                 .transaction_map
                 .get(&outpoint.txid)
-                .ok_or(anyhow!("transaction hash not found!"))?;
+                .ok_or(TransactionValidationError::MissingOutpoint)?;
 
-            let Transaction::Version1 { outputs, .. } = transaction;
-            match outputs.get::<usize>(outpoint.index.try_into().unwrap()) {
+            match transaction
+                .outputs()
+                .get::<usize>(outpoint.index.try_into().unwrap())
+            {
                 Some(output) => Ok(output.clone()),
-                None => Err(anyhow!("outpoint index out of bounds for transaction")),
+                None => Err(TransactionValidationError::MissingOutpoint.into()),
             }
         }
 
+        fn block_height(&self) -> u64 {
+            self.block_height
+        }
+
+        fn block_timestamp(&self) -> u64 {
+            self.block_timestamp
+        }
+
+        async fn fetch_output_block_height(
+            &mut self,
+            outpoint: TransactionOutpoint,
+        ) -> anyhow::Result<u64> {
+            self.transaction_heights
+                .get(&outpoint.txid)
+                .copied()
+                .ok_or(TransactionValidationError::MissingOutpoint.into())
+        }
+
+        async fn fetch_output_is_coinbase(
+            &mut self,
+            outpoint: TransactionOutpoint,
+        ) -> anyhow::Result<bool> {
+            Ok(self.coinbase_transactions.contains(&outpoint.txid))
+        }
+
+        fn coinbase_maturity(&self) -> u64 {
+            self.coinbase_maturity
+        }
+
+        async fn fetch_object_claim_managers(
+            &mut self,
+            object_id: [u8; 32],
+        ) -> anyhow::Result<Vec<[u8; 20]>> {
+            Ok(self
+                .claim_managers
+                .get(&object_id)
+                .cloned()
+                .unwrap_or_default())
+        }
+
+        fn minimum_fee_rate(&self) -> u64 {
+            self.minimum_fee_rate
+        }
+
+        fn chain_id(&self) -> u64 {
+            self.chain_id
+        }
+
+        fn chain_id_activation_height(&self) -> Option<u64> {
+            self.chain_id_activation_height
+        }
+
         async fn include_in_next_block(
             &mut self,
             transaction_hash: [u8; 32],
+            fee: u64,
         ) -> anyhow::Result<()> {
             let transaction = self
                 .transaction_map
                 .get(&transaction_hash)
                 .ok_or(anyhow!("transaction hash not found!"))?;
-            let Transaction::Version1 { inputs, .. } = transaction;
-
-            for input in inputs {
+            for input in transaction.inputs() {
                 self.spent_outpoints.push(input.clone().outpoint);
             }
 
             self.included_transactions.push(transaction_hash);
+            self.collected_fees += fee;
 
             Ok(())
         }
@@ -536,77 +934,88 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn pubkey_script() -> anyhow::Result<()> {
-        let signer_secret = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
-        let signer_address = Address::from_private_key(&signer_secret);
-
-        // TODO: https://linear.app/quible/issue/QUI-95/refactor-transaction-signing
-        let create_subcontext =
-            |include_signature: bool| -> anyhow::Result<TestingExecutionContext> {
-                let coinbase = Transaction::Version1 {
-                    inputs: vec![],
-                    outputs: vec![TransactionOutput::Value {
-                        value: 5,
-                        pubkey_script: vec![
-                            TransactionOpCode::Dup,
-                            TransactionOpCode::Push {
-                                data: signer_address.into_array().to_vec(),
-                            },
-                            TransactionOpCode::EqualVerify,
-                            TransactionOpCode::CheckEip191SigVerify,
-                        ],
-                    }],
-                    locktime: 0,
-                };
+    async fn collects_implicit_transaction_fees() -> anyhow::Result<()> {
+        let coinbase = Transaction::Version1 {
+            inputs: vec![],
+            outputs: vec![
+                TransactionOutput::Value {
+                    value: 5,
+                    pubkey_script: vec![],
+                },
+                TransactionOutput::Value {
+                    value: 7,
+                    pubkey_script: vec![],
+                },
+            ],
+            locktime: 0,
+        };
 
-                let coinbase_hash = coinbase.hash_eip191()?;
+        let coinbase_hash = coinbase.hash_eip191()?;
 
-                let transaction = &mut Transaction::Version1 {
+        let mut context = create_context(
+            vec![coinbase],
+            (0..2)
+                .map(|index| Transaction::Version1 {
                     inputs: vec![TransactionInput {
                         outpoint: TransactionOutpoint {
                             txid: coinbase_hash,
-                            index: 0,
+                            index,
                         },
                         signature_script: vec![],
                     }],
                     outputs: vec![TransactionOutput::Value {
-                        value: 5,
+                        value: 3,
                         pubkey_script: vec![],
                     }],
                     locktime: 0,
-                }
-                .to_owned();
+                })
+                .collect(),
+        );
 
-                let signature = sign_message(
-                    B256::from_slice(&signer_secret.to_bytes()[..]),
-                    transaction.hash_eip191()?.into(),
-                )?
-                .to_vec();
+        collect_valid_block_transactions(&mut context).await?;
 
-                if include_signature {
-                    match transaction {
-                        Transaction::Version1 { inputs, .. } => {
-                            for input in inputs.iter_mut() {
-                                *input = TransactionInput {
-                                    outpoint: input.clone().outpoint,
-                                    signature_script: vec![
-                                        TransactionOpCode::Push {
-                                            data: signature.clone(),
-                                        },
-                                        TransactionOpCode::Push {
-                                            data: signer_address.into_array().to_vec(),
-                                        },
-                                    ],
-                                }
-                            }
-                        }
-                    }
-                }
+        assert_eq!(context.included_transactions.len(), 2);
+        assert_eq!(context.failed_transactions.len(), 0);
+        assert_eq!(context.collected_fees, 2 + 4);
 
-                Ok(create_context(vec![coinbase], vec![transaction.clone()]))
-            };
+        Ok(())
+    }
 
-        let mut failure_context = create_subcontext(false)?;
+    #[tokio::test]
+    async fn fee_rate_must_meet_minimum() -> anyhow::Result<()> {
+        let coinbase = Transaction::Version1 {
+            inputs: vec![],
+            outputs: vec![TransactionOutput::Value {
+                value: 5000,
+                pubkey_script: vec![],
+            }],
+            locktime: 0,
+        };
+
+        let coinbase_hash = coinbase.hash_eip191()?;
+
+        let create_transaction = |value: u64| Transaction::Version1 {
+            inputs: vec![TransactionInput {
+                outpoint: TransactionOutpoint {
+                    txid: coinbase_hash,
+                    index: 0,
+                },
+                signature_script: vec![],
+            }],
+            outputs: vec![TransactionOutput::Value {
+                value,
+                pubkey_script: vec![],
+            }],
+            locktime: 0,
+        };
+
+        let size = create_transaction(0).encoded_size()?;
+
+        let mut failure_context = create_context(
+            vec![coinbase.clone()],
+            vec![create_transaction(5000 - (size * 2 - 1))],
+        );
+        failure_context.minimum_fee_rate = 2;
 
         collect_valid_block_transactions(&mut failure_context).await?;
 
@@ -616,31 +1025,565 @@ mod tests {
         let err = &failure_context.failed_transactions.get(0).unwrap().1;
         assert_eq!(
             format!("{}", err.root_cause()),
-            "pubkey script failed: EQUALVERIFY"
+            "transaction fee rate below minimum"
         );
 
-        let mut context = create_subcontext(true)?;
+        let mut context = create_context(vec![coinbase], vec![create_transaction(5000 - size * 2)]);
+        context.minimum_fee_rate = 2;
 
         collect_valid_block_transactions(&mut context).await?;
 
         assert_eq!(context.included_transactions.len(), 1);
-        let failure_count = context.failed_transactions.len();
-        assert_eq!(failure_count, 0);
+        assert_eq!(context.failed_transactions.len(), 0);
+        assert_eq!(context.collected_fees, size * 2);
 
         Ok(())
     }
 
-    #[tokio::test]
-    async fn validates_fresh_object_id() -> anyhow::Result<()> {
-        let create_subcontext =
-            |include_computed_object_id: bool| -> anyhow::Result<TestingExecutionContext> {
-                let coinbase = Transaction::Version1 {
-                    inputs: vec![],
-                    outputs: vec![TransactionOutput::Value {
-                        value: 5,
-                        pubkey_script: vec![],
-                    }],
-                    locktime: 0,
+    #[test]
+    fn coinbase_cannot_exceed_block_reward_plus_fees() -> anyhow::Result<()> {
+        let create_coinbase = |value: u64| Transaction::Version1 {
+            inputs: vec![],
+            outputs: vec![TransactionOutput::Value {
+                value,
+                pubkey_script: vec![],
+            }],
+            locktime: 0,
+        };
+
+        validate_coinbase_transaction(&create_coinbase(8), 5, 3)?;
+        validate_coinbase_transaction(&create_coinbase(5), 5, 3)?;
+
+        let err = validate_coinbase_transaction(&create_coinbase(9), 5, 3).unwrap_err();
+        assert_eq!(
+            format!("{}", err.root_cause()),
+            "coinbase output value exceeds block reward plus fees"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn block_header_must_commit_to_transactions() -> anyhow::Result<()> {
+        let transactions = (0..3)
+            .map(|value| Transaction::Version1 {
+                inputs: vec![],
+                outputs: vec![TransactionOutput::Value {
+                    value,
+                    pubkey_script: vec![],
+                }],
+                locktime: 0,
+            })
+            .collect::<Vec<Transaction>>();
+
+        let transaction_hashes = transactions
+            .iter()
+            .map(|transaction| transaction.hash_eip191())
+            .collect::<anyhow::Result<Vec<[u8; 32]>>>()?;
+
+        let create_header = |merkle_root: [u8; 32]| BlockHeader::Version1 {
+            previous_block_header_hash: [0u8; 32],
+            merkle_root,
+            timestamp: 0,
+        };
+
+        validate_block_header(
+            &create_header(compute_merkle_root(&transaction_hashes)),
+            &transactions,
+        )?;
+
+        let err = validate_block_header(&create_header([0u8; 32]), &transactions).unwrap_err();
+        assert_eq!(
+            format!("{}", err.root_cause()),
+            "block header merkle root does not match transactions"
+        );
+
+        // reordering the transactions changes the root
+        let mut reordered_transactions = transactions.clone();
+        reordered_transactions.swap(0, 2);
+        assert!(validate_block_header(
+            &create_header(compute_merkle_root(&transaction_hashes)),
+            &reordered_transactions,
+        )
+        .is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rejects_oversized_transactions() -> anyhow::Result<()> {
+        let transaction = Transaction::Version1 {
+            inputs: vec![],
+            outputs: vec![TransactionOutput::Value {
+                value: 0,
+                pubkey_script: vec![TransactionOpCode::Push {
+                    data: vec![0u8; MAX_TRANSACTION_SIZE as usize],
+                }],
+            }],
+            locktime: 0,
+        };
+
+        let mut context = create_context(vec![], vec![transaction]);
+
+        collect_valid_block_transactions(&mut context).await?;
+
+        assert_eq!(context.included_transactions.len(), 0);
+        let failure_count = context.failed_transactions.len();
+        assert_eq!(failure_count, 1);
+        let err = &context.failed_transactions.get(0).unwrap().1;
+        assert_eq!(
+            format!("{}", err.root_cause()),
+            "transaction exceeds maximum size"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn enforces_transaction_locktime() -> anyhow::Result<()> {
+        let create_transaction = |locktime: u64| Transaction::Version1 {
+            inputs: vec![],
+            outputs: vec![],
+            locktime,
+        };
+
+        let cases = [
+            (10, 10, 0, false),
+            (10, 11, 0, true),
+            (
+                LOCKTIME_THRESHOLD + 100,
+                11,
+                LOCKTIME_THRESHOLD + 100,
+                false,
+            ),
+            (LOCKTIME_THRESHOLD + 100, 11, LOCKTIME_THRESHOLD + 101, true),
+        ];
+
+        for (locktime, block_height, block_timestamp, is_final) in cases {
+            let mut context = create_context(vec![], vec![create_transaction(locktime)]);
+            context.block_height = block_height;
+            context.block_timestamp = block_timestamp;
+
+            collect_valid_block_transactions(&mut context).await?;
+
+            if is_final {
+                assert_eq!(context.included_transactions.len(), 1);
+                assert_eq!(context.failed_transactions.len(), 0);
+            } else {
+                assert_eq!(context.included_transactions.len(), 0);
+                let err = &context.failed_transactions.get(0).unwrap().1;
+                assert_eq!(
+                    format!("{}", err.root_cause()),
+                    "transaction locktime not reached"
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn enforces_chain_ids() -> anyhow::Result<()> {
+        let version1 = Transaction::Version1 {
+            inputs: vec![],
+            outputs: vec![],
+            locktime: 0,
+        };
+        let version2 = |chain_id: u64| Transaction::Version2 {
+            chain_id,
+            inputs: vec![],
+            outputs: vec![],
+            locktime: 0,
+        };
+
+        let cases = [
+            (version1.clone(), None, None),
+            (version1.clone(), Some(2), None),
+            (
+                version1,
+                Some(1),
+                Some("transaction does not commit to a chain id"),
+            ),
+            (version2(7), None, None),
+            (version2(7), Some(1), None),
+            (
+                version2(8),
+                None,
+                Some("transaction is for a different chain"),
+            ),
+        ];
+
+        for (transaction, activation_height, expected_error) in cases {
+            let mut context = create_context(vec![], vec![transaction]);
+            context.chain_id = 7;
+            context.chain_id_activation_height = activation_height;
+
+            collect_valid_block_transactions(&mut context).await?;
+
+            match expected_error {
+                None => {
+                    assert_eq!(context.included_transactions.len(), 1);
+                    assert_eq!(context.failed_transactions.len(), 0);
+                }
+                Some(expected_error) => {
+                    assert_eq!(context.included_transactions.len(), 0);
+                    let err = &context.failed_transactions.get(0).unwrap().1;
+                    assert_eq!(format!("{}", err.root_cause()), expected_error);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn check_lock_time_verify() -> anyhow::Result<()> {
+        let coinbase = Transaction::Version1 {
+            inputs: vec![],
+            outputs: vec![TransactionOutput::Value {
+                value: 5,
+                pubkey_script: vec![TransactionOpCode::CheckLockTimeVerify { data: 20 }],
+            }],
+            locktime: 0,
+        };
+
+        let coinbase_hash = coinbase.hash_eip191()?;
+
+        let create_transaction = |locktime: u64| Transaction::Version1 {
+            inputs: vec![TransactionInput {
+                outpoint: TransactionOutpoint {
+                    txid: coinbase_hash,
+                    index: 0,
+                },
+                signature_script: vec![],
+            }],
+            outputs: vec![],
+            locktime,
+        };
+
+        for locktime in [0, 19, LOCKTIME_THRESHOLD + 20] {
+            let mut failure_context =
+                create_context(vec![coinbase.clone()], vec![create_transaction(locktime)]);
+            failure_context.block_height = 100;
+            failure_context.block_timestamp = LOCKTIME_THRESHOLD + 100;
+
+            collect_valid_block_transactions(&mut failure_context).await?;
+
+            assert_eq!(failure_context.included_transactions.len(), 0);
+            let err = &failure_context.failed_transactions.get(0).unwrap().1;
+            assert_eq!(
+                format!("{}", err.root_cause()),
+                "pubkey script failed: CHECKLOCKTIMEVERIFY"
+            );
+        }
+
+        let mut context = create_context(vec![coinbase], vec![create_transaction(20)]);
+        context.block_height = 100;
+
+        collect_valid_block_transactions(&mut context).await?;
+
+        assert_eq!(context.included_transactions.len(), 1);
+        assert_eq!(context.failed_transactions.len(), 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn check_sequence_verify() -> anyhow::Result<()> {
+        let coinbase = Transaction::Version1 {
+            inputs: vec![],
+            outputs: vec![TransactionOutput::Value {
+                value: 5,
+                pubkey_script: vec![TransactionOpCode::CheckSequenceVerify { data: 3 }],
+            }],
+            locktime: 0,
+        };
+
+        let coinbase_hash = coinbase.hash_eip191()?;
+
+        let transaction = Transaction::Version1 {
+            inputs: vec![TransactionInput {
+                outpoint: TransactionOutpoint {
+                    txid: coinbase_hash,
+                    index: 0,
+                },
+                signature_script: vec![],
+            }],
+            outputs: vec![],
+            locktime: 0,
+        };
+
+        let mut failure_context = create_context(vec![coinbase.clone()], vec![transaction.clone()]);
+        failure_context.transaction_heights.insert(coinbase_hash, 5);
+        failure_context.block_height = 7;
+
+        collect_valid_block_transactions(&mut failure_context).await?;
+
+        assert_eq!(failure_context.included_transactions.len(), 0);
+        let err = &failure_context.failed_transactions.get(0).unwrap().1;
+        assert_eq!(
+            format!("{}", err.root_cause()),
+            "pubkey script failed: CHECKSEQUENCEVERIFY"
+        );
+
+        let mut context = create_context(vec![coinbase], vec![transaction]);
+        context.transaction_heights.insert(coinbase_hash, 5);
+        context.block_height = 8;
+
+        collect_valid_block_transactions(&mut context).await?;
+
+        assert_eq!(context.included_transactions.len(), 1);
+        assert_eq!(context.failed_transactions.len(), 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn enforces_coinbase_maturity() -> anyhow::Result<()> {
+        let coinbase = Transaction::Version1 {
+            inputs: vec![],
+            outputs: vec![TransactionOutput::Value {
+                value: 5,
+                pubkey_script: vec![],
+            }],
+            locktime: 0,
+        };
+
+        let coinbase_hash = coinbase.hash_eip191()?;
+
+        let transaction = Transaction::Version1 {
+            inputs: vec![TransactionInput {
+                outpoint: TransactionOutpoint {
+                    txid: coinbase_hash,
+                    index: 0,
+                },
+                signature_script: vec![],
+            }],
+            outputs: vec![],
+            locktime: 0,
+        };
+
+        for (block_height, is_mature) in [(14, false), (15, true)] {
+            let mut context = create_context(vec![coinbase.clone()], vec![transaction.clone()]);
+            context.transaction_heights.insert(coinbase_hash, 5);
+            context.coinbase_transactions.push(coinbase_hash);
+            context.coinbase_maturity = 10;
+            context.block_height = block_height;
+
+            collect_valid_block_transactions(&mut context).await?;
+
+            if is_mature {
+                assert_eq!(context.included_transactions.len(), 1);
+                assert_eq!(context.failed_transactions.len(), 0);
+            } else {
+                assert_eq!(context.included_transactions.len(), 0);
+                let err = &context.failed_transactions.get(0).unwrap().1;
+                assert_eq!(
+                    format!("{}", err.root_cause()),
+                    "coinbase output spent before maturity"
+                );
+            }
+        }
+
+        // outputs of other transactions are not subject to
+        // coinbase maturity
+        let mut context = create_context(vec![coinbase], vec![transaction]);
+        context.transaction_heights.insert(coinbase_hash, 5);
+        context.coinbase_maturity = 10;
+        context.block_height = 6;
+
+        collect_valid_block_transactions(&mut context).await?;
+
+        assert_eq!(context.included_transactions.len(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn pubkey_script() -> anyhow::Result<()> {
+        let signer_secret = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let signer_address = Address::from_private_key(&signer_secret);
+
+        // TODO: https://linear.app/quible/issue/QUI-95/refactor-transaction-signing
+        let create_subcontext =
+            |include_signature: bool| -> anyhow::Result<TestingExecutionContext> {
+                let coinbase = Transaction::Version1 {
+                    inputs: vec![],
+                    outputs: vec![TransactionOutput::Value {
+                        value: 5,
+                        pubkey_script: vec![
+                            TransactionOpCode::Dup,
+                            TransactionOpCode::Push {
+                                data: signer_address.into_array().to_vec(),
+                            },
+                            TransactionOpCode::EqualVerify,
+                            TransactionOpCode::CheckEip191SigVerify,
+                        ],
+                    }],
+                    locktime: 0,
+                };
+
+                let coinbase_hash = coinbase.hash_eip191()?;
+
+                let transaction = &mut Transaction::Version1 {
+                    inputs: vec![TransactionInput {
+                        outpoint: TransactionOutpoint {
+                            txid: coinbase_hash,
+                            index: 0,
+                        },
+                        signature_script: vec![],
+                    }],
+                    outputs: vec![TransactionOutput::Value {
+                        value: 5,
+                        pubkey_script: vec![],
+                    }],
+                    locktime: 0,
+                }
+                .to_owned();
+
+                let signature = sign_message(
+                    B256::from_slice(&signer_secret.to_bytes()[..]),
+                    transaction.hash_eip191()?.into(),
+                )?
+                .to_vec();
+
+                if include_signature {
+                    for input in transaction.inputs_mut().iter_mut() {
+                        *input = TransactionInput {
+                            outpoint: input.clone().outpoint,
+                            signature_script: vec![
+                                TransactionOpCode::Push {
+                                    data: signature.clone(),
+                                },
+                                TransactionOpCode::Push {
+                                    data: signer_address.into_array().to_vec(),
+                                },
+                            ],
+                        }
+                    }
+                }
+
+                Ok(create_context(vec![coinbase], vec![transaction.clone()]))
+            };
+
+        let mut failure_context = create_subcontext(false)?;
+
+        collect_valid_block_transactions(&mut failure_context).await?;
+
+        assert_eq!(failure_context.included_transactions.len(), 0);
+        let failure_count = failure_context.failed_transactions.len();
+        assert_eq!(failure_count, 1);
+        let err = &failure_context.failed_transactions.get(0).unwrap().1;
+        assert_eq!(
+            format!("{}", err.root_cause()),
+            "pubkey script failed: DUP when stack is empty"
+        );
+
+        let mut context = create_subcontext(true)?;
+
+        collect_valid_block_transactions(&mut context).await?;
+
+        assert_eq!(context.included_transactions.len(), 1);
+        let failure_count = context.failed_transactions.len();
+        assert_eq!(failure_count, 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn check_multisig_verify() -> anyhow::Result<()> {
+        let signer_secrets = (0..3)
+            .map(|_| k256::ecdsa::SigningKey::random(&mut rand::thread_rng()))
+            .collect::<Vec<_>>();
+        let addresses = signer_secrets
+            .iter()
+            .flat_map(|secret| Address::from_private_key(secret).into_array())
+            .collect::<Vec<u8>>();
+
+        let coinbase = Transaction::Version1 {
+            inputs: vec![],
+            outputs: vec![TransactionOutput::Value {
+                value: 5,
+                pubkey_script: vec![
+                    TransactionOpCode::Push { data: addresses },
+                    TransactionOpCode::CheckMultiSigVerify { data: 2 },
+                ],
+            }],
+            locktime: 0,
+        };
+        let coinbase_hash = coinbase.hash_eip191()?;
+
+        // spends the 2-of-3 output with signatures by the
+        // participants at the given indices, in that order
+        let run = |signers: &[usize]| {
+            let mut transaction = Transaction::Version1 {
+                inputs: vec![TransactionInput {
+                    outpoint: TransactionOutpoint {
+                        txid: coinbase_hash,
+                        index: 0,
+                    },
+                    signature_script: vec![],
+                }],
+                outputs: vec![TransactionOutput::Value {
+                    value: 5,
+                    pubkey_script: vec![],
+                }],
+                locktime: 0,
+            };
+
+            let signing_hash = transaction.hash_eip191()?;
+            transaction.inputs_mut()[0].signature_script = signers
+                .iter()
+                .map(|signer| {
+                    Ok(TransactionOpCode::Push {
+                        data: sign_message(
+                            B256::from_slice(&signer_secrets[*signer].to_bytes()[..]),
+                            signing_hash.into(),
+                        )?
+                        .to_vec(),
+                    })
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+
+            anyhow::Ok(create_context(vec![coinbase.clone()], vec![transaction]))
+        };
+
+        let mut context = run(&[0, 2])?;
+        collect_valid_block_transactions(&mut context).await?;
+        assert_eq!(context.included_transactions.len(), 1);
+
+        // signatures out of order, by the same participant twice,
+        // or too few of them do not satisfy the script
+        for (signers, message) in [
+            (vec![2, 0], "pubkey script failed: CHECKMULTISIGVERIFY"),
+            (vec![1, 1], "pubkey script failed: CHECKMULTISIGVERIFY"),
+            (
+                vec![1],
+                "pubkey script failed: CHECKMULTISIGVERIFY when stack is empty",
+            ),
+        ] {
+            let mut context = run(&signers)?;
+            collect_valid_block_transactions(&mut context).await?;
+
+            assert_eq!(context.included_transactions.len(), 0);
+            let err = &context.failed_transactions.get(0).unwrap().1;
+            assert_eq!(format!("{}", err.root_cause()), message);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn validates_fresh_object_id() -> anyhow::Result<()> {
+        let create_subcontext =
+            |include_computed_object_id: bool| -> anyhow::Result<TestingExecutionContext> {
+                let coinbase = Transaction::Version1 {
+                    inputs: vec![],
+                    outputs: vec![TransactionOutput::Value {
+                        value: 5,
+                        pubkey_script: vec![],
+                    }],
+                    locktime: 0,
                 };
 
                 let coinbase_hash = coinbase.hash_eip191()?;
@@ -826,4 +1769,353 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn objects_are_continued_by_at_most_one_output() -> anyhow::Result<()> {
+        let create_subcontext = |output_count: usize| -> anyhow::Result<TestingExecutionContext> {
+            let object_id_raw = compute_object_id(vec![], 0)?;
+            let coinbase = Transaction::Version1 {
+                inputs: vec![],
+                outputs: vec![TransactionOutput::Object {
+                    object_id: ObjectIdentifier {
+                        raw: object_id_raw,
+                        mode: ObjectMode::Fresh,
+                    },
+                    data_script: vec![],
+                    pubkey_script: vec![],
+                }],
+                locktime: 0,
+            };
+
+            let coinbase_hash = coinbase.hash_eip191()?;
+
+            let inputs = vec![TransactionInput {
+                outpoint: TransactionOutpoint {
+                    txid: coinbase_hash,
+                    index: 0,
+                },
+                signature_script: vec![],
+            }];
+
+            let outputs = (0..output_count)
+                .map(|index| TransactionOutput::Object {
+                    object_id: ObjectIdentifier {
+                        raw: object_id_raw,
+                        mode: ObjectMode::Existing { permit_index: 0 },
+                    },
+                    data_script: vec![TransactionOpCode::Insert {
+                        data: vec![index as u8],
+                    }],
+                    pubkey_script: vec![],
+                })
+                .collect();
+
+            let transaction = Transaction::Version1 {
+                inputs,
+                outputs,
+                locktime: 0,
+            };
+
+            Ok(create_context(vec![coinbase], vec![transaction]))
+        };
+
+        let mut failure_context = create_subcontext(2)?;
+
+        collect_valid_block_transactions(&mut failure_context).await?;
+
+        assert_eq!(failure_context.included_transactions.len(), 0);
+        let failure_count = failure_context.failed_transactions.len();
+        assert_eq!(failure_count, 1);
+        let err = &failure_context.failed_transactions.get(0).unwrap().1;
+        assert_eq!(
+            format!("{}", err.root_cause()),
+            "object continued by more than one output"
+        );
+
+        // continuing the object once, or not at all (which
+        // destroys it), are both valid
+        for output_count in [1, 0] {
+            let mut context = create_subcontext(output_count)?;
+
+            collect_valid_block_transactions(&mut context).await?;
+
+            assert_eq!(context.included_transactions.len(), 1);
+            let failure_count = context.failed_transactions.len();
+            assert_eq!(failure_count, 0);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_data_scripts() -> anyhow::Result<()> {
+        let transaction = Transaction::Version1 {
+            inputs: vec![],
+            outputs: vec![TransactionOutput::Object {
+                object_id: ObjectIdentifier {
+                    raw: compute_object_id(vec![], 0)?,
+                    mode: ObjectMode::Fresh,
+                },
+                data_script: vec![
+                    TransactionOpCode::Insert { data: vec![1] },
+                    TransactionOpCode::Push { data: vec![2] },
+                ],
+                pubkey_script: vec![],
+            }],
+            locktime: 0,
+        };
+
+        let mut context = create_context(vec![], vec![transaction]);
+
+        collect_valid_block_transactions(&mut context).await?;
+
+        assert_eq!(context.included_transactions.len(), 0);
+        let failure_count = context.failed_transactions.len();
+        assert_eq!(failure_count, 1);
+        let err = &context.failed_transactions.get(0).unwrap().1;
+        assert_eq!(
+            format!("{}", err.root_cause()),
+            "data script failed: only data opcodes are allowed in data scripts"
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn enforces_claim_manager_permissions() -> anyhow::Result<()> {
+        let owner_secret = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let owner_address = Address::from_private_key(&owner_secret);
+        let manager_secret = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let manager_address = Address::from_private_key(&manager_secret);
+        let stranger_secret = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+
+        let owner_pubkey_script = vec![
+            TransactionOpCode::Dup,
+            TransactionOpCode::Push {
+                data: owner_address.into_array().to_vec(),
+            },
+            TransactionOpCode::EqualVerify,
+            TransactionOpCode::CheckEip191SigVerify,
+        ];
+
+        let object_id_raw = compute_object_id(vec![], 0)?;
+        let coinbase = Transaction::Version1 {
+            inputs: vec![],
+            outputs: vec![TransactionOutput::Object {
+                object_id: ObjectIdentifier {
+                    raw: object_id_raw,
+                    mode: ObjectMode::Fresh,
+                },
+                data_script: vec![],
+                pubkey_script: owner_pubkey_script.clone(),
+            }],
+            locktime: 0,
+        };
+        let coinbase_hash = coinbase.hash_eip191()?;
+
+        // spends the object signed by the given key, continuing
+        // it with the given scripts unless they are None
+        let run =
+            |signer_secret: &k256::ecdsa::SigningKey,
+             continuation: Option<(Vec<TransactionOpCode>, Vec<TransactionOpCode>)>| {
+                let signer_address = Address::from_private_key(signer_secret);
+                let mut transaction = Transaction::Version1 {
+                    inputs: vec![TransactionInput {
+                        outpoint: TransactionOutpoint {
+                            txid: coinbase_hash,
+                            index: 0,
+                        },
+                        signature_script: vec![],
+                    }],
+                    outputs: continuation
+                        .into_iter()
+                        .map(|(data_script, pubkey_script)| TransactionOutput::Object {
+                            object_id: ObjectIdentifier {
+                                raw: object_id_raw,
+                                mode: ObjectMode::Existing { permit_index: 0 },
+                            },
+                            data_script,
+                            pubkey_script,
+                        })
+                        .collect(),
+                    locktime: 0,
+                };
+
+                let signature = sign_message(
+                    B256::from_slice(&signer_secret.to_bytes()[..]),
+                    transaction.hash_eip191()?.into(),
+                )?
+                .to_vec();
+
+                transaction.inputs_mut()[0].signature_script = vec![
+                    TransactionOpCode::Push { data: signature },
+                    TransactionOpCode::Push {
+                        data: signer_address.into_array().to_vec(),
+                    },
+                ];
+
+                let mut context = create_context(vec![coinbase.clone()], vec![transaction]);
+                context
+                    .claim_managers
+                    .insert(object_id_raw, vec![manager_address.into_array()]);
+
+                anyhow::Ok(context)
+            };
+
+        // claim managers may insert and delete claims
+        let mut context = run(
+            &manager_secret,
+            Some((
+                vec![
+                    TransactionOpCode::Insert { data: vec![1] },
+                    TransactionOpCode::Delete { data: vec![2] },
+                ],
+                owner_pubkey_script.clone(),
+            )),
+        )?;
+        collect_valid_block_transactions(&mut context).await?;
+        assert_eq!(context.included_transactions.len(), 1);
+
+        // but not change the certificate time-to-live, transfer
+        // or destroy the object
+        for continuation in [
+            Some((
+                vec![TransactionOpCode::SetCertTTL { data: 60 }],
+                owner_pubkey_script.clone(),
+            )),
+            Some((vec![TransactionOpCode::Insert { data: vec![1] }], vec![])),
+            None,
+        ] {
+            let mut context = run(&manager_secret, continuation)?;
+            collect_valid_block_transactions(&mut context).await?;
+
+            assert_eq!(context.included_transactions.len(), 0);
+            let err = &context.failed_transactions.get(0).unwrap().1;
+            assert_eq!(
+                format!("{}", err.root_cause()),
+                "claim managers may only insert and delete claims"
+            );
+        }
+
+        // addresses that are not claim managers fail the pubkey
+        // script as before
+        let mut context = run(
+            &stranger_secret,
+            Some((
+                vec![TransactionOpCode::Insert { data: vec![1] }],
+                owner_pubkey_script.clone(),
+            )),
+        )?;
+        collect_valid_block_transactions(&mut context).await?;
+        assert_eq!(context.included_transactions.len(), 0);
+        let err = &context.failed_transactions.get(0).unwrap().1;
+        assert_eq!(
+            format!("{}", err.root_cause()),
+            "pubkey script failed: EQUALVERIFY"
+        );
+
+        // the owner is not restricted
+        let mut context = run(
+            &owner_secret,
+            Some((vec![TransactionOpCode::SetCertTTL { data: 60 }], vec![])),
+        )?;
+        collect_valid_block_transactions(&mut context).await?;
+        assert_eq!(context.included_transactions.len(), 1);
+
+        Ok(())
+    }
+
+    // compares block building with signatures recovered one at
+    // a time against recovering them up front in parallel. Run
+    // with `cargo test --release -- --ignored --nocapture`.
+    #[tokio::test(flavor = "multi_thread")]
+    #[ignore]
+    async fn benchmark_signature_verification() -> anyhow::Result<()> {
+        const TRANSACTION_COUNT: usize = 500;
+        const INPUTS_PER_TRANSACTION: usize = 4;
+
+        let signer_secret = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let signer_address = Address::from_private_key(&signer_secret);
+
+        let coinbase = Transaction::Version1 {
+            inputs: vec![],
+            outputs: (0..TRANSACTION_COUNT * INPUTS_PER_TRANSACTION)
+                .map(|_| TransactionOutput::Value {
+                    value: 1,
+                    pubkey_script: vec![
+                        TransactionOpCode::Dup,
+                        TransactionOpCode::Push {
+                            data: signer_address.into_array().to_vec(),
+                        },
+                        TransactionOpCode::EqualVerify,
+                        TransactionOpCode::CheckEip191SigVerify,
+                    ],
+                })
+                .collect(),
+            locktime: 0,
+        };
+
+        let coinbase_hash = coinbase.hash_eip191()?;
+
+        let mut transactions = vec![];
+        for transaction_index in 0..TRANSACTION_COUNT {
+            let transaction = &mut Transaction::Version1 {
+                inputs: (0..INPUTS_PER_TRANSACTION)
+                    .map(|input_index| TransactionInput {
+                        outpoint: TransactionOutpoint {
+                            txid: coinbase_hash,
+                            index: (transaction_index * INPUTS_PER_TRANSACTION + input_index)
+                                as u64,
+                        },
+                        signature_script: vec![],
+                    })
+                    .collect(),
+                outputs: vec![],
+                locktime: 0,
+            };
+
+            let signature = sign_message(
+                B256::from_slice(&signer_secret.to_bytes()[..]),
+                transaction.hash_eip191()?.into(),
+            )?
+            .to_vec();
+
+            for input in transaction.inputs_mut().iter_mut() {
+                input.signature_script = vec![
+                    TransactionOpCode::Push {
+                        data: signature.clone(),
+                    },
+                    TransactionOpCode::Push {
+                        data: signer_address.into_array().to_vec(),
+                    },
+                ];
+            }
+
+            transactions.push(transaction.clone());
+        }
+
+        for preview_pending_transactions in [false, true] {
+            let mut context = create_context(vec![coinbase.clone()], transactions.clone());
+            context.preview_pending_transactions = preview_pending_transactions;
+
+            let started_at = std::time::Instant::now();
+            collect_valid_block_transactions(&mut context).await?;
+            let elapsed = started_at.elapsed();
+
+            assert_eq!(context.included_transactions.len(), TRANSACTION_COUNT);
+
+            println!(
+                "{} signatures ({}): {:?}",
+                TRANSACTION_COUNT * INPUTS_PER_TRANSACTION,
+                if preview_pending_transactions {
+                    "recovered in parallel"
+                } else {
+                    "recovered sequentially"
+                },
+                elapsed
+            );
+        }
+
+        Ok(())
+    }
 }
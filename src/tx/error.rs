@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+
+// reasons a transaction can be rejected by the engine. Codes
+// are part of the RPC interface and must never be reused.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum TransactionValidationError {
+    TransactionTooLarge,
+    LocktimeNotReached,
+    DoubleSpend,
+    MissingOutpoint,
+    ScriptFailed { reason: String },
+    BadSignature { reason: String },
+    ValueOverflow,
+    OutputValueExceedsInput,
+    FeeRateBelowMinimum,
+    InvalidObjectId,
+    ObjectIdMismatch,
+    NonObjectPermit,
+    BadPermitIndex,
+    DuplicateObjectContinuation,
+    InvalidDataScript { reason: String },
+    ChainIdMismatch,
+    MissingChainId,
+    ImmatureCoinbaseSpend,
+    UnauthorizedObjectUpdate,
+
+    // mempool admission
+    MempoolConflict,
+    InsufficientReplacementFee,
+
+    // anything that is not a validation failure as such,
+    // for example a storage error while executing
+    Internal { reason: String },
+}
+
+impl TransactionValidationError {
+    pub fn code(&self) -> u32 {
+        match self {
+            TransactionValidationError::TransactionTooLarge => 1,
+            TransactionValidationError::LocktimeNotReached => 2,
+            TransactionValidationError::DoubleSpend => 3,
+            TransactionValidationError::MissingOutpoint => 4,
+            TransactionValidationError::ScriptFailed { .. } => 5,
+            TransactionValidationError::BadSignature { .. } => 6,
+            TransactionValidationError::ValueOverflow => 7,
+            TransactionValidationError::OutputValueExceedsInput => 8,
+            TransactionValidationError::FeeRateBelowMinimum => 9,
+            TransactionValidationError::InvalidObjectId => 10,
+            TransactionValidationError::ObjectIdMismatch => 11,
+            TransactionValidationError::NonObjectPermit => 12,
+            TransactionValidationError::BadPermitIndex => 13,
+            TransactionValidationError::MempoolConflict => 14,
+            TransactionValidationError::InsufficientReplacementFee => 15,
+            TransactionValidationError::DuplicateObjectContinuation => 16,
+            TransactionValidationError::InvalidDataScript { .. } => 17,
+            TransactionValidationError::ChainIdMismatch => 18,
+            TransactionValidationError::MissingChainId => 19,
+            TransactionValidationError::ImmatureCoinbaseSpend => 20,
+            TransactionValidationError::UnauthorizedObjectUpdate => 21,
+            TransactionValidationError::Internal { .. } => 999,
+        }
+    }
+
+    pub fn script_failed(reason: &str) -> Self {
+        TransactionValidationError::ScriptFailed {
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn bad_signature(reason: &str) -> Self {
+        TransactionValidationError::BadSignature {
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn invalid_data_script(reason: &str) -> Self {
+        TransactionValidationError::InvalidDataScript {
+            reason: reason.to_string(),
+        }
+    }
+
+    // recovers the typed error from an engine failure, treating
+    // anything untyped as an internal error
+    pub fn from_anyhow(error: &anyhow::Error) -> Self {
+        match error.downcast_ref::<TransactionValidationError>() {
+            Some(validation_error) => validation_error.clone(),
+            None => TransactionValidationError::Internal {
+                reason: format!("{}", error.root_cause()),
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for TransactionValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TransactionValidationError::TransactionTooLarge => {
+                write!(f, "transaction exceeds maximum size")
+            }
+            TransactionValidationError::LocktimeNotReached => {
+                write!(f, "transaction locktime not reached")
+            }
+            TransactionValidationError::DoubleSpend => write!(f, "cannot spend output twice"),
+            TransactionValidationError::MissingOutpoint => {
+                write!(f, "transaction hash not found!")
+            }
+            TransactionValidationError::ScriptFailed { reason } => write!(f, "{}", reason),
+            TransactionValidationError::BadSignature { reason } => write!(f, "{}", reason),
+            TransactionValidationError::ValueOverflow => write!(f, "transaction value overflows"),
+            TransactionValidationError::OutputValueExceedsInput => {
+                write!(f, "output value exceeds input value")
+            }
+            TransactionValidationError::FeeRateBelowMinimum => {
+                write!(f, "transaction fee rate below minimum")
+            }
+            TransactionValidationError::InvalidObjectId => write!(f, "object id invalid"),
+            TransactionValidationError::ObjectIdMismatch => {
+                write!(f, "object id does not match permitted object id")
+            }
+            TransactionValidationError::NonObjectPermit => {
+                write!(f, "non-object output cannot be used as a permit")
+            }
+            TransactionValidationError::BadPermitIndex => write!(f, "permit index out of bounds"),
+            TransactionValidationError::DuplicateObjectContinuation => {
+                write!(f, "object continued by more than one output")
+            }
+            TransactionValidationError::InvalidDataScript { reason } => write!(f, "{}", reason),
+            TransactionValidationError::ChainIdMismatch => {
+                write!(f, "transaction is for a different chain")
+            }
+            TransactionValidationError::MissingChainId => {
+                write!(f, "transaction does not commit to a chain id")
+            }
+            TransactionValidationError::ImmatureCoinbaseSpend => {
+                write!(f, "coinbase output spent before maturity")
+            }
+            TransactionValidationError::UnauthorizedObjectUpdate => {
+                write!(f, "claim managers may only insert and delete claims")
+            }
+            TransactionValidationError::MempoolConflict => {
+                write!(f, "transaction conflicts with a pending transaction")
+            }
+            TransactionValidationError::InsufficientReplacementFee => {
+                write!(f, "replacement transaction fee too low")
+            }
+            TransactionValidationError::Internal { reason } => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl std::error::Error for TransactionValidationError {}
@@ -0,0 +1,115 @@
+use alloy_primitives::keccak256;
+
+// merkle tree over the transaction hashes of a block. Pairs
+// are sorted before hashing, as in OpenZeppelin's MerkleProof,
+// so that a proof is just the list of siblings from the leaf
+// up and can be checked on EVM chains without the leaf index.
+// A node without a sibling moves up a level unchanged.
+
+fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let (first, second) = if left <= right {
+        (left, right)
+    } else {
+        (right, left)
+    };
+
+    let mut data = [0u8; 64];
+    data[..32].copy_from_slice(first);
+    data[32..].copy_from_slice(second);
+
+    keccak256(data).0
+}
+
+fn next_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => hash_pair(left, right),
+            [node] => *node,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+// root of the tree over the given leaves, or zero if there
+// are none
+pub fn compute_merkle_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    if leaves.is_empty() {
+        return [0u8; 32];
+    }
+
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+
+    level[0]
+}
+
+// siblings of the leaf at the given index, from the bottom
+// of the tree up, or None if the index is out of bounds
+pub fn compute_merkle_proof(leaves: &[[u8; 32]], index: usize) -> Option<Vec<[u8; 32]>> {
+    if index >= leaves.len() {
+        return None;
+    }
+
+    let mut proof = vec![];
+    let mut level = leaves.to_vec();
+    let mut index = index;
+    while level.len() > 1 {
+        if let Some(sibling) = level.get(index ^ 1) {
+            proof.push(*sibling);
+        }
+
+        level = next_level(&level);
+        index /= 2;
+    }
+
+    Some(proof)
+}
+
+pub fn verify_merkle_proof(leaf: &[u8; 32], proof: &[[u8; 32]], root: &[u8; 32]) -> bool {
+    let computed_root = proof
+        .iter()
+        .fold(*leaf, |node, sibling| hash_pair(&node, sibling));
+
+    &computed_root == root
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::keccak256;
+
+    use super::{compute_merkle_proof, compute_merkle_root, hash_pair, verify_merkle_proof};
+
+    #[test]
+    fn proves_inclusion_of_every_leaf() {
+        assert_eq!(compute_merkle_root(&[]), [0u8; 32]);
+
+        let leaf = keccak256([0u8]).0;
+        assert_eq!(compute_merkle_root(&[leaf]), leaf);
+        assert_eq!(compute_merkle_proof(&[leaf], 0), Some(vec![]));
+
+        for leaf_count in 2..=9u8 {
+            let leaves = (0..leaf_count)
+                .map(|leaf| keccak256([leaf]).0)
+                .collect::<Vec<_>>();
+            let root = compute_merkle_root(&leaves);
+
+            for (index, leaf) in leaves.iter().enumerate() {
+                let proof = compute_merkle_proof(&leaves, index).unwrap();
+                assert!(verify_merkle_proof(leaf, &proof, &root));
+                assert!(!verify_merkle_proof(&[0u8; 32], &proof, &root));
+            }
+
+            assert_eq!(compute_merkle_proof(&leaves, leaves.len()), None);
+        }
+
+        // the odd leaf out moves up unchanged
+        let leaves = (0..3u8).map(|leaf| keccak256([leaf]).0).collect::<Vec<_>>();
+        assert_eq!(
+            compute_merkle_root(&leaves),
+            hash_pair(&hash_pair(&leaves[0], &leaves[1]), &leaves[2])
+        );
+    }
+}
@@ -1,2 +1,8 @@
+pub mod eip712;
 pub mod engine;
+pub mod error;
+pub mod merkle;
+pub mod reward;
+pub mod script;
+pub mod sigcache;
 pub mod types;
@@ -0,0 +1,125 @@
+// value minted by the coinbase of each block, as a function of
+// the block height
+#[derive(Debug, Clone, PartialEq)]
+pub struct RewardSchedule {
+    // reward of the first block
+    pub initial_reward: u64,
+
+    // number of blocks after which the reward halves. Zero
+    // keeps the reward constant.
+    pub halving_interval: u64,
+
+    // upper bound on the total value ever minted by coinbase
+    // transactions, if any
+    pub supply_cap: Option<u64>,
+}
+
+impl Default for RewardSchedule {
+    fn default() -> Self {
+        RewardSchedule {
+            initial_reward: 5,
+            halving_interval: 0,
+            supply_cap: None,
+        }
+    }
+}
+
+impl RewardSchedule {
+    // reward at the given height, ignoring the supply cap
+    fn uncapped_block_reward(&self, height: u64) -> u64 {
+        if self.halving_interval == 0 {
+            return self.initial_reward;
+        }
+
+        self.initial_reward
+            .checked_shr(
+                (height / self.halving_interval)
+                    .try_into()
+                    .unwrap_or(u32::MAX),
+            )
+            .unwrap_or(0)
+    }
+
+    // total minted by the blocks below the given height,
+    // ignoring the supply cap
+    fn uncapped_supply_before(&self, height: u64) -> u64 {
+        if self.halving_interval == 0 {
+            return self.initial_reward.saturating_mul(height);
+        }
+
+        let mut supply = 0u64;
+        let mut era_start = 0u64;
+        while era_start < height {
+            let reward = self.uncapped_block_reward(era_start);
+            if reward == 0 {
+                break;
+            }
+
+            let era_end = era_start.saturating_add(self.halving_interval).min(height);
+            supply = supply.saturating_add(reward.saturating_mul(era_end - era_start));
+            era_start = era_end;
+        }
+
+        supply
+    }
+
+    // total value minted by the coinbases of all blocks below
+    // the given height
+    pub fn supply_before(&self, height: u64) -> u64 {
+        let supply = self.uncapped_supply_before(height);
+
+        match self.supply_cap {
+            Some(supply_cap) => supply.min(supply_cap),
+            None => supply,
+        }
+    }
+
+    // value the coinbase of the block at the given height may
+    // mint, on top of the fees it collects
+    pub fn block_reward(&self, height: u64) -> u64 {
+        let reward = self.uncapped_block_reward(height);
+
+        match self.supply_cap {
+            Some(supply_cap) => reward.min(supply_cap.saturating_sub(self.supply_before(height))),
+            None => reward,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RewardSchedule;
+
+    #[test]
+    fn halves_rewards_and_respects_supply_cap() {
+        let constant = RewardSchedule::default();
+        assert_eq!(constant.block_reward(0), 5);
+        assert_eq!(constant.block_reward(1_000_000), 5);
+        assert_eq!(constant.supply_before(10), 50);
+
+        let halving = RewardSchedule {
+            initial_reward: 100,
+            halving_interval: 10,
+            supply_cap: None,
+        };
+        assert_eq!(halving.block_reward(9), 100);
+        assert_eq!(halving.block_reward(10), 50);
+        assert_eq!(halving.block_reward(25), 25);
+        assert_eq!(halving.block_reward(10 * 64), 0);
+        assert_eq!(halving.supply_before(15), 100 * 10 + 50 * 5);
+        assert_eq!(
+            halving.supply_before(u64::MAX),
+            halving.supply_before(10 * 64)
+        );
+
+        let capped = RewardSchedule {
+            supply_cap: Some(1_120),
+            ..halving
+        };
+        assert_eq!(capped.block_reward(11), 50);
+        assert_eq!(capped.block_reward(12), 20);
+        assert_eq!(capped.block_reward(13), 0);
+        assert_eq!(capped.supply_before(13), 1_120);
+        assert_eq!(capped.supply_before(100), 1_120);
+    }
+}
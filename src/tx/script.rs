@@ -0,0 +1,1244 @@
+use alloy_primitives::{eip191_hash_message, keccak256};
+
+use crate::quible_ecdsa_utils::{
+    recover_signer_unchecked, split_sighash_flags, SIGHASH_ALL, SIGHASH_ANYONECANPAY,
+    SIGHASH_SINGLE,
+};
+
+use super::eip712::compute_eip712_signing_hash;
+use super::engine::LOCKTIME_THRESHOLD;
+use super::error::TransactionValidationError;
+use super::sigcache::SignatureCache;
+use super::types::{Hashable, Transaction, TransactionInput, TransactionOpCode};
+
+// upper bound on the encoded size of a single script
+pub const MAX_SCRIPT_SIZE: u64 = 10_000;
+
+// upper bound on the number of opcodes in a single script
+pub const MAX_SCRIPT_OPS: usize = 201;
+
+// upper bound on the number of items on the stack at any
+// point while a signature script and pubkey script run
+pub const MAX_STACK_DEPTH: usize = 1_000;
+
+// upper bound on the number of participants of a multisig
+pub const MAX_MULTISIG_ADDRESSES: usize = 20;
+
+// upper bound on the size of a claim inserted into or
+// deleted from an object
+pub const MAX_CLAIM_SIZE: usize = 1_024;
+
+// upper bound on the number of claims a single data
+// script may insert or delete
+pub const MAX_CLAIMS_PER_OUTPUT: usize = 100;
+
+// upper bound on the certificate TTL an object may set,
+// in seconds (one year)
+pub const MAX_CERT_TTL: u64 = 31_536_000;
+
+// checks the parts of a script that depend on the
+// transaction and chain rather than on the stack
+pub trait ScriptChecker {
+    // fails unless `signature` is a valid EIP-191 signature
+    // over the spending transaction by `address`
+    fn check_eip191_signature(&self, signature: &[u8], address: &[u8]) -> anyhow::Result<()>;
+
+    // fails unless `signature` is a valid EIP-712 signature
+    // over the typed data of the spending transaction by
+    // `address`
+    fn check_eip712_signature(&self, signature: &[u8], address: &[u8]) -> anyhow::Result<()>;
+
+    fn check_locktime(&self, locktime: u64) -> anyhow::Result<bool>;
+
+    fn check_sequence(&self, blocks: u64) -> anyhow::Result<bool>;
+}
+
+// checks scripts on behalf of one input of a transaction
+pub struct TransactionScriptChecker<'a> {
+    pub transaction: &'a Transaction,
+
+    // index of the input being spent, which signatures with
+    // sighash flags other than SIGHASH_ALL commit to
+    pub input_index: usize,
+
+    // result of compute_signing_hash for the transaction,
+    // computed once and shared by all of its inputs
+    pub signing_hash: [u8; 32],
+
+    // result of compute_eip712_signing_hash for the transaction,
+    // computed once if an output it spends checks a typed-data
+    // signature, and on demand otherwise
+    pub eip712_signing_hash: Option<[u8; 32]>,
+
+    // signers recovered ahead of time; signatures missing
+    // from the cache are recovered on demand
+    pub signature_cache: &'a SignatureCache,
+
+    // height of the block the transaction is executed in
+    pub block_height: u64,
+
+    // height of the block that created the output being
+    // spent, if known. Required by CHECKSEQUENCEVERIFY.
+    pub output_block_height: Option<u64>,
+}
+
+// hash that signatures commit to: the transaction with every
+// signature script emptied, since a signature cannot sign itself
+pub fn compute_signing_hash(transaction: &Transaction) -> anyhow::Result<[u8; 32]> {
+    let signable_transaction = &mut transaction.to_owned();
+    for input in signable_transaction.inputs_mut().iter_mut() {
+        *input = TransactionInput {
+            outpoint: input.clone().outpoint,
+            signature_script: vec![],
+        };
+    }
+
+    signable_transaction.hash_eip191()
+}
+
+// hash that a signature over the given input commits to.
+// SIGHASH_ALL signatures share the plain signing hash; other
+// flags drop the parts of the transaction that other parties
+// may still change and commit to the flags themselves
+pub fn compute_sighash(
+    transaction: &Transaction,
+    input_index: usize,
+    sighash_flags: u8,
+) -> anyhow::Result<[u8; 32]> {
+    if sighash_flags == SIGHASH_ALL {
+        return compute_signing_hash(transaction);
+    }
+
+    let inputs = transaction.inputs();
+    let outputs = transaction.outputs();
+
+    let signed_input = inputs
+        .get(input_index)
+        .ok_or(TransactionValidationError::bad_signature(
+            "pubkey script failed (input out of bounds)",
+        ))?;
+
+    let inputs = if sighash_flags & SIGHASH_ANYONECANPAY != 0 {
+        vec![signed_input.clone()]
+    } else {
+        inputs.clone()
+    };
+
+    let outputs = if sighash_flags & !SIGHASH_ANYONECANPAY == SIGHASH_SINGLE {
+        vec![outputs
+            .get(input_index)
+            .ok_or(TransactionValidationError::bad_signature(
+                "pubkey script failed (no output matches SIGHASH_SINGLE input)",
+            ))?
+            .clone()]
+    } else {
+        outputs.clone()
+    };
+
+    let mut signable_transaction = transaction.clone();
+    *signable_transaction.inputs_mut() = inputs
+        .into_iter()
+        .map(|input| TransactionInput {
+            outpoint: input.outpoint,
+            signature_script: vec![],
+        })
+        .collect();
+    *signable_transaction.outputs_mut() = outputs;
+
+    let mut message = postcard::to_stdvec(&signable_transaction)?;
+    message.push(sighash_flags);
+
+    Ok(eip191_hash_message(message).0)
+}
+
+impl TransactionScriptChecker<'_> {
+    fn check_signer(
+        &self,
+        signing_hash: &[u8; 32],
+        signature: &[u8; 65],
+        address: &[u8],
+    ) -> anyhow::Result<()> {
+        let signer = match self.signature_cache.get(signing_hash, signature) {
+            Some(signer) => signer,
+            None => recover_signer_unchecked(signature, signing_hash).map_err(|err| {
+                TransactionValidationError::bad_signature(&format!(
+                    "pubkey script failed ({})",
+                    err
+                ))
+            })?,
+        };
+        if address != signer.as_slice() {
+            return Err(TransactionValidationError::bad_signature(
+                "pubkey script failed (signer does not match pubkey)",
+            )
+            .into());
+        }
+
+        Ok(())
+    }
+}
+
+impl ScriptChecker for TransactionScriptChecker<'_> {
+    fn check_eip191_signature(&self, signature: &[u8], address: &[u8]) -> anyhow::Result<()> {
+        let (sig_slice, sighash_flags) =
+            split_sighash_flags(signature).ok_or(TransactionValidationError::bad_signature(
+                "pubkey script failed (invalid signature encoding)",
+            ))?;
+
+        let signing_hash = match sighash_flags {
+            SIGHASH_ALL => self.signing_hash,
+            _ => compute_sighash(self.transaction, self.input_index, sighash_flags)?,
+        };
+
+        self.check_signer(&signing_hash, &sig_slice, address)
+    }
+
+    fn check_eip712_signature(&self, signature: &[u8], address: &[u8]) -> anyhow::Result<()> {
+        let sig_slice: [u8; 65] = signature.try_into().map_err(|_| {
+            TransactionValidationError::bad_signature(
+                "pubkey script failed (signature is not 65 bytes)",
+            )
+        })?;
+
+        let signing_hash = match self.eip712_signing_hash {
+            Some(signing_hash) => signing_hash,
+            None => compute_eip712_signing_hash(self.transaction)?,
+        };
+
+        self.check_signer(&signing_hash, &sig_slice, address)
+    }
+
+    fn check_locktime(&self, locktime: u64) -> anyhow::Result<bool> {
+        let transaction_locktime = self.transaction.locktime();
+
+        // the transaction locktime itself is enforced against
+        // the chain, so it is enough to compare against it when
+        // both are measured in the same unit
+        let same_unit =
+            (locktime < LOCKTIME_THRESHOLD) == (transaction_locktime < LOCKTIME_THRESHOLD);
+
+        Ok(same_unit && transaction_locktime >= locktime)
+    }
+
+    fn check_sequence(&self, blocks: u64) -> anyhow::Result<bool> {
+        let output_block_height =
+            self.output_block_height
+                .ok_or(TransactionValidationError::script_failed(
+                    "block height of spent output is unknown",
+                ))?;
+
+        Ok(self.block_height >= output_block_height.saturating_add(blocks))
+    }
+}
+
+fn push_item(stack: &mut Vec<Vec<u8>>, item: Vec<u8>) -> anyhow::Result<()> {
+    if stack.len() >= MAX_STACK_DEPTH {
+        return Err(TransactionValidationError::script_failed(
+            "script failed: stack depth exceeded",
+        )
+        .into());
+    }
+
+    stack.push(item);
+
+    Ok(())
+}
+
+fn check_script_limits(script: &[TransactionOpCode]) -> anyhow::Result<()> {
+    if script.len() > MAX_SCRIPT_OPS {
+        return Err(
+            TransactionValidationError::script_failed("script failed: too many opcodes").into(),
+        );
+    }
+
+    let script_size: u64 = postcard::to_stdvec(script)?.len().try_into()?;
+    if script_size > MAX_SCRIPT_SIZE {
+        return Err(
+            TransactionValidationError::script_failed("script failed: script too large").into(),
+        );
+    }
+
+    Ok(())
+}
+
+// pops the addresses and signatures of a CHECKMULTISIGVERIFY
+// and fails unless every signature is by a distinct address,
+// in the same order as the addresses
+fn check_multisig<C: ScriptChecker>(
+    stack: &mut Vec<Vec<u8>>,
+    threshold: u64,
+    checker: &C,
+) -> anyhow::Result<()> {
+    let addresses = stack
+        .pop()
+        .ok_or(TransactionValidationError::script_failed(
+            "pubkey script failed: CHECKMULTISIGVERIFY when stack is empty",
+        ))?;
+
+    let address_count = addresses.len() / 20;
+    let threshold: usize = threshold.try_into()?;
+    if addresses.len() % 20 != 0
+        || address_count > MAX_MULTISIG_ADDRESSES
+        || threshold == 0
+        || threshold > address_count
+    {
+        return Err(TransactionValidationError::script_failed(
+            "pubkey script failed: CHECKMULTISIGVERIFY with invalid addresses or threshold",
+        )
+        .into());
+    }
+
+    if stack.len() < threshold {
+        return Err(TransactionValidationError::script_failed(
+            "pubkey script failed: CHECKMULTISIGVERIFY when stack is empty",
+        )
+        .into());
+    }
+    let signatures = stack.split_off(stack.len() - threshold);
+
+    // each address is tried at most once, so a signature that
+    // matches none of the remaining addresses fails the script
+    let mut addresses = addresses.chunks(20);
+    for signature in signatures {
+        if !addresses
+            .by_ref()
+            .any(|address| checker.check_eip191_signature(&signature, address).is_ok())
+        {
+            return Err(TransactionValidationError::script_failed(
+                "pubkey script failed: CHECKMULTISIGVERIFY",
+            )
+            .into());
+        }
+    }
+
+    Ok(())
+}
+
+// whether a stack item counts as true for IF
+fn is_true(item: &[u8]) -> bool {
+    item.iter().any(|byte| *byte != 0)
+}
+
+// runs a pubkey script against the given stack. Every opcode
+// either succeeds or fails the whole script; data script
+// opcodes are not executable and always fail, even in a
+// branch that is not taken.
+pub fn eval_script<C: ScriptChecker>(
+    script: &[TransactionOpCode],
+    stack: &mut Vec<Vec<u8>>,
+    checker: &C,
+) -> anyhow::Result<()> {
+    check_script_limits(script)?;
+
+    // whether each enclosing IF branch is taken
+    let mut branches: Vec<bool> = vec![];
+
+    for opcode in script {
+        let executing = branches.iter().all(|taken| *taken);
+
+        match opcode {
+            TransactionOpCode::If => {
+                let taken = if executing {
+                    let item = stack
+                        .pop()
+                        .ok_or(TransactionValidationError::script_failed(
+                            "pubkey script failed: IF when stack is empty",
+                        ))?;
+                    is_true(&item)
+                } else {
+                    false
+                };
+                branches.push(taken);
+            }
+
+            TransactionOpCode::Else => {
+                let taken =
+                    branches
+                        .last_mut()
+                        .ok_or(TransactionValidationError::script_failed(
+                            "pubkey script failed: ELSE without IF",
+                        ))?;
+                *taken = !*taken;
+            }
+
+            TransactionOpCode::EndIf => {
+                branches
+                    .pop()
+                    .ok_or(TransactionValidationError::script_failed(
+                        "pubkey script failed: ENDIF without IF",
+                    ))?;
+            }
+
+            TransactionOpCode::Insert { .. }
+            | TransactionOpCode::Delete { .. }
+            | TransactionOpCode::DeleteAll
+            | TransactionOpCode::SetCertTTL { .. }
+            | TransactionOpCode::GrantClaimManager { .. }
+            | TransactionOpCode::RevokeClaimManager { .. } => {
+                return Err(TransactionValidationError::script_failed(
+                    "pubkey script failed: data script opcode is not executable",
+                )
+                .into());
+            }
+
+            _ if !executing => {}
+
+            TransactionOpCode::Push { data } => {
+                push_item(stack, data.clone())?;
+            }
+
+            TransactionOpCode::Dup => {
+                let item =
+                    stack
+                        .last()
+                        .cloned()
+                        .ok_or(TransactionValidationError::script_failed(
+                            "pubkey script failed: DUP when stack is empty",
+                        ))?;
+                push_item(stack, item)?;
+            }
+
+            TransactionOpCode::EqualVerify => match (stack.pop(), stack.pop()) {
+                (Some(left), Some(right)) if left == right => {}
+                _ => {
+                    return Err(TransactionValidationError::script_failed(
+                        "pubkey script failed: EQUALVERIFY",
+                    )
+                    .into());
+                }
+            },
+
+            TransactionOpCode::CheckEip191SigVerify => match (stack.pop(), stack.pop()) {
+                (Some(pubkey), Some(sig)) => {
+                    checker.check_eip191_signature(&sig, &pubkey)?;
+                }
+
+                _ => {
+                    return Err(TransactionValidationError::script_failed(
+                        "pubkey script failed: CHECKSIGVERIFY when stack is empty",
+                    )
+                    .into());
+                }
+            },
+
+            TransactionOpCode::CheckEip712SigVerify => match (stack.pop(), stack.pop()) {
+                (Some(pubkey), Some(sig)) => {
+                    checker.check_eip712_signature(&sig, &pubkey)?;
+                }
+
+                _ => {
+                    return Err(TransactionValidationError::script_failed(
+                        "pubkey script failed: CHECKEIP712SIGVERIFY when stack is empty",
+                    )
+                    .into());
+                }
+            },
+
+            TransactionOpCode::CheckLockTimeVerify { data } => {
+                if !checker.check_locktime(*data)? {
+                    return Err(TransactionValidationError::script_failed(
+                        "pubkey script failed: CHECKLOCKTIMEVERIFY",
+                    )
+                    .into());
+                }
+            }
+
+            TransactionOpCode::CheckSequenceVerify { data } => {
+                if !checker.check_sequence(*data)? {
+                    return Err(TransactionValidationError::script_failed(
+                        "pubkey script failed: CHECKSEQUENCEVERIFY",
+                    )
+                    .into());
+                }
+            }
+
+            TransactionOpCode::CheckMultiSigVerify { data } => {
+                check_multisig(stack, *data, checker)?;
+            }
+
+            TransactionOpCode::CheckKeccakPreimageVerify => match (stack.pop(), stack.pop()) {
+                (Some(hash), Some(preimage)) if keccak256(&preimage).as_slice() == hash => {}
+                _ => {
+                    return Err(TransactionValidationError::script_failed(
+                        "pubkey script failed: CHECKKECCAKPREIMAGEVERIFY",
+                    )
+                    .into());
+                }
+            },
+        }
+    }
+
+    if !branches.is_empty() {
+        return Err(TransactionValidationError::script_failed(
+            "pubkey script failed: IF without ENDIF",
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+fn is_data_opcode(opcode: &TransactionOpCode) -> bool {
+    matches!(
+        opcode,
+        TransactionOpCode::Insert { .. }
+            | TransactionOpCode::Delete { .. }
+            | TransactionOpCode::DeleteAll
+            | TransactionOpCode::SetCertTTL { .. }
+            | TransactionOpCode::GrantClaimManager { .. }
+            | TransactionOpCode::RevokeClaimManager { .. }
+    )
+}
+
+// checks a pubkey script when the output carrying it is
+// created, so that outputs which can never be spent by
+// a well-formed script are rejected up front
+pub fn validate_pubkey_script(pubkey_script: &[TransactionOpCode]) -> anyhow::Result<()> {
+    check_script_limits(pubkey_script)?;
+
+    if pubkey_script.iter().any(is_data_opcode) {
+        return Err(TransactionValidationError::script_failed(
+            "pubkey script failed: data script opcode is not allowed in pubkey scripts",
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+// checks the data script of an object output. Data scripts
+// are not executed; they may only contain data opcodes, and
+// are bounded in the claims and TTL they set.
+pub fn validate_data_script(data_script: &[TransactionOpCode]) -> anyhow::Result<()> {
+    let mut claim_count = 0usize;
+
+    for opcode in data_script {
+        match opcode {
+            TransactionOpCode::Insert { data } | TransactionOpCode::Delete { data } => {
+                if data.len() > MAX_CLAIM_SIZE {
+                    return Err(TransactionValidationError::invalid_data_script(
+                        "data script failed: claim too large",
+                    )
+                    .into());
+                }
+
+                claim_count += 1;
+                if claim_count > MAX_CLAIMS_PER_OUTPUT {
+                    return Err(TransactionValidationError::invalid_data_script(
+                        "data script failed: too many claims",
+                    )
+                    .into());
+                }
+            }
+
+            TransactionOpCode::SetCertTTL { data } => {
+                if *data > MAX_CERT_TTL {
+                    return Err(TransactionValidationError::invalid_data_script(
+                        "data script failed: certificate TTL too large",
+                    )
+                    .into());
+                }
+            }
+
+            TransactionOpCode::GrantClaimManager { data }
+            | TransactionOpCode::RevokeClaimManager { data } => {
+                if data.len() != 20 {
+                    return Err(TransactionValidationError::invalid_data_script(
+                        "data script failed: claim manager is not an address",
+                    )
+                    .into());
+                }
+            }
+
+            TransactionOpCode::DeleteAll => {}
+
+            _ => {
+                return Err(TransactionValidationError::invalid_data_script(
+                    "data script failed: only data opcodes are allowed in data scripts",
+                )
+                .into());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// runs the signature script of an input followed by the
+// pubkey script of the output it spends. The spend is valid
+// only if both run without failing and leave the stack empty.
+pub fn verify_script<C: ScriptChecker>(
+    signature_script: &[TransactionOpCode],
+    pubkey_script: &[TransactionOpCode],
+    checker: &C,
+) -> anyhow::Result<()> {
+    if signature_script
+        .iter()
+        .any(|opcode| !matches!(opcode, TransactionOpCode::Push { .. }))
+    {
+        return Err(TransactionValidationError::script_failed(
+            "only pushes are allowed in signature scripts",
+        )
+        .into());
+    }
+
+    let mut stack: Vec<Vec<u8>> = vec![];
+
+    eval_script(signature_script, &mut stack, checker)?;
+    eval_script(pubkey_script, &mut stack, checker)?;
+
+    if !stack.is_empty() {
+        return Err(
+            TransactionValidationError::script_failed("script failed: stack not empty").into(),
+        );
+    }
+
+    Ok(())
+}
+
+// addresses able to sign for an output with a standard
+// pubkey script: the address of a P2A script, or the
+// participants of a multisig script that check_multisig
+// would accept
+pub fn extract_pubkey_script_owners(pubkey_script: &[TransactionOpCode]) -> Vec<String> {
+    match pubkey_script {
+        [TransactionOpCode::Dup, TransactionOpCode::Push { data: address_vec }, TransactionOpCode::EqualVerify, TransactionOpCode::CheckEip191SigVerify] =>
+        {
+            vec![hex::encode(address_vec.as_slice())]
+        }
+        [TransactionOpCode::Push { data: addresses }, TransactionOpCode::CheckMultiSigVerify { data: threshold }]
+            if addresses.len() % 20 == 0
+                && addresses.len() / 20 <= MAX_MULTISIG_ADDRESSES
+                && *threshold > 0
+                && *threshold <= (addresses.len() / 20) as u64 =>
+        {
+            addresses.chunks(20).map(hex::encode).collect()
+        }
+        _ => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{keccak256, Address, B256};
+
+    use crate::quible_ecdsa_utils::{
+        sign_message, sign_message_with_sighash_flags, SIGHASH_ANYONECANPAY, SIGHASH_SINGLE,
+    };
+    use crate::tx::eip712::compute_eip712_signing_hash;
+    use crate::tx::sigcache::SignatureCache;
+    use crate::tx::types::{
+        Transaction, TransactionInput, TransactionOpCode, TransactionOutpoint, TransactionOutput,
+    };
+
+    use super::{
+        compute_sighash, compute_signing_hash, eval_script, extract_pubkey_script_owners,
+        validate_data_script, validate_pubkey_script, verify_script, TransactionScriptChecker,
+        MAX_CERT_TTL, MAX_CLAIMS_PER_OUTPUT, MAX_CLAIM_SIZE, MAX_MULTISIG_ADDRESSES,
+        MAX_SCRIPT_OPS, MAX_STACK_DEPTH,
+    };
+
+    fn create_transaction() -> Transaction {
+        Transaction::Version1 {
+            inputs: vec![TransactionInput {
+                outpoint: TransactionOutpoint {
+                    txid: [0u8; 32],
+                    index: 0,
+                },
+                signature_script: vec![],
+            }],
+            outputs: vec![],
+            locktime: 0,
+        }
+    }
+
+    #[test]
+    fn verifies_pay_to_address_scripts() -> anyhow::Result<()> {
+        let signer_secret = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let signer_address = Address::from_private_key(&signer_secret);
+        let transaction = create_transaction();
+
+        let signature = sign_message(
+            B256::from_slice(&signer_secret.to_bytes()[..]),
+            compute_signing_hash(&transaction)?.into(),
+        )?
+        .to_vec();
+
+        let checker = TransactionScriptChecker {
+            transaction: &transaction,
+            input_index: 0,
+            signing_hash: compute_signing_hash(&transaction)?,
+            eip712_signing_hash: None,
+            signature_cache: &SignatureCache::default(),
+            block_height: 0,
+            output_block_height: None,
+        };
+
+        let pubkey_script = vec![
+            TransactionOpCode::Dup,
+            TransactionOpCode::Push {
+                data: signer_address.to_vec(),
+            },
+            TransactionOpCode::EqualVerify,
+            TransactionOpCode::CheckEip191SigVerify,
+        ];
+
+        verify_script(
+            &[
+                TransactionOpCode::Push { data: signature },
+                TransactionOpCode::Push {
+                    data: signer_address.to_vec(),
+                },
+            ],
+            &pubkey_script,
+            &checker,
+        )?;
+
+        let other_signer_secret = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let other_signature = sign_message(
+            B256::from_slice(&other_signer_secret.to_bytes()[..]),
+            compute_signing_hash(&transaction)?.into(),
+        )?
+        .to_vec();
+
+        let err = verify_script(
+            &[
+                TransactionOpCode::Push {
+                    data: other_signature,
+                },
+                TransactionOpCode::Push {
+                    data: signer_address.to_vec(),
+                },
+            ],
+            &pubkey_script,
+            &checker,
+        )
+        .unwrap_err();
+        assert_eq!(
+            format!("{}", err.root_cause()),
+            "pubkey script failed (signer does not match pubkey)"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn verifies_eip712_signatures() -> anyhow::Result<()> {
+        let signer_secret = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let signer_address = Address::from_private_key(&signer_secret);
+        let transaction = create_transaction();
+
+        let checker = TransactionScriptChecker {
+            transaction: &transaction,
+            input_index: 0,
+            signing_hash: compute_signing_hash(&transaction)?,
+            eip712_signing_hash: Some(compute_eip712_signing_hash(&transaction)?),
+            signature_cache: &SignatureCache::default(),
+            block_height: 0,
+            output_block_height: None,
+        };
+
+        let pubkey_script = vec![
+            TransactionOpCode::Dup,
+            TransactionOpCode::Push {
+                data: signer_address.to_vec(),
+            },
+            TransactionOpCode::EqualVerify,
+            TransactionOpCode::CheckEip712SigVerify,
+        ];
+        let signature_script = |signing_hash: [u8; 32]| -> anyhow::Result<_> {
+            Ok(vec![
+                TransactionOpCode::Push {
+                    data: sign_message(
+                        B256::from_slice(&signer_secret.to_bytes()[..]),
+                        signing_hash.into(),
+                    )?
+                    .to_vec(),
+                },
+                TransactionOpCode::Push {
+                    data: signer_address.to_vec(),
+                },
+            ])
+        };
+
+        verify_script(
+            &signature_script(compute_eip712_signing_hash(&transaction)?)?,
+            &pubkey_script,
+            &checker,
+        )?;
+
+        // EIP-191 signatures over the same transaction are not
+        // accepted in place of typed-data signatures
+        let err = verify_script(
+            &signature_script(compute_signing_hash(&transaction)?)?,
+            &pubkey_script,
+            &checker,
+        )
+        .unwrap_err();
+        assert_eq!(
+            format!("{}", err.root_cause()),
+            "pubkey script failed (signer does not match pubkey)"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn verifies_signatures_with_sighash_flags() -> anyhow::Result<()> {
+        let signer_secret = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let signer_address = Address::from_private_key(&signer_secret);
+        let pubkey_script = vec![
+            TransactionOpCode::Dup,
+            TransactionOpCode::Push {
+                data: signer_address.to_vec(),
+            },
+            TransactionOpCode::EqualVerify,
+            TransactionOpCode::CheckEip191SigVerify,
+        ];
+
+        let output = TransactionOutput::Value {
+            value: 10,
+            pubkey_script: vec![],
+        };
+        let mut transaction = Transaction::Version1 {
+            inputs: vec![TransactionInput {
+                outpoint: TransactionOutpoint {
+                    txid: [0u8; 32],
+                    index: 0,
+                },
+                signature_script: vec![],
+            }],
+            outputs: vec![output.clone()],
+            locktime: 0,
+        };
+
+        let sign = |transaction: &Transaction, sighash_flags| -> anyhow::Result<Vec<u8>> {
+            Ok(sign_message_with_sighash_flags(
+                B256::from_slice(&signer_secret.to_bytes()[..]),
+                compute_sighash(transaction, 0, sighash_flags)?.into(),
+                sighash_flags,
+            )?
+            .to_vec())
+        };
+        let flagged_signature = sign(&transaction, SIGHASH_SINGLE | SIGHASH_ANYONECANPAY)?;
+        let plain_signature = sign_message(
+            B256::from_slice(&signer_secret.to_bytes()[..]),
+            compute_signing_hash(&transaction)?.into(),
+        )?
+        .to_vec();
+
+        // a sponsor adds an input and an output of their own
+        // after the first signature was made
+        transaction.inputs_mut().push(TransactionInput {
+            outpoint: TransactionOutpoint {
+                txid: [1u8; 32],
+                index: 0,
+            },
+            signature_script: vec![],
+        });
+        transaction.outputs_mut().push(output.clone());
+
+        let checker = TransactionScriptChecker {
+            transaction: &transaction,
+            input_index: 0,
+            signing_hash: compute_signing_hash(&transaction)?,
+            eip712_signing_hash: None,
+            signature_cache: &SignatureCache::default(),
+            block_height: 0,
+            output_block_height: None,
+        };
+        let signature_script = |signature: Vec<u8>| {
+            vec![
+                TransactionOpCode::Push { data: signature },
+                TransactionOpCode::Push {
+                    data: signer_address.to_vec(),
+                },
+            ]
+        };
+
+        verify_script(
+            &signature_script(flagged_signature.clone()),
+            &pubkey_script,
+            &checker,
+        )?;
+
+        let err = verify_script(&signature_script(plain_signature), &pubkey_script, &checker)
+            .unwrap_err();
+        assert_eq!(
+            format!("{}", err.root_cause()),
+            "pubkey script failed (signer does not match pubkey)"
+        );
+
+        // the output matching the signed input is still covered
+        transaction.outputs_mut()[0] = TransactionOutput::Value {
+            value: 20,
+            pubkey_script: vec![],
+        };
+        let checker = TransactionScriptChecker {
+            transaction: &transaction,
+            input_index: 0,
+            signing_hash: compute_signing_hash(&transaction)?,
+            eip712_signing_hash: None,
+            signature_cache: &SignatureCache::default(),
+            block_height: 0,
+            output_block_height: None,
+        };
+        let err = verify_script(
+            &signature_script(flagged_signature.clone()),
+            &pubkey_script,
+            &checker,
+        )
+        .unwrap_err();
+        assert_eq!(
+            format!("{}", err.root_cause()),
+            "pubkey script failed (signer does not match pubkey)"
+        );
+
+        let mut unknown_flags = flagged_signature;
+        unknown_flags[65] = 0x02;
+        let err =
+            verify_script(&signature_script(unknown_flags), &pubkey_script, &checker).unwrap_err();
+        assert_eq!(
+            format!("{}", err.root_cause()),
+            "pubkey script failed (invalid signature encoding)"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn verifies_hashlocked_scripts() -> anyhow::Result<()> {
+        let recipient_secret = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let recipient_address = Address::from_private_key(&recipient_secret);
+        let refund_secret = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let refund_address = Address::from_private_key(&refund_secret);
+        let preimage = b"atomic swap secret".to_vec();
+
+        // pays the recipient if they reveal the preimage, or
+        // refunds once the transaction locktime reaches 100
+        let pubkey_script = vec![
+            TransactionOpCode::If,
+            TransactionOpCode::Push {
+                data: keccak256(&preimage).to_vec(),
+            },
+            TransactionOpCode::CheckKeccakPreimageVerify,
+            TransactionOpCode::Dup,
+            TransactionOpCode::Push {
+                data: recipient_address.to_vec(),
+            },
+            TransactionOpCode::EqualVerify,
+            TransactionOpCode::CheckEip191SigVerify,
+            TransactionOpCode::Else,
+            TransactionOpCode::CheckLockTimeVerify { data: 100 },
+            TransactionOpCode::Dup,
+            TransactionOpCode::Push {
+                data: refund_address.to_vec(),
+            },
+            TransactionOpCode::EqualVerify,
+            TransactionOpCode::CheckEip191SigVerify,
+            TransactionOpCode::EndIf,
+        ];
+
+        let spend = |transaction: &Transaction,
+                     signer_secret: &k256::ecdsa::SigningKey,
+                     branch: Vec<TransactionOpCode>| {
+            let signature = sign_message(
+                B256::from_slice(&signer_secret.to_bytes()[..]),
+                compute_signing_hash(transaction)?.into(),
+            )?
+            .to_vec();
+
+            let checker = TransactionScriptChecker {
+                transaction,
+                input_index: 0,
+                signing_hash: compute_signing_hash(transaction)?,
+                eip712_signing_hash: None,
+                signature_cache: &SignatureCache::default(),
+                block_height: 0,
+                output_block_height: None,
+            };
+
+            let mut signature_script = vec![
+                TransactionOpCode::Push { data: signature },
+                TransactionOpCode::Push {
+                    data: Address::from_private_key(signer_secret).to_vec(),
+                },
+            ];
+            signature_script.extend(branch);
+
+            verify_script(&signature_script, &pubkey_script, &checker)
+        };
+
+        let transaction = create_transaction();
+
+        spend(
+            &transaction,
+            &recipient_secret,
+            vec![
+                TransactionOpCode::Push {
+                    data: preimage.clone(),
+                },
+                TransactionOpCode::Push { data: vec![1] },
+            ],
+        )?;
+
+        let err = spend(
+            &transaction,
+            &recipient_secret,
+            vec![
+                TransactionOpCode::Push {
+                    data: b"wrong secret".to_vec(),
+                },
+                TransactionOpCode::Push { data: vec![1] },
+            ],
+        )
+        .unwrap_err();
+        assert_eq!(
+            format!("{}", err.root_cause()),
+            "pubkey script failed: CHECKKECCAKPREIMAGEVERIFY"
+        );
+
+        // the refund branch is only spendable after the locktime
+        let err = spend(
+            &transaction,
+            &refund_secret,
+            vec![TransactionOpCode::Push { data: vec![0] }],
+        )
+        .unwrap_err();
+        assert_eq!(
+            format!("{}", err.root_cause()),
+            "pubkey script failed: CHECKLOCKTIMEVERIFY"
+        );
+
+        let mut refund_transaction = create_transaction();
+        if let Transaction::Version1 { locktime, .. } = &mut refund_transaction {
+            *locktime = 100;
+        }
+        spend(
+            &refund_transaction,
+            &refund_secret,
+            vec![TransactionOpCode::Push { data: vec![0] }],
+        )?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_unbalanced_conditionals() {
+        let transaction = create_transaction();
+        let checker = TransactionScriptChecker {
+            transaction: &transaction,
+            input_index: 0,
+            signing_hash: compute_signing_hash(&transaction).unwrap(),
+            eip712_signing_hash: None,
+            signature_cache: &SignatureCache::default(),
+            block_height: 0,
+            output_block_height: None,
+        };
+
+        let err = eval_script(&[TransactionOpCode::If], &mut vec![vec![1]], &checker).unwrap_err();
+        assert_eq!(
+            format!("{}", err.root_cause()),
+            "pubkey script failed: IF without ENDIF"
+        );
+
+        let err = eval_script(&[TransactionOpCode::EndIf], &mut vec![], &checker).unwrap_err();
+        assert_eq!(
+            format!("{}", err.root_cause()),
+            "pubkey script failed: ENDIF without IF"
+        );
+
+        // opcodes in a branch that is not taken do not run
+        eval_script(
+            &[
+                TransactionOpCode::If,
+                TransactionOpCode::Dup,
+                TransactionOpCode::EndIf,
+            ],
+            &mut vec![vec![0, 0]],
+            &checker,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn dup_fails_on_empty_stack() {
+        let transaction = create_transaction();
+        let checker = TransactionScriptChecker {
+            transaction: &transaction,
+            input_index: 0,
+            signing_hash: compute_signing_hash(&transaction).unwrap(),
+            eip712_signing_hash: None,
+            signature_cache: &SignatureCache::default(),
+            block_height: 0,
+            output_block_height: None,
+        };
+
+        let err = eval_script(&[TransactionOpCode::Dup], &mut vec![], &checker).unwrap_err();
+        assert_eq!(
+            format!("{}", err.root_cause()),
+            "pubkey script failed: DUP when stack is empty"
+        );
+    }
+
+    #[test]
+    fn data_script_opcodes_are_not_executable() {
+        let transaction = create_transaction();
+        let checker = TransactionScriptChecker {
+            transaction: &transaction,
+            input_index: 0,
+            signing_hash: compute_signing_hash(&transaction).unwrap(),
+            eip712_signing_hash: None,
+            signature_cache: &SignatureCache::default(),
+            block_height: 0,
+            output_block_height: None,
+        };
+
+        let err = eval_script(&[TransactionOpCode::DeleteAll], &mut vec![], &checker).unwrap_err();
+        assert_eq!(
+            format!("{}", err.root_cause()),
+            "pubkey script failed: data script opcode is not executable"
+        );
+    }
+
+    #[test]
+    fn enforces_script_limits() {
+        let transaction = create_transaction();
+        let checker = TransactionScriptChecker {
+            transaction: &transaction,
+            input_index: 0,
+            signing_hash: compute_signing_hash(&transaction).unwrap(),
+            eip712_signing_hash: None,
+            signature_cache: &SignatureCache::default(),
+            block_height: 0,
+            output_block_height: None,
+        };
+
+        let too_many_opcodes = vec![TransactionOpCode::Push { data: vec![] }; MAX_SCRIPT_OPS + 1];
+        let err = eval_script(&too_many_opcodes, &mut vec![], &checker).unwrap_err();
+        assert_eq!(
+            format!("{}", err.root_cause()),
+            "script failed: too many opcodes"
+        );
+
+        let too_large = vec![TransactionOpCode::Push {
+            data: vec![0u8; 10_001],
+        }];
+        let err = eval_script(&too_large, &mut vec![], &checker).unwrap_err();
+        assert_eq!(
+            format!("{}", err.root_cause()),
+            "script failed: script too large"
+        );
+
+        let mut stack = vec![vec![]; MAX_STACK_DEPTH];
+        let err = eval_script(&[TransactionOpCode::Dup], &mut stack, &checker).unwrap_err();
+        assert_eq!(
+            format!("{}", err.root_cause()),
+            "script failed: stack depth exceeded"
+        );
+    }
+
+    #[test]
+    fn requires_clean_stack() {
+        let transaction = create_transaction();
+        let checker = TransactionScriptChecker {
+            transaction: &transaction,
+            input_index: 0,
+            signing_hash: compute_signing_hash(&transaction).unwrap(),
+            eip712_signing_hash: None,
+            signature_cache: &SignatureCache::default(),
+            block_height: 0,
+            output_block_height: None,
+        };
+
+        verify_script(&[], &[], &checker).unwrap();
+
+        let err =
+            verify_script(&[TransactionOpCode::Push { data: vec![1] }], &[], &checker).unwrap_err();
+        assert_eq!(
+            format!("{}", err.root_cause()),
+            "script failed: stack not empty"
+        );
+    }
+
+    #[test]
+    fn restricts_opcodes_by_script_kind() {
+        let err = validate_pubkey_script(&[TransactionOpCode::DeleteAll]).unwrap_err();
+        assert_eq!(
+            format!("{}", err.root_cause()),
+            "pubkey script failed: data script opcode is not allowed in pubkey scripts"
+        );
+
+        let err = validate_data_script(&[TransactionOpCode::Dup]).unwrap_err();
+        assert_eq!(
+            format!("{}", err.root_cause()),
+            "data script failed: only data opcodes are allowed in data scripts"
+        );
+
+        assert!(validate_data_script(&[
+            TransactionOpCode::DeleteAll,
+            TransactionOpCode::Insert { data: vec![1] },
+            TransactionOpCode::Delete { data: vec![1] },
+            TransactionOpCode::SetCertTTL { data: 3600 },
+        ])
+        .is_ok());
+    }
+
+    #[test]
+    fn enforces_data_script_limits() {
+        let err = validate_data_script(&[TransactionOpCode::Insert {
+            data: vec![0u8; MAX_CLAIM_SIZE + 1],
+        }])
+        .unwrap_err();
+        assert_eq!(
+            format!("{}", err.root_cause()),
+            "data script failed: claim too large"
+        );
+
+        let too_many_claims = (0..=MAX_CLAIMS_PER_OUTPUT)
+            .map(|index| TransactionOpCode::Insert {
+                data: index.to_le_bytes().to_vec(),
+            })
+            .collect::<Vec<_>>();
+        let err = validate_data_script(&too_many_claims).unwrap_err();
+        assert_eq!(
+            format!("{}", err.root_cause()),
+            "data script failed: too many claims"
+        );
+
+        let err = validate_data_script(&[TransactionOpCode::SetCertTTL {
+            data: MAX_CERT_TTL + 1,
+        }])
+        .unwrap_err();
+        assert_eq!(
+            format!("{}", err.root_cause()),
+            "data script failed: certificate TTL too large"
+        );
+    }
+
+    #[test]
+    fn extracts_owners_of_spendable_multisig_scripts() {
+        let multisig_script = |address_count: usize, threshold: u64| {
+            vec![
+                TransactionOpCode::Push {
+                    data: vec![7u8; 20 * address_count],
+                },
+                TransactionOpCode::CheckMultiSigVerify { data: threshold },
+            ]
+        };
+
+        assert_eq!(
+            extract_pubkey_script_owners(&multisig_script(MAX_MULTISIG_ADDRESSES, 1)).len(),
+            MAX_MULTISIG_ADDRESSES
+        );
+
+        // CHECKMULTISIGVERIFY fails on these, so nobody can
+        // spend the outputs they guard
+        assert!(
+            extract_pubkey_script_owners(&multisig_script(MAX_MULTISIG_ADDRESSES + 1, 1))
+                .is_empty()
+        );
+        assert!(extract_pubkey_script_owners(&multisig_script(2, 0)).is_empty());
+        assert!(extract_pubkey_script_owners(&multisig_script(2, 3)).is_empty());
+    }
+}
@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+
+use alloy_primitives::Address;
+
+use crate::quible_ecdsa_utils::{recover_signer_unchecked, split_sighash_flags, SIGHASH_ALL};
+
+use super::eip712::compute_eip712_signing_hash;
+use super::script::{compute_sighash, compute_signing_hash};
+use super::types::{Transaction, TransactionOpCode};
+
+// hash a signature commits to, and the signature itself
+type SignatureKey = ([u8; 32], [u8; 65]);
+
+// a pending transaction, with the pubkey script of each output
+// it spends where known. The pubkey script tells which hash
+// the signatures of the input commit to.
+#[derive(Debug, Clone)]
+pub struct PreviewedTransaction {
+    pub transaction: Transaction,
+    pub spent_pubkey_scripts: Vec<Option<Vec<TransactionOpCode>>>,
+}
+
+// signers recovered ahead of time from the signatures pushed
+// by pending transactions
+#[derive(Debug, Default)]
+pub struct SignatureCache {
+    signers: HashMap<SignatureKey, Address>,
+}
+
+impl SignatureCache {
+    pub fn get(&self, signing_hash: &[u8; 32], signature: &[u8; 65]) -> Option<Address> {
+        self.signers.get(&(*signing_hash, *signature)).copied()
+    }
+
+    // recovers the signers of every signature pushed by the
+    // given transactions, spreading the transactions across
+    // the blocking thread pool
+    pub async fn recover_batch(transactions: Vec<PreviewedTransaction>) -> anyhow::Result<Self> {
+        let parallelism = std::thread::available_parallelism().map_or(1, |count| count.get());
+        let chunk_size = transactions.len().div_ceil(parallelism).max(1);
+
+        let tasks = transactions
+            .chunks(chunk_size)
+            .map(|chunk| {
+                let chunk = chunk.to_vec();
+                tokio::task::spawn_blocking(move || recover_signers(&chunk))
+            })
+            .collect::<Vec<_>>();
+
+        let mut signers = HashMap::new();
+        for task in tasks {
+            signers.extend(task.await?);
+        }
+
+        Ok(SignatureCache { signers })
+    }
+}
+
+fn recover_signers(transactions: &[PreviewedTransaction]) -> Vec<(SignatureKey, Address)> {
+    let mut signers = vec![];
+
+    for PreviewedTransaction {
+        transaction,
+        spent_pubkey_scripts,
+    } in transactions
+    {
+        let signatures = transaction
+            .inputs()
+            .iter()
+            .enumerate()
+            .flat_map(|(input_index, input)| {
+                input
+                    .signature_script
+                    .iter()
+                    .map(move |opcode| (input_index, opcode))
+            })
+            .filter_map(|(input_index, opcode)| match opcode {
+                TransactionOpCode::Push { data } => split_sighash_flags(data)
+                    .map(|(signature, sighash_flags)| (input_index, signature, sighash_flags)),
+                _ => None,
+            })
+            .collect::<Vec<(usize, [u8; 65], u8)>>();
+
+        if signatures.is_empty() {
+            continue;
+        }
+
+        // transactions and signatures that cannot be processed
+        // here are left out, and fail with a proper error when
+        // their scripts run
+        let Ok(signing_hash) = compute_signing_hash(transaction) else {
+            continue;
+        };
+
+        // inputs spending outputs that check typed-data signatures
+        // are signed over the EIP-712 hash instead
+        let checks_eip712_signature = |input_index: usize| {
+            spent_pubkey_scripts
+                .get(input_index)
+                .and_then(Option::as_ref)
+                .is_some_and(|pubkey_script| {
+                    pubkey_script
+                        .iter()
+                        .any(|opcode| matches!(opcode, TransactionOpCode::CheckEip712SigVerify))
+                })
+        };
+
+        let eip712_signing_hash = (0..transaction.inputs().len())
+            .any(checks_eip712_signature)
+            .then(|| compute_eip712_signing_hash(transaction).ok())
+            .flatten();
+
+        for (input_index, signature, sighash_flags) in signatures {
+            let signing_hash = if checks_eip712_signature(input_index) {
+                match eip712_signing_hash {
+                    Some(signing_hash) if sighash_flags == SIGHASH_ALL => signing_hash,
+                    _ => continue,
+                }
+            } else {
+                match sighash_flags {
+                    SIGHASH_ALL => signing_hash,
+                    _ => match compute_sighash(transaction, input_index, sighash_flags) {
+                        Ok(signing_hash) => signing_hash,
+                        Err(_) => continue,
+                    },
+                }
+            };
+
+            if let Ok(signer) = recover_signer_unchecked(&signature, &signing_hash) {
+                signers.push(((signing_hash, signature), signer));
+            }
+        }
+    }
+
+    signers
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{Address, B256};
+
+    use crate::quible_ecdsa_utils::sign_message;
+    use crate::tx::eip712::compute_eip712_signing_hash;
+    use crate::tx::script::compute_signing_hash;
+    use crate::tx::types::{Transaction, TransactionInput, TransactionOpCode, TransactionOutpoint};
+
+    use super::{PreviewedTransaction, SignatureCache};
+
+    #[tokio::test]
+    async fn recovers_typed_data_signatures_against_the_eip712_hash() -> anyhow::Result<()> {
+        let signer_secret = k256::ecdsa::SigningKey::random(&mut rand::thread_rng());
+        let signer_address = Address::from_private_key(&signer_secret);
+
+        let mut transaction = Transaction::Version1 {
+            inputs: (0..2)
+                .map(|index| TransactionInput {
+                    outpoint: TransactionOutpoint {
+                        txid: [0u8; 32],
+                        index,
+                    },
+                    signature_script: vec![],
+                })
+                .collect(),
+            outputs: vec![],
+            locktime: 0,
+        };
+
+        let signing_hash = compute_signing_hash(&transaction)?;
+        let eip712_signing_hash = compute_eip712_signing_hash(&transaction)?;
+
+        let sign = |signing_hash: [u8; 32]| -> anyhow::Result<[u8; 65]> {
+            let signature = sign_message(
+                B256::from_slice(&signer_secret.to_bytes()[..]),
+                signing_hash.into(),
+            )?;
+
+            Ok(signature.to_vec().as_slice().try_into()?)
+        };
+        let signature = sign(signing_hash)?;
+        let eip712_signature = sign(eip712_signing_hash)?;
+
+        for (input, signature) in transaction
+            .inputs_mut()
+            .iter_mut()
+            .zip([signature, eip712_signature])
+        {
+            input.signature_script = vec![
+                TransactionOpCode::Push {
+                    data: signature.to_vec(),
+                },
+                TransactionOpCode::Push {
+                    data: signer_address.to_vec(),
+                },
+            ];
+        }
+
+        let pubkey_script = |check_signature: TransactionOpCode| {
+            vec![
+                TransactionOpCode::Dup,
+                TransactionOpCode::Push {
+                    data: signer_address.to_vec(),
+                },
+                TransactionOpCode::EqualVerify,
+                check_signature,
+            ]
+        };
+
+        let signature_cache = SignatureCache::recover_batch(vec![PreviewedTransaction {
+            transaction,
+            spent_pubkey_scripts: vec![
+                Some(pubkey_script(TransactionOpCode::CheckEip191SigVerify)),
+                Some(pubkey_script(TransactionOpCode::CheckEip712SigVerify)),
+            ],
+        }])
+        .await?;
+
+        assert_eq!(
+            signature_cache.get(&signing_hash, &signature),
+            Some(signer_address)
+        );
+        assert_eq!(
+            signature_cache.get(&eip712_signing_hash, &eip712_signature),
+            Some(signer_address)
+        );
+        assert_eq!(signature_cache.get(&signing_hash, &eip712_signature), None);
+
+        Ok(())
+    }
+}
@@ -14,18 +14,56 @@ pub enum BlockHeader {
         previous_block_header_hash: [u8; 32],
         merkle_root: [u8; 32],
 
+        #[serde(with = "postcard::fixint::le")]
+        timestamp: u64,
+    },
+    Version2 {
+        previous_block_header_hash: [u8; 32],
+        merkle_root: [u8; 32],
+
+        // root of the state tree over objects and claims after
+        // applying the block (see state::tree)
+        state_root: [u8; 32],
+
         #[serde(with = "postcard::fixint::le")]
         timestamp: u64,
     },
 }
 
+impl BlockHeader {
+    // root of the merkle tree over the hashes of the block's
+    // transactions, coinbase first (see tx::merkle)
+    pub fn merkle_root(&self) -> [u8; 32] {
+        match self {
+            BlockHeader::Version1 { merkle_root, .. }
+            | BlockHeader::Version2 { merkle_root, .. } => *merkle_root,
+        }
+    }
+
+    // None for Version1 headers, which do not commit to state
+    pub fn state_root(&self) -> Option<[u8; 32]> {
+        match self {
+            BlockHeader::Version1 { .. } => None,
+            BlockHeader::Version2 { state_root, .. } => Some(*state_root),
+        }
+    }
+
+    pub fn timestamp(&self) -> u64 {
+        match self {
+            BlockHeader::Version1 { timestamp, .. } | BlockHeader::Version2 { timestamp, .. } => {
+                *timestamp
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Block {
     pub header: BlockHeader,
     pub transactions: Vec<Transaction>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TransactionOpCode {
     // general
     Push { data: Vec<u8> },
@@ -42,6 +80,43 @@ pub enum TransactionOpCode {
     Delete { data: Vec<u8> },
     DeleteAll,
     SetCertTTL { data: u64 },
+
+    // timelocks
+    // CHECKLOCKTIMEVERIFY: the spending transaction's locktime must be
+    // at least <data>, measured in the same unit (height or timestamp)
+    // CHECKSEQUENCEVERIFY: the output being spent must have been
+    // confirmed at least <data> blocks before the spending block
+    CheckLockTimeVerify { data: u64 },
+    CheckSequenceVerify { data: u64 },
+
+    // like CheckEip191SigVerify, but the signature is over the
+    // EIP-712 typed data of the transaction (see tx::eip712)
+    CheckEip712SigVerify,
+
+    // unspendable script opcodes delegating claim management:
+    // the address <data> may insert and delete claims by
+    // signing the permit input, but may not transfer the
+    // object or change anything else about it
+    GrantClaimManager { data: Vec<u8> },
+    RevokeClaimManager { data: Vec<u8> },
+
+    // hashlocks: pops a hash and a preimage, and fails unless
+    // the Keccak256 hash of the preimage equals the hash
+    CheckKeccakPreimageVerify,
+
+    // conditionals: IF pops an item and runs the opcodes up to
+    // the matching ELSE or ENDIF only if the item is true (has
+    // a non-zero byte), and those after ELSE otherwise
+    If,
+    Else,
+    EndIf,
+
+    // M-of-N multisig: pops the concatenated addresses of the N
+    // participants and then <data> signatures, which must be by
+    // distinct participants in the same order as the addresses
+    // multisig pubkey script: OP_PUSH(<address_1>...<address_N>) OP_CHECKMULTISIGVERIFY(<M>)
+    // multisig sig script: OP_PUSH(<sig_1>) ... OP_PUSH(<sig_M>)
+    CheckMultiSigVerify { data: u64 },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -97,6 +172,71 @@ pub enum Transaction {
         #[serde(with = "postcard::fixint::le")]
         locktime: u64,
     },
+
+    // commits to the chain it is meant for, so that its
+    // signatures cannot be replayed on another network
+    Version2 {
+        #[serde(with = "postcard::fixint::le")]
+        chain_id: u64,
+
+        inputs: Vec<TransactionInput>,
+        outputs: Vec<TransactionOutput>,
+
+        #[serde(with = "postcard::fixint::le")]
+        locktime: u64,
+    },
+}
+
+impl Transaction {
+    pub fn inputs(&self) -> &Vec<TransactionInput> {
+        match self {
+            Transaction::Version1 { inputs, .. } => inputs,
+            Transaction::Version2 { inputs, .. } => inputs,
+        }
+    }
+
+    pub fn inputs_mut(&mut self) -> &mut Vec<TransactionInput> {
+        match self {
+            Transaction::Version1 { inputs, .. } => inputs,
+            Transaction::Version2 { inputs, .. } => inputs,
+        }
+    }
+
+    pub fn outputs(&self) -> &Vec<TransactionOutput> {
+        match self {
+            Transaction::Version1 { outputs, .. } => outputs,
+            Transaction::Version2 { outputs, .. } => outputs,
+        }
+    }
+
+    pub fn outputs_mut(&mut self) -> &mut Vec<TransactionOutput> {
+        match self {
+            Transaction::Version1 { outputs, .. } => outputs,
+            Transaction::Version2 { outputs, .. } => outputs,
+        }
+    }
+
+    pub fn locktime(&self) -> u64 {
+        match self {
+            Transaction::Version1 { locktime, .. } => *locktime,
+            Transaction::Version2 { locktime, .. } => *locktime,
+        }
+    }
+
+    // chain the transaction commits to. Version1 transactions
+    // predate chain ids and commit to none.
+    pub fn chain_id(&self) -> Option<u64> {
+        match self {
+            Transaction::Version1 { .. } => None,
+            Transaction::Version2 { chain_id, .. } => Some(*chain_id),
+        }
+    }
+
+    // length of the postcard encoding of the transaction,
+    // which is what fee rates and block space are measured in
+    pub fn encoded_size(&self) -> anyhow::Result<u64> {
+        Ok(postcard::to_stdvec(self)?.len().try_into()?)
+    }
 }
 
 impl Hashable for Transaction {
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, DisplayFromStr};
 
+use crate::tx::error::TransactionValidationError;
 use crate::tx::types::{BlockHeader, Transaction, TransactionOutpoint};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -241,6 +242,22 @@ pub struct BlockHeightPayload {
     pub height: u64,
 }
 
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockRewardPayload {
+    #[serde_as(as = "DisplayFromStr")]
+    pub height: u64,
+
+    // value the coinbase at this height may mint, on top of
+    // the fees it collects
+    #[serde_as(as = "DisplayFromStr")]
+    pub reward: u64,
+
+    // total value minted by the blocks below this height
+    #[serde_as(as = "DisplayFromStr")]
+    pub supply_before: u64,
+}
+
 #[serde_as]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlockDetailsPayload {
@@ -251,3 +268,53 @@ pub struct BlockDetailsPayload {
     #[serde_as(as = "DisplayFromStr")]
     pub transaction_count: u64,
 }
+
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionInclusionProofPayload {
+    pub transaction_hash: [u8; 32],
+    pub block_hash: [u8; 32],
+    #[serde_as(as = "DisplayFromStr")]
+    pub block_height: u64,
+    pub merkle_root: [u8; 32],
+
+    // siblings from the transaction hash up to the merkle
+    // root (see tx::merkle)
+    pub proof: Vec<[u8; 32]>,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionRejectionPayload {
+    pub transaction_hash: [u8; 32],
+    pub code: u32,
+    pub message: String,
+    pub error: TransactionValidationError,
+    #[serde_as(as = "DisplayFromStr")]
+    pub block_height: u64,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ObjectChangeEntry {
+    pub object_id: [u8; 32],
+    pub created: bool,
+    pub deleted_all_claims: bool,
+    pub inserted_claims: Vec<Vec<u8>>,
+    pub deleted_claims: Vec<Vec<u8>>,
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    pub cert_ttl: Option<u64>,
+    pub granted_claim_managers: Vec<Vec<u8>>,
+    pub revoked_claim_managers: Vec<Vec<u8>>,
+}
+
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionSimulationPayload {
+    pub transaction_hash: [u8; 32],
+    pub success: bool,
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    pub fee: Option<u64>,
+    pub object_changes: Vec<ObjectChangeEntry>,
+    pub error: Option<TransactionValidationError>,
+}